@@ -0,0 +1,164 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use lazy_static::lazy_static;
+use regex::Regex;
+use rustc_hash::FxHashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::mem;
+use std::path::PathBuf;
+use ucfirst::ucfirst;
+use TaxaGO::parsers::obo_parser::*;
+
+// About the number of terms in the full GO release.
+const TERM_COUNT: u32 = 48_000;
+
+lazy_static! {
+    static ref RELATIONSHIP_REGEX: Regex =
+        Regex::new(r"relationship:\s+(\w+(?:_\w+)*)\s+GO:(\d+)(?:\s+!.*)?").unwrap();
+}
+
+lazy_static! {
+    static ref INTERSECTION_REGEX: Regex =
+        Regex::new(r"intersection_of:\s+(\w+(?:_\w+)*)\s+GO:(\d+)(?:\s+!.*)?").unwrap();
+}
+
+// The line-by-line parser that `parse_obo_file` replaced, kept as the baseline of the benchmark.
+fn parse_obo_file_line_by_line(obo_file_path: &PathBuf) -> OboMap {
+    let mut obo_terms: OboMap = FxHashMap::with_capacity_and_hasher(
+        41_000,
+        rustc_hash::FxBuildHasher
+    );
+
+    let obo = File::open(obo_file_path).unwrap();
+    let reader = BufReader::with_capacity(3000 * 1024, obo);
+
+    let mut new_term = false;
+    let mut current_term = OboTerm::new();
+    let mut current_id: u32 = 0;
+
+    for line in reader.lines() {
+        let line = line.unwrap();
+
+        if line == "[Term]" {
+            new_term = true;
+            current_term = OboTerm::new();
+            current_id = 0;
+        } else if new_term {
+            match line.trim() {
+                line if line.starts_with("id: ") => {
+                    current_id = line.split("GO:").nth(1).unwrap().parse().unwrap();
+                },
+                line if line.starts_with("name: ") => {
+                    current_term.name = ucfirst(line.split(": ").nth(1).unwrap());
+                },
+                line if line.starts_with("namespace: ") => {
+                    current_term.namespace = match line.split(": ").nth(1).unwrap() {
+                        "biological_process" => NameSpace::BiologicalProcess,
+                        "molecular_function" => NameSpace::MolecularFunction,
+                        "cellular_component" => NameSpace::CellularComponent,
+                        _ => panic!("Invalid namespace"),
+                    };
+                },
+                line if line.starts_with("def: ") => {
+                    current_term.definition = line.split("\"").nth(1).unwrap().to_string();
+                },
+                line if line.starts_with("is_a: ") => {
+                    current_term.relationships.insert(parse_is_a(line).unwrap(), Relationship::IsA);
+                },
+                line if line.starts_with("is_obsolete: ") => {
+                    current_term.is_obsolete = line.split(": ").nth(1).unwrap() == "true";
+                },
+                line if line.starts_with("relationship: ") => {
+                    if let Some((id, relationship)) = parse_relationship(line, &RELATIONSHIP_REGEX) {
+                        current_term.relationships.insert(id, relationship);
+                    }
+                },
+                line if line.starts_with("intersection_of: ") && !line.contains("intersection_of: GO:") => {
+                    if let Some((id, relationship)) = parse_relationship(line, &INTERSECTION_REGEX) {
+                        current_term.relationships.insert(id, relationship);
+                    }
+                },
+                line if line.starts_with("disjoint_from: ") => {
+                    if let Some(disjoint_id) = parse_is_a(line) {
+                        current_term.disjoint_from.push(disjoint_id);
+                    }
+                },
+                line if line.starts_with("replaced_by: ") => {
+                    if let Some(replacement_id) = parse_is_a(line) {
+                        current_term.replaced_by.push(replacement_id);
+                    }
+                },
+                "" => {
+                    new_term = false;
+                    if current_id != 0 && !current_term.is_obsolete {
+                        obo_terms.insert(current_id, mem::take(&mut current_term));
+                    }
+                },
+                _ => (),
+            }
+        }
+    }
+
+    if new_term && current_id != 0 && !current_term.is_obsolete {
+        obo_terms.insert(current_id, mem::take(&mut current_term));
+    }
+
+    obo_terms
+}
+
+// Writes an OBO file with the size and stanza layout of the GO release, including a few obsolete terms.
+fn write_synthetic_obo(obo_path: &PathBuf) {
+    let namespaces = ["biological_process", "molecular_function", "cellular_component"];
+    let mut writer = BufWriter::new(File::create(obo_path).unwrap());
+
+    writeln!(writer, "format-version: 1.2\nontology: go\n").unwrap();
+    for go_term in 1..=TERM_COUNT {
+        writeln!(writer, "[Term]").unwrap();
+        writeln!(writer, "id: GO:{:07}", go_term).unwrap();
+        writeln!(writer, "name: synthetic process {}", go_term).unwrap();
+        writeln!(writer, "namespace: {}", namespaces[go_term as usize % 3]).unwrap();
+        writeln!(
+            writer,
+            "def: \"A synthetic term used to benchmark the OBO parser, with a definition of typical length for GO term {}.\" [GOC:bench]",
+            go_term
+        ).unwrap();
+        writeln!(writer, "synonym: \"synthetic synonym {}\" EXACT []", go_term).unwrap();
+        if go_term > 1 {
+            writeln!(writer, "is_a: GO:{:07} ! parent term", go_term / 2).unwrap();
+        }
+        if go_term > 3 {
+            writeln!(writer, "relationship: part_of GO:{:07} ! whole term", go_term / 3).unwrap();
+        }
+        if go_term % 997 == 0 {
+            writeln!(writer, "is_obsolete: true").unwrap();
+            writeln!(writer, "replaced_by: GO:{:07}", go_term - 1).unwrap();
+        }
+        writeln!(writer).unwrap();
+    }
+    writeln!(writer, "[Typedef]\nid: part_of\nname: part of").unwrap();
+    writer.flush().unwrap();
+}
+
+fn bench_obo_parser(c: &mut Criterion) {
+    let obo_path = std::env::temp_dir().join(format!("taxago_bench_{}.obo", std::process::id()));
+    write_synthetic_obo(&obo_path);
+
+    assert_eq!(parse_obo_file(&obo_path).unwrap().len(), parse_obo_file_line_by_line(&obo_path).len());
+
+    let mut group = c.benchmark_group("parse_obo_file");
+    group.sample_size(20);
+
+    group.bench_function("line_by_line", |b| {
+        b.iter(|| black_box(parse_obo_file_line_by_line(&obo_path)))
+    });
+
+    group.bench_function("mmap_parallel", |b| {
+        b.iter(|| black_box(parse_obo_file(&obo_path).unwrap()))
+    });
+
+    group.finish();
+    fs::remove_file(&obo_path).unwrap();
+}
+
+criterion_group!(benches, bench_obo_parser);
+criterion_main!(benches);
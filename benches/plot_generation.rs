@@ -0,0 +1,190 @@
+use compact_str::CompactString;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rand::{rngs::StdRng, seq::index::sample, Rng, SeedableRng};
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use TaxaGO::analysis::{
+    enrichment_analysis::*,
+    enrichment_plots::*,
+    multiple_testing_correction::*,
+    write_results::*
+};
+use TaxaGO::parsers::{
+    background_parser::*,
+    obo_parser::*,
+    study_parser::StudyPop
+};
+
+const TAXON_COUNT: TaxonID = 50;
+const GO_TERM_COUNT: GOTermID = 5_000;
+const STUDY_PROTEINS: usize = 400;
+const BACKGROUND_PROTEINS: usize = 20_000;
+
+struct SyntheticDataset {
+    taxon_ids: FxHashSet<TaxonID>,
+    ontology: OboMap,
+    study_population: StudyPop,
+    background_go_counts: FxHashMap<TaxonID, GOTermCount>,
+    background_totals: FxHashMap<TaxonID, usize>,
+    taxid_species_map: FxHashMap<TaxonID, String>,
+}
+
+// Every taxon tests the same 5000 GO terms. A term is annotated to a random subset of the study proteins, and
+// about a tenth of the terms are rare in the background so that they come out as enriched.
+fn synthetic_dataset() -> SyntheticDataset {
+    let mut rng = StdRng::seed_from_u64(42);
+    let namespaces = [NameSpace::BiologicalProcess, NameSpace::MolecularFunction, NameSpace::CellularComponent];
+
+    let ontology: OboMap = (1..=GO_TERM_COUNT)
+        .map(|go_term| {
+            let mut term = OboTerm::new();
+            term.name = format!("Synthetic term {}", go_term);
+            term.namespace = namespaces[go_term as usize % 3];
+            (go_term, term)
+        })
+        .collect();
+
+    let taxon_ids: FxHashSet<TaxonID> = (1..=TAXON_COUNT).collect();
+    let mut study_population = StudyPop {
+        taxon_map: FxHashMap::default(),
+        taxon_protein_count: FxHashMap::default(),
+        go_term_count: FxHashMap::default(),
+        go_term_to_protein_set: FxHashMap::default(),
+        protein_scores: FxHashMap::default(),
+    };
+    let mut background_go_counts: FxHashMap<TaxonID, GOTermCount> = FxHashMap::default();
+
+    for &taxon_id in &taxon_ids {
+        let proteins: Vec<Protein> = (0..STUDY_PROTEINS)
+            .map(|idx| Arc::new(CompactString::from(format!("P{}_{}", taxon_id, idx))))
+            .collect();
+
+        let mut go_term_to_proteins: GOTermToProteinSet = FxHashMap::default();
+        let mut study_counts: GOTermCount = FxHashMap::default();
+        let mut background_counts: GOTermCount = FxHashMap::default();
+        for go_term in 1..=GO_TERM_COUNT {
+            let study_count = rng.random_range(1..=20);
+            let annotated: FxHashSet<Protein> = sample(&mut rng, STUDY_PROTEINS, study_count)
+                .into_iter()
+                .map(|idx| proteins[idx].clone())
+                .collect();
+            let background_scale = if rng.random_bool(0.1) { 5 } else { 60 };
+
+            study_counts.insert(go_term, study_count);
+            background_counts.insert(go_term, study_count * background_scale);
+            go_term_to_proteins.insert(go_term, annotated);
+        }
+
+        study_population.taxon_map.insert(taxon_id, proteins.into_iter().collect());
+        study_population.taxon_protein_count.insert(taxon_id, STUDY_PROTEINS);
+        study_population.go_term_count.insert(taxon_id, study_counts);
+        study_population.go_term_to_protein_set.insert(taxon_id, go_term_to_proteins);
+        background_go_counts.insert(taxon_id, background_counts);
+    }
+
+    SyntheticDataset {
+        background_totals: taxon_ids.iter().map(|&taxon_id| (taxon_id, BACKGROUND_PROTEINS)).collect(),
+        taxid_species_map: taxon_ids.iter().map(|&taxon_id| (taxon_id, format!("Species {}", taxon_id))).collect(),
+        taxon_ids,
+        ontology,
+        study_population,
+        background_go_counts,
+    }
+}
+
+// The single taxon part of the taxago pipeline: enrichment, multiple testing correction, writing the results and,
+// unless `save_plots` is `PlotType::None`, the plot and network data. Rendering is left out since it is already
+// skipped with `PlotType::None` and is dominated by the network layout.
+fn run_pipeline(dataset: &SyntheticDataset, output_dir: &PathBuf, save_plots: PlotType) {
+    let analysis = EnrichmentAnalysis::new(StatisticalTest::Fishers);
+    let enrichment_results = analysis.classic(
+        &dataset.taxon_ids,
+        &dataset.background_go_counts,
+        &dataset.study_population.go_term_count,
+        &dataset.background_totals,
+        &dataset.study_population.taxon_protein_count,
+    );
+
+    let significant_species_results = adjust_species_p_values(
+        &enrichment_results,
+        AdjustmentMethod::BenjaminiHochberg,
+        Some(0.05),
+        0.2
+    );
+    let significant_species_terms: FxHashMap<TaxonID, FxHashSet<GOTermID>> = significant_species_results
+        .iter()
+        .map(|(taxon_id, go_terms)| (*taxon_id, go_terms.keys().copied().collect()))
+        .collect();
+
+    write_single_taxon_results(
+        &significant_species_results,
+        &dataset.ontology,
+        &dataset.taxid_species_map,
+        output_dir,
+        &WriteOptions {
+            output_format: OutputFormat::Tsv,
+            statistical_test: StatisticalTest::Fishers,
+            raw_counts: None,
+            max_terms_per_namespace: 20,
+            tested_results: &enrichment_results,
+            all_results: None,
+            significant_terms: &significant_species_terms,
+        },
+        None,
+    ).unwrap();
+
+    if save_plots != PlotType::None {
+        let (processed_species_data, go_term_to_protein_set) = process_species_data(
+            significant_species_results,
+            &dataset.study_population,
+            &dataset.taxid_species_map
+        );
+
+        let species_plot_data = prepare_plot_data(
+            &processed_species_data,
+            &dataset.ontology,
+            20,
+            StatisticalTest::Fishers.effect_size_label());
+
+        let species_protein_provider = ProteinDataProvider::Species(&go_term_to_protein_set);
+        let species_network_data = prepare_network_data(
+            &processed_species_data,
+            &species_protein_provider,
+            &dataset.ontology,
+        );
+
+        let species_networks = build_networks(
+            &species_network_data,
+            &processed_species_data,
+            &dataset.ontology,
+            0.25,
+            StatisticalTest::Fishers.effect_size_label()
+        );
+
+        black_box((species_plot_data, species_networks));
+    }
+}
+
+fn bench_plot_generation(c: &mut Criterion) {
+    let dataset = synthetic_dataset();
+    let output_dir = std::env::temp_dir().join(format!("taxago_bench_plots_{}", std::process::id()));
+
+    let mut group = c.benchmark_group("single_taxon_pipeline_50_taxa_5000_terms");
+    group.sample_size(10);
+
+    group.bench_function("save_plots_none", |b| {
+        b.iter(|| run_pipeline(&dataset, &output_dir, PlotType::None))
+    });
+
+    group.bench_function("save_plots_interactive", |b| {
+        b.iter(|| run_pipeline(&dataset, &output_dir, PlotType::Interactive))
+    });
+
+    group.finish();
+    fs::remove_dir_all(&output_dir).unwrap();
+}
+
+criterion_group!(benches, bench_plot_generation);
+criterion_main!(benches);
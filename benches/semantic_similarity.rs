@@ -0,0 +1,107 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use daggy::NodeIndex;
+use petgraph::algo::toposort;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rustc_hash::{FxHashMap, FxHashSet};
+use TaxaGO::parsers::{
+    background_parser::GOTermID,
+    obo_parser::*
+};
+use TaxaGO::utils::semantic_similarity::*;
+
+const ONTOLOGY_SIZE: u32 = 5_000;
+const TERM_COUNT: u32 = 100;
+
+struct Ontology {
+    graph: OntologyGraph,
+    go_id_to_node_index: FxHashMap<GOTermID, NodeIndex>,
+    node_index_to_go_id: FxHashMap<NodeIndex, GOTermID>,
+    rev_topo_order: Vec<GOTermID>,
+}
+
+// A random DAG rooted at term 1, where every other term has one to three `is_a` or `part_of` parents with a lower ID.
+fn synthetic_ontology() -> Ontology {
+    let mut rng = StdRng::seed_from_u64(42);
+    let mut obo_map: OboMap = FxHashMap::default();
+    obo_map.insert(1, OboTerm::new());
+
+    for go_term in 2..=ONTOLOGY_SIZE {
+        let mut term = OboTerm::new();
+        for _ in 0..rng.random_range(1..=3) {
+            let relationship = if rng.random_bool(0.8) { Relationship::IsA } else { Relationship::PartOf };
+            term.relationships.insert(rng.random_range(1..go_term), relationship);
+        }
+        obo_map.insert(go_term, term);
+    }
+
+    let (graph, go_id_to_node_index) = build_ontology_graph(&obo_map).unwrap();
+    let node_index_to_go_id: FxHashMap<NodeIndex, GOTermID> = go_id_to_node_index
+        .iter()
+        .map(|(&go_id, &node_idx)| (node_idx, go_id))
+        .collect();
+    let mut rev_topo_order: Vec<GOTermID> = toposort(&graph, None)
+        .unwrap()
+        .iter()
+        .map(|node_idx| node_index_to_go_id[node_idx])
+        .collect();
+    rev_topo_order.reverse();
+
+    Ontology { graph, go_id_to_node_index, node_index_to_go_id, rev_topo_order }
+}
+
+fn bench_wang_similarity(c: &mut Criterion) {
+    let ontology = synthetic_ontology();
+    // The deepest terms have the most ancestors, so they are the most expensive to traverse.
+    let terms: FxHashSet<GOTermID> = (ONTOLOGY_SIZE - TERM_COUNT + 1..=ONTOLOGY_SIZE).collect();
+    let terms_vec: Vec<GOTermID> = terms.iter().copied().collect();
+
+    let mut group = c.benchmark_group("wang_similarity_100_terms");
+
+    group.bench_function("s_values_per_pair", |b| {
+        b.iter(|| {
+            let mut total = 0.0;
+            for i in 0..terms_vec.len() {
+                for j in i..terms_vec.len() {
+                    total += wang_similarity(
+                        terms_vec[i],
+                        terms_vec[j],
+                        &ontology.graph,
+                        &ontology.go_id_to_node_index,
+                        &ontology.node_index_to_go_id,
+                        &ontology.rev_topo_order,
+                    ).unwrap();
+                }
+            }
+            black_box(total)
+        })
+    });
+
+    group.bench_function("precomputed_s_values", |b| {
+        b.iter(|| {
+            let s_value_cache = precompute_s_values(
+                &terms,
+                &ontology.graph,
+                &ontology.go_id_to_node_index,
+                &ontology.node_index_to_go_id,
+                &ontology.rev_topo_order,
+            );
+            let mut total = 0.0;
+            for i in 0..terms_vec.len() {
+                for j in i..terms_vec.len() {
+                    total += wang_similarity_cached(
+                        terms_vec[i],
+                        terms_vec[j],
+                        &s_value_cache[&terms_vec[i]],
+                        &s_value_cache[&terms_vec[j]],
+                    );
+                }
+            }
+            black_box(total)
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_wang_similarity);
+criterion_main!(benches);
@@ -8,6 +8,8 @@ use::rayon::prelude::*;
 use petgraph::algo::toposort;
 use petgraph::visit::IntoNodeIdentifiers;
 
+pub const NAMESPACE_ROOTS: [GOTermID; 3] = [8150, 3674, 5575];
+
 #[derive(Debug, Default, Clone)]
 pub struct GOAncestorCache {
     pub parent_map: FxHashMap<GOTermID, FxHashSet<GOTermID>>,
@@ -29,7 +31,7 @@ impl GOAncestorCache {
             .filter_map(|&go_term| {
                 go_id_to_node_index.get(go_term).map(|&node_idx| {
                     let ancestors = get_unique_ancestors(
-                        node_idx, ontology_graph, &node_index_to_go_id);
+                        node_idx, ontology_graph, &node_index_to_go_id, go_term_map);
                     
                     (*go_term, ancestors)
                 })
@@ -53,7 +55,17 @@ impl GOAncestorCache {
             propagation_order
         })
     }
-    
+
+    // Builds the ontology graph on the way, for callers that only need the cache to propagate counts.
+    pub fn from_ontology(ontology: &OboMap) -> Result<Self> {
+        let (ontology_graph, go_id_to_node_index) = build_ontology_graph(ontology)?;
+        let node_index_to_go_id: FxHashMap<NodeIndex, GOTermID> = go_id_to_node_index
+            .iter()
+            .map(|(&go_id, &node_idx)| (node_idx, go_id))
+            .collect();
+
+        Self::new(&ontology_graph, ontology, &go_id_to_node_index, &node_index_to_go_id)
+    }
 }
 
 impl StudyPop {
@@ -138,26 +150,41 @@ impl BackgroundPop {
     }
 }
 
+// Ancestors are only collected inside the namespace of the starting term, and never above its namespace root.
 fn get_unique_ancestors(
     node_idx: NodeIndex,
     ontology_graph: &OntologyGraph,
-    node_index_to_go_id: &FxHashMap<NodeIndex, GOTermID>
+    node_index_to_go_id: &FxHashMap<NodeIndex, GOTermID>,
+    go_term_map: &OboMap
 ) -> FxHashSet<GOTermID> {
 
     let mut ancestors = FxHashSet::default();
     let mut to_visit = vec![node_idx];
     let mut visited = FxHashSet::default();
+
+    let namespace = node_index_to_go_id
+        .get(&node_idx)
+        .and_then(|go_term_id| go_term_map.get(go_term_id))
+        .map(|term| term.namespace);
     
     while let Some(current_idx) = to_visit.pop() {
         if !visited.insert(current_idx) {
             continue;
         }
+
+        if NAMESPACE_ROOTS.contains(&node_index_to_go_id[&current_idx]) {
+            continue;
+        }
         
         let mut parents = ontology_graph.parents(current_idx);
         while let Some((edge_idx, parent_idx)) = parents.walk_next(ontology_graph) {
             match ontology_graph.edge_weight(edge_idx).unwrap() {
                 Relationship::IsA | Relationship::PartOf => {
                     let go_term_id = node_index_to_go_id.get(&parent_idx).unwrap();
+                    let parent_namespace = go_term_map.get(go_term_id).map(|term| term.namespace);
+                    if parent_namespace != namespace {
+                        continue;
+                    }
                     ancestors.insert(*go_term_id);
                     to_visit.push(parent_idx);
                 },
@@ -167,4 +194,63 @@ fn get_unique_ancestors(
     }
     
     ancestors
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use compact_str::CompactString;
+
+    fn term(namespace: NameSpace, parents: &[GOTermID]) -> OboTerm {
+        let mut term = OboTerm::new();
+        term.namespace = namespace;
+        term.relationships = parents.iter().map(|&parent| (parent, Relationship::IsA)).collect();
+        term
+    }
+
+    // GO:2 -> GO:1 -> GO:8150 (Biological Process root), with two malformed edges:
+    // GO:1 is also a child of the Molecular Function term GO:3, and the root is a child of GO:99.
+    fn ancestor_cache() -> GOAncestorCache {
+        let mut ontology: OboMap = FxHashMap::default();
+        ontology.insert(99, term(NameSpace::BiologicalProcess, &[]));
+        ontology.insert(8150, term(NameSpace::BiologicalProcess, &[99]));
+        ontology.insert(3, term(NameSpace::MolecularFunction, &[]));
+        ontology.insert(1, term(NameSpace::BiologicalProcess, &[8150, 3]));
+        ontology.insert(2, term(NameSpace::BiologicalProcess, &[1]));
+
+        let (ontology_graph, go_id_to_node_index) = build_ontology_graph(&ontology).unwrap();
+        let node_index_to_go_id: FxHashMap<NodeIndex, GOTermID> = go_id_to_node_index
+            .iter()
+            .map(|(&go_term, &node_index)| (node_index, go_term))
+            .collect();
+
+        GOAncestorCache::new(&ontology_graph, &ontology, &go_id_to_node_index, &node_index_to_go_id).unwrap()
+    }
+
+    #[test]
+    fn ancestors_stop_at_namespace_boundaries_and_roots() {
+        let cache = ancestor_cache();
+
+        let expected: FxHashSet<GOTermID> = [1, 8150].into_iter().collect();
+        assert_eq!(cache.parent_map[&2], expected);
+        assert!(cache.parent_map[&8150].is_empty());
+    }
+
+    #[test]
+    fn propagation_stops_at_namespace_boundaries() {
+        let cache = ancestor_cache();
+        let protein: Protein = Arc::new(CompactString::from("P12345"));
+
+        let mut study_pop = StudyPop::default();
+        study_pop.go_term_count.insert(9606, [(2, 1)].into_iter().collect());
+        study_pop.go_term_to_protein_set.insert(9606, [(2, [protein].into_iter().collect())].into_iter().collect());
+
+        study_pop.propagate_counts(&[9606].into_iter().collect(), &cache);
+
+        let counts = &study_pop.go_term_count[&9606];
+        assert_eq!(counts.get(&1), Some(&1));
+        assert_eq!(counts.get(&8150), Some(&1));
+        assert_eq!(counts.get(&3), None);
+        assert_eq!(counts.get(&99), None);
+    }
+}
@@ -0,0 +1,130 @@
+use rustc_hash::FxHashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::error::Error;
+use std::path::PathBuf;
+use plotly::{
+    Plot, Scatter, Layout,
+    common::{Title, Font, Mode},
+    layout::Axis
+};
+use crate::parsers::{
+    background_parser::*,
+    obo_parser::*
+};
+
+#[derive(Debug, Clone)]
+pub struct CutoffCounts {
+    pub threshold: f64,
+    pub taxon: String,
+    pub biological_process: usize,
+    pub molecular_function: usize,
+    pub cellular_component: usize,
+}
+
+impl CutoffCounts {
+    pub fn total(&self) -> usize {
+        self.biological_process + self.molecular_function + self.cellular_component
+    }
+}
+
+// Counts, for every threshold, the significant terms of each taxon whose log(Odds Ratio) is at least the threshold.
+// Terms missing from the ontology cannot be assigned a namespace and are skipped.
+pub fn cutoff_analysis(
+    species_results: &FxHashMap<String, Vec<(GOTermID, f64, f64)>>,
+    ontology: &OboMap,
+    thresholds: &[f64],
+) -> Vec<CutoffCounts> {
+    let mut taxa: Vec<&String> = species_results.keys().collect();
+    taxa.sort();
+
+    let mut cutoff_counts = Vec::with_capacity(thresholds.len() * taxa.len());
+
+    for &threshold in thresholds {
+        for taxon in &taxa {
+            let mut counts = CutoffCounts {
+                threshold,
+                taxon: taxon.to_string(),
+                biological_process: 0,
+                molecular_function: 0,
+                cellular_component: 0,
+            };
+
+            for &(go_term, log_odds_ratio, _) in &species_results[*taxon] {
+                if log_odds_ratio < threshold {
+                    continue;
+                }
+                match ontology.get(&go_term).map(|term| term.namespace) {
+                    Some(NameSpace::BiologicalProcess) => counts.biological_process += 1,
+                    Some(NameSpace::MolecularFunction) => counts.molecular_function += 1,
+                    Some(NameSpace::CellularComponent) => counts.cellular_component += 1,
+                    None => {},
+                }
+            }
+
+            cutoff_counts.push(counts);
+        }
+    }
+
+    cutoff_counts
+}
+
+pub fn write_cutoff_analysis(
+    cutoff_counts: &[CutoffCounts],
+    output_file: &PathBuf,
+) -> Result<(), Box<dyn Error>> {
+    println!("Writing cutoff analysis to: {}\n", output_file.display());
+
+    let file = File::create(output_file)?;
+    let mut writer = BufWriter::new(file);
+
+    writer.write_all(b"Threshold\tTaxon\tN_significant_BP\tN_significant_MF\tN_significant_CC\tN_total_significant\n")?;
+
+    for counts in cutoff_counts {
+        writeln!(
+            writer,
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            counts.threshold,
+            counts.taxon,
+            counts.biological_process,
+            counts.molecular_function,
+            counts.cellular_component,
+            counts.total()
+        )?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+// Elbow curve of the number of significant terms, summed over all taxa and namespaces, against the threshold.
+pub fn cutoff_plot(
+    cutoff_counts: &[CutoffCounts],
+    thresholds: &[f64],
+    html_file: &PathBuf,
+) -> Result<(), Box<dyn Error>> {
+    let totals: Vec<usize> = thresholds
+        .iter()
+        .map(|&threshold| {
+            cutoff_counts
+                .iter()
+                .filter(|counts| counts.threshold == threshold)
+                .map(CutoffCounts::total)
+                .sum()
+        })
+        .collect();
+
+    let trace = Scatter::new(thresholds.to_vec(), totals)
+        .mode(Mode::LinesMarkers)
+        .name("Significant GO terms");
+
+    let mut plot = Plot::new();
+    plot.add_trace(trace);
+    plot.set_layout(Layout::new()
+        .title(Title::with_text("Significant GO terms per log(Odds Ratio) threshold").font(Font::new().size(14)))
+        .x_axis(Axis::new().title(Title::with_text("Minimum log(Odds Ratio)")))
+        .y_axis(Axis::new().title(Title::with_text("Significant GO terms"))));
+
+    plot.write_html(html_file);
+    Ok(())
+}
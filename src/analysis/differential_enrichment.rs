@@ -0,0 +1,170 @@
+use rustc_hash::FxHashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::error::Error;
+use std::path::PathBuf;
+use statrs::distribution::{Normal, ContinuousCDF};
+use crate::parsers::{
+    background_parser::*,
+    obo_parser::*
+};
+use crate::analysis::{
+    enrichment_analysis::*,
+    multiple_testing_correction::*
+};
+
+#[derive(Debug, Clone)]
+pub struct DifferentialResult {
+    pub log_odds_ratio_a: f64,
+    pub log_odds_ratio_b: f64,
+    pub delta_log_odds_ratio: f64,
+    pub z_score: f64,
+    pub p_value: f64,
+    pub adjusted_p_value: f64,
+}
+
+pub fn differential_enrichment(
+    results_a: &FxHashMap<GOTermID, GOTermResults>,
+    results_b: &FxHashMap<GOTermID, GOTermResults>,
+    adjustment_method: AdjustmentMethod,
+) -> FxHashMap<GOTermID, DifferentialResult> {
+    let standard_normal = Normal::new(0.0, 1.0).unwrap();
+
+    let mut differential_results: Vec<(GOTermID, DifferentialResult)> = results_a
+        .iter()
+        .filter_map(|(go_term, result_a)| {
+            let result_b = results_b.get(go_term)?;
+
+            let delta_log_odds_ratio = result_a.log_odds_ratio - result_b.log_odds_ratio;
+            let standard_error = (result_a.variance + result_b.variance).sqrt();
+            if !standard_error.is_finite() || standard_error == 0.0 {
+                return None;
+            }
+
+            let z_score = delta_log_odds_ratio / standard_error;
+            let p_value = (2.0 * standard_normal.sf(z_score.abs())).min(1.0);
+
+            Some((*go_term, DifferentialResult {
+                log_odds_ratio_a: result_a.log_odds_ratio,
+                log_odds_ratio_b: result_b.log_odds_ratio,
+                delta_log_odds_ratio,
+                z_score,
+                p_value,
+                adjusted_p_value: p_value,
+            }))
+        })
+        .collect();
+
+    let p_values: Vec<f64> = differential_results
+        .iter()
+        .map(|(_, result)| result.p_value)
+        .collect();
+
+    let adjusted_p_values = adjustment_method.adjust(p_values);
+
+    for ((_, result), adjusted_p_value) in differential_results.iter_mut().zip(adjusted_p_values) {
+        result.adjusted_p_value = adjusted_p_value;
+    }
+
+    differential_results.into_iter().collect()
+}
+
+pub fn write_differential_results(
+    differential_results: &FxHashMap<GOTermID, DifferentialResult>,
+    ontology: &OboMap,
+    output_file: &PathBuf,
+) -> Result<(), Box<dyn Error>> {
+    println!("Writing differential enrichment results to: {}\n", output_file.display());
+
+    let file = File::create(output_file)?;
+    let mut writer = BufWriter::new(file);
+
+    writer.write_all(b"GO Term ID\tName\tNamespace\tlog(Odds Ratio) A\tlog(Odds Ratio) B\tDelta log(Odds Ratio)\tZ-score\tp-value\tAdjusted p-value\n")?;
+
+    let mut sorted_results: Vec<(&GOTermID, &DifferentialResult)> = differential_results.iter().collect();
+    sorted_results.sort_by(|a, b| a.1.p_value.partial_cmp(&b.1.p_value).unwrap_or(std::cmp::Ordering::Equal));
+
+    for (go_term, result) in sorted_results {
+        let Some(term) = ontology.get(go_term) else { continue };
+        if term.is_obsolete {
+            continue;
+        }
+
+        let namespace = term.namespace.label();
+
+        writeln!(
+            writer,
+            "GO:{:07}\t{}\t{}\t{:.3}\t{:.3}\t{:.3}\t{:.3}\t{:.5e}\t{:.5e}",
+            go_term,
+            term.name,
+            namespace,
+            result.log_odds_ratio_a,
+            result.log_odds_ratio_b,
+            result.delta_log_odds_ratio,
+            result.z_score,
+            result.p_value,
+            result.adjusted_p_value
+        )?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term_results(log_odds_ratio: f64, variance: f64) -> GOTermResults {
+        GOTermResults {
+            log_odds_ratio,
+            p_value: 0.01,
+            contingency_table: None,
+            study_count: 5,
+            variance,
+        }
+    }
+
+    #[test]
+    fn z_scores_and_p_values_match_the_hand_computed_values() {
+        let results_a: FxHashMap<GOTermID, GOTermResults> = [
+            (1, term_results(2.0, 0.5)),
+            (2, term_results(1.0, 0.25)),
+            (3, term_results(1.5, 0.5)),
+            (4, term_results(1.0, 0.0)),
+        ].into_iter().collect();
+        let results_b: FxHashMap<GOTermID, GOTermResults> = [
+            (1, term_results(0.0, 0.5)),
+            (2, term_results(1.0, 0.75)),
+            (4, term_results(0.5, 0.0)),
+        ].into_iter().collect();
+
+        let differential = differential_enrichment(&results_a, &results_b, AdjustmentMethod::Bonferroni);
+
+        // Term 3 is only tested in A and term 4 has no variance, so neither can be compared.
+        assert_eq!(differential.len(), 2);
+
+        // Delta = 2, SE = sqrt(0.5 + 0.5) = 1, so z = 2 and p = 2 * (1 - Φ(2)).
+        let shifted = &differential[&1];
+        assert_eq!(shifted.delta_log_odds_ratio, 2.0);
+        assert_eq!(shifted.z_score, 2.0);
+        assert!((shifted.p_value - 0.045500263896358).abs() < 1e-9);
+        assert!((shifted.adjusted_p_value - 2.0 * 0.045500263896358).abs() < 1e-9);
+
+        let unchanged = &differential[&2];
+        assert_eq!(unchanged.z_score, 0.0);
+        assert_eq!(unchanged.p_value, 1.0);
+        assert_eq!(unchanged.adjusted_p_value, 1.0);
+    }
+
+    #[test]
+    fn depletion_in_a_gives_a_negative_z_score() {
+        let results_a: FxHashMap<GOTermID, GOTermResults> = [(1, term_results(-1.0, 0.125))].into_iter().collect();
+        let results_b: FxHashMap<GOTermID, GOTermResults> = [(1, term_results(0.0, 0.125))].into_iter().collect();
+
+        let differential = differential_enrichment(&results_a, &results_b, AdjustmentMethod::None);
+
+        assert_eq!(differential[&1].z_score, -2.0);
+        assert!((differential[&1].p_value - 0.045500263896358).abs() < 1e-9);
+    }
+}
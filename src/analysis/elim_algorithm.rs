@@ -4,18 +4,22 @@ use crate::parsers::{
     study_parser::*,
     obo_parser::*
 };
-use crate::analysis::
-    enrichment_analysis::*;
+use crate::analysis::{
+    enrichment_analysis::*,
+    count_propagation::*
+};
 use rayon::prelude::*;
 
 impl EnrichmentAnalysis{
+    #[allow(clippy::too_many_arguments)]
     pub fn elim_analysis(
         &self,
         taxon_ids: &FxHashSet<TaxonID>, 
-        significance_threshold: f64,         
+        elim_alpha: f64,
         study_pop: &StudyPop,
         background_pop: &BackgroundPop,
-        level_to_go_term: &LevelToTerms
+        level_to_go_term: &LevelToTerms,
+        ancestor_cache: &GOAncestorCache
     ) -> FxHashMap<TaxonID, FxHashMap<GOTermID, GOTermResults>> {
         
         let max_level = match level_to_go_term.keys().max() {
@@ -40,13 +44,15 @@ impl EnrichmentAnalysis{
                     Some(counts) => counts,
                     None => return (taxon_id, FxHashMap::default())
                 };
+
+                let taxon_background_go_term_proteins = background_pop.go_term_to_protein_set.get(&taxon_id);
                 
                 let taxon_background_total_count = match background_pop.taxon_protein_count.get(&taxon_id) {
                     Some(&count) => count,
                     None => return (taxon_id, FxHashMap::default())
                 };
         
-                let mut marked_proteins: FxHashSet<&Protein> = FxHashSet::default();
+                let mut removed_proteins: FxHashMap<GOTermID, FxHashSet<&Protein>> = FxHashMap::default();
                 let mut go_term_results = FxHashMap::with_capacity_and_hasher(
                     level_to_go_term.values().map(|v| v.len()).sum(),
                     rustc_hash::FxBuildHasher::default()
@@ -59,29 +65,47 @@ impl EnrichmentAnalysis{
                                 Some(proteins) => proteins,
                                 None => continue
                             };
+
+                            let removed = removed_proteins.get(&go_term);
+                            let is_kept = |protein: &&Protein| removed.is_none_or(|r| !r.contains(protein));
                             
-                            let background_counts = match taxon_background_go_term_count.get(&go_term) {
-                                Some(&count) => count,
-                                None => continue
+                            let remaining_background_proteins: Option<Vec<&Protein>> = taxon_background_go_term_proteins
+                                .and_then(|term_proteins| term_proteins.get(&go_term))
+                                .map(|proteins| proteins.iter().filter(is_kept).collect());
+
+                            let background_counts = match &remaining_background_proteins {
+                                Some(proteins) => proteins.len(),
+                                None => match taxon_background_go_term_count.get(&go_term) {
+                                    Some(&count) => count,
+                                    None => continue
+                                }
                             };
                             
-                            let unmarked_proteins: Vec<&Protein> = original_study_proteins
+                            let remaining_study_proteins: Vec<&Protein> = original_study_proteins
                                 .iter()
-                                .filter(|protein| !marked_proteins.contains(protein))
+                                .filter(is_kept)
                                 .collect();
                             
-                            let study_counts = unmarked_proteins.len();
+                            let study_counts = remaining_study_proteins.len();
                 
-                            let results = analyze_single_go_term(
+                            let results = self.analyze_single_go_term(
                                 study_counts,
                                 background_counts,
                                 taxon_study_total_count,
-                                taxon_background_total_count,
-                                self.test_type
+                                taxon_background_total_count
                             );
                 
-                            if results.p_value <= significance_threshold {
-                                marked_proteins.extend(unmarked_proteins);
+                            // Only terms significant at `elim_alpha` remove their proteins from their ancestors.
+                            if results.p_value <= elim_alpha {
+                                if let Some(ancestors) = ancestor_cache.parent_map.get(&go_term) {
+                                    for ancestor in ancestors {
+                                        let ancestor_removed = removed_proteins.entry(*ancestor).or_default();
+                                        ancestor_removed.extend(remaining_study_proteins.iter().copied());
+                                        if let Some(proteins) = &remaining_background_proteins {
+                                            ancestor_removed.extend(proteins.iter().copied());
+                                        }
+                                    }
+                                }
                             }
                 
                             go_term_results.insert(go_term, results);
@@ -93,4 +117,169 @@ impl EnrichmentAnalysis{
             })
             .collect()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use compact_str::CompactString;
+    use std::sync::Arc;
+
+    const TAXON: TaxonID = 9606;
+    const PARENT: GOTermID = 1;
+    const CHILD: GOTermID = 2;
+
+    fn proteins(prefix: &str, count: usize) -> FxHashSet<Protein> {
+        (0..count)
+            .map(|i| Arc::new(CompactString::from(format!("{}{}", prefix, i))))
+            .collect()
+    }
+
+    // 50 study and 1000 background proteins. The child term is annotated to 20 background proteins,
+    // `child_study` of which are in the study population. The parent term holds the child's proteins
+    // (counts are already propagated) plus 30 background proteins of its own, 2 of them study proteins.
+    fn populations(child_study: usize) -> (StudyPop, BackgroundPop, LevelToTerms, GOAncestorCache) {
+        let child_study_proteins = proteins("child_study_", child_study);
+        let parent_study_proteins: FxHashSet<Protein> = child_study_proteins
+            .iter()
+            .cloned()
+            .chain(proteins("parent_study_", 2))
+            .collect();
+
+        let child_background_proteins: FxHashSet<Protein> = child_study_proteins
+            .iter()
+            .cloned()
+            .chain(proteins("child_other_", 20 - child_study))
+            .collect();
+        let parent_background_proteins: FxHashSet<Protein> = child_background_proteins
+            .iter()
+            .cloned()
+            .chain(proteins("parent_study_", 2))
+            .chain(proteins("parent_other_", 28))
+            .collect();
+
+        let study_proteins: FxHashSet<Protein> = parent_study_proteins
+            .iter()
+            .cloned()
+            .chain(proteins("study_other_", 48 - child_study))
+            .collect();
+
+        let mut study_pop = StudyPop::default();
+        study_pop.taxon_protein_count.insert(TAXON, study_proteins.len());
+        study_pop.taxon_map.insert(TAXON, study_proteins);
+        study_pop.go_term_count.insert(TAXON, [
+            (PARENT, parent_study_proteins.len()),
+            (CHILD, child_study_proteins.len()),
+        ].into_iter().collect());
+        study_pop.go_term_to_protein_set.insert(TAXON, [
+            (PARENT, parent_study_proteins),
+            (CHILD, child_study_proteins),
+        ].into_iter().collect());
+
+        let mut background_pop = BackgroundPop::default();
+        background_pop.taxon_protein_count.insert(TAXON, 1000);
+        background_pop.go_term_count.insert(TAXON, [
+            (PARENT, parent_background_proteins.len()),
+            (CHILD, child_background_proteins.len()),
+        ].into_iter().collect());
+        background_pop.go_term_to_protein_set.insert(TAXON, [
+            (PARENT, parent_background_proteins),
+            (CHILD, child_background_proteins),
+        ].into_iter().collect());
+
+        let level_to_go_term: LevelToTerms = [(1, vec![PARENT]), (2, vec![CHILD])].into_iter().collect();
+
+        let mut ancestor_cache = GOAncestorCache::default();
+        ancestor_cache.parent_map.insert(CHILD, [PARENT].into_iter().collect());
+        ancestor_cache.parent_map.insert(PARENT, FxHashSet::default());
+
+        (study_pop, background_pop, level_to_go_term, ancestor_cache)
+    }
+
+    fn elim_results(child_study: usize, elim_alpha: f64) -> FxHashMap<GOTermID, GOTermResults> {
+        let (study_pop, background_pop, level_to_go_term, ancestor_cache) = populations(child_study);
+        let mut results = EnrichmentAnalysis::new(StatisticalTest::Fishers).elim_analysis(
+            &[TAXON].into_iter().collect(),
+            elim_alpha,
+            &study_pop,
+            &background_pop,
+            &level_to_go_term,
+            &ancestor_cache,
+        );
+        results.remove(&TAXON).unwrap()
+    }
+
+    #[test]
+    fn significant_child_proteins_are_removed_from_the_parent() {
+        let analysis = EnrichmentAnalysis::new(StatisticalTest::Fishers);
+        let classic_parent = analysis.analyze_single_go_term(17, 50, 50, 1000);
+        let classic_child = analysis.analyze_single_go_term(15, 20, 50, 1000);
+
+        let results = elim_results(15, 0.05);
+
+        assert_eq!(results[&CHILD].p_value, classic_child.p_value);
+        // Only the 2 study and 30 background proteins of the parent itself are left.
+        assert_eq!(results[&PARENT].contingency_table, Some(create_contingency_table(2, 30, 50, 1000)));
+        assert!(classic_parent.p_value < 1e-10);
+        assert!(results[&PARENT].p_value > classic_parent.p_value);
+    }
+
+    #[test]
+    fn elim_threshold_controls_which_children_suppress_their_parents() {
+        let child_p_value = elim_results(3, 0.05)[&CHILD].p_value;
+        assert!(child_p_value > 0.01 && child_p_value < 0.05, "child p = {}", child_p_value);
+
+        let strict = elim_results(3, 0.01);
+        assert_eq!(strict[&PARENT].contingency_table, Some(create_contingency_table(5, 50, 50, 1000)));
+
+        let lenient = elim_results(3, 0.05);
+        assert_eq!(lenient[&PARENT].contingency_table, Some(create_contingency_table(2, 30, 50, 1000)));
+    }
+
+    #[test]
+    fn elim_and_weight_differ_from_classic() {
+        let (study_pop, background_pop, level_to_go_term, ancestor_cache) = populations(15);
+        let analysis = EnrichmentAnalysis::new(StatisticalTest::Fishers);
+        let taxon_ids: FxHashSet<TaxonID> = [TAXON].into_iter().collect();
+
+        let mut ontology: OboMap = FxHashMap::default();
+        ontology.insert(PARENT, OboTerm::new());
+        let mut child = OboTerm::new();
+        child.relationships.insert(PARENT, Relationship::IsA);
+        ontology.insert(CHILD, child);
+        let (ontology_graph, go_id_to_node_index) = build_ontology_graph(&ontology).unwrap();
+        let node_index_to_go_id: FxHashMap<daggy::NodeIndex, GOTermID> = go_id_to_node_index
+            .iter()
+            .map(|(&go_term, &node_index)| (node_index, go_term))
+            .collect();
+
+        let classic = analysis.classic(
+            &taxon_ids,
+            &background_pop.go_term_count,
+            &study_pop.go_term_count,
+            &background_pop.taxon_protein_count,
+            &study_pop.taxon_protein_count,
+        );
+        let elim = analysis.elim_analysis(
+            &taxon_ids,
+            0.05,
+            &study_pop,
+            &background_pop,
+            &level_to_go_term,
+            &ancestor_cache,
+        );
+        let weight = analysis.weight(
+            &taxon_ids,
+            &study_pop,
+            &background_pop,
+            &level_to_go_term,
+            &ontology_graph,
+            &go_id_to_node_index,
+            &node_index_to_go_id,
+        );
+
+        let classic_parent = classic[&TAXON][&PARENT].p_value;
+        assert!(elim[&TAXON][&PARENT].p_value > classic_parent);
+        assert!(weight[&TAXON][&PARENT].p_value > classic_parent);
+    }
+}
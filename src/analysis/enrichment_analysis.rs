@@ -1,7 +1,8 @@
 use rustc_hash::{FxHashMap, FxHashSet};
 use fishers_exact::fishers_exact;
 use crate::parsers::background_parser::{GOTermCount, GOTermID, TaxonID};
-use statrs::distribution::{Hypergeometric, DiscreteCDF};
+use crate::parsers::study_parser::StudyPop;
+use statrs::distribution::{Beta, ContinuousCDF, Hypergeometric, DiscreteCDF};
 use rayon::prelude::*;
 use clap::ValueEnum;
 
@@ -11,14 +12,43 @@ pub type ContingencyTable = [usize; 4];
 pub struct GOTermResults {
     pub log_odds_ratio: f64,
     pub p_value: f64,
-    pub contingency_table: ContingencyTable,
+    // None for the Kolmogorov-Smirnov test, which compares score distributions instead of counts.
+    pub contingency_table: Option<ContingencyTable>,
+    // Study proteins annotated to the term, which sizes the term in plots.
+    pub study_count: usize,
     pub variance: f64
 }
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
 pub enum StatisticalTest {
     Fishers,
+    FishersTwoTailed,
     Hypergeometric,
+    KolmogorovSmirnov,
+    BetaBinomial,
+}
+
+impl StatisticalTest {
+    // Label of the effect size stored in `log_odds_ratio`, which holds the log Bayes factor for the Beta-Binomial test.
+    pub fn effect_size_label(&self) -> &'static str {
+        match self {
+            StatisticalTest::BetaBinomial => "log(Bayes Factor)",
+            _ => "log(Odds Ratio)",
+        }
+    }
+}
+
+// Beta(alpha, beta) prior on the probability that a study protein is annotated to a GO term.
+#[derive(Debug, Clone, Copy)]
+pub struct BetaPrior {
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+impl Default for BetaPrior {
+    fn default() -> Self {
+        Self { alpha: 1.0, beta: 1.0 }
+    }
 }
 
 pub fn create_contingency_table(
@@ -37,46 +67,92 @@ pub fn create_contingency_table(
     [a + 1, b + 1, c + 1, d + 1]
 }
 
+// Zero cells make the odds ratio and its variance undefined, so they are replaced with 0.5
+// (Haldane-Anscombe correction). Tables from `create_contingency_table` already carry a pseudocount.
+fn zero_cell_corrected(counts: &ContingencyTable) -> [f64; 4] {
+    counts.map(|count| if count == 0 { 0.5 } else { count as f64 })
+}
+
 pub fn calculate_log_odds_ratio(counts: &ContingencyTable) -> f64 {
-    let [a, b, c, d] = *counts;
-    ((f64::from(a as u32) * f64::from(d as u32)) / 
-     (f64::from(b as u32) * f64::from(c as u32))).ln()
+    let [a, b, c, d] = zero_cell_corrected(counts);
+    ((a * d) / (b * c)).ln()
+}
+
+// Pearson residuals (observed - expected) / sqrt(expected) of each cell, in the order of the contingency table.
+// Rows are study / non-study proteins and columns are proteins with / without the GO term.
+pub fn calculate_pearson_residuals(counts: &ContingencyTable) -> [f64; 4] {
+    let [a, b, c, d] = counts.map(|count| count as f64);
+    let total = a + b + c + d;
+    if total == 0.0 {
+        return [0.0; 4];
+    }
+
+    let (row_study, row_other) = (a + b, c + d);
+    let (col_with_term, col_without_term) = (a + c, b + d);
+    let expected = [
+        row_study * col_with_term / total,
+        row_study * col_without_term / total,
+        row_other * col_with_term / total,
+        row_other * col_without_term / total,
+    ];
+
+    let observed = [a, b, c, d];
+    std::array::from_fn(|i| {
+        if expected[i] > 0.0 {
+            (observed[i] - expected[i]) / expected[i].sqrt()
+        } else {
+            0.0
+        }
+    })
 }
 
 pub fn calculate_variance(contingency: &[usize; 4]) -> f64 {
-    let variance: f64 = if contingency.iter().all(|&x| x > 0) {
-        1.0 / contingency[0] as f64 +
-        1.0 / contingency[1] as f64 +
-        1.0 / contingency[2] as f64 +
-        1.0 / contingency[3] as f64
-    } else {
-        f64::INFINITY
-    };
-    
-    variance
+    zero_cell_corrected(contingency)
+        .iter()
+        .map(|count| 1.0 / count)
+        .sum()
 }
 
+// `Fishers` and `Hypergeometric` are one-sided tests for enrichment (over-representation of the GO term
+// in the study population); depleted terms get p-values close to 1. `FishersTwoTailed` tests both directions.
 pub fn calculate_p_value(counts: &ContingencyTable, test_type: StatisticalTest) -> f64 {
     match test_type {
         StatisticalTest::Fishers => fishers_test(counts),
+        StatisticalTest::FishersTwoTailed => fishers_two_tailed_test(counts),
         StatisticalTest::Hypergeometric => hypergeometric_test(counts),
+        // Contingency tables carry no ranking, so algorithms built on them fall back to Fisher's test.
+        StatisticalTest::KolmogorovSmirnov => fishers_test(counts),
+        // Without an `EnrichmentAnalysis` to take the prior from, the uniform prior is used.
+        StatisticalTest::BetaBinomial => beta_binomial_test(counts, &BetaPrior::default()).1,
     }
 }
 
-pub fn fishers_test(counts: &ContingencyTable) -> f64 {
-    let counts_u32 = [
+fn contingency_table_u32(counts: &ContingencyTable) -> [u32; 4] {
+    [
         counts[0] as u32,
         counts[1] as u32,
         counts[2] as u32,
         counts[3] as u32,
-    ];
-    
-    match fishers_exact(&counts_u32) {
+    ]
+}
+
+// P(X >= a) under the hypergeometric distribution of the study/GO term cell.
+pub fn fishers_test(counts: &ContingencyTable) -> f64 {
+    match fishers_exact(&contingency_table_u32(counts)) {
         Ok(result) => result.greater_pvalue,
         Err(_) => 1.0,
     }
 }
 
+// Sum of the probabilities of all tables with the same margins that are at most as likely as the observed one.
+pub fn fishers_two_tailed_test(counts: &ContingencyTable) -> f64 {
+    match fishers_exact(&contingency_table_u32(counts)) {
+        Ok(result) => result.two_tail_pvalue,
+        Err(_) => 1.0,
+    }
+}
+
+// P(X >= k), equivalent to the one-sided Fisher's test.
 pub fn hypergeometric_test(counts: &ContingencyTable) -> f64 {
     let k = counts[0] as u32;
     let n = (counts[0] + counts[1]) as u32;
@@ -89,36 +165,161 @@ pub fn hypergeometric_test(counts: &ContingencyTable) -> f64 {
     }
 }
 
-pub fn analyze_single_go_term(
-    study_with_go: usize,
-    background_with_go: usize,
-    total_study: usize,
-    total_background: usize,
-    test_type: StatisticalTest,
-) -> GOTermResults {
-    let contingency_table = create_contingency_table(
-        study_with_go,
-        background_with_go,
-        total_study,
-        total_background,
-    );
-    
-    GOTermResults {
-        log_odds_ratio: calculate_log_odds_ratio(&contingency_table),
-        p_value: calculate_p_value(&contingency_table, test_type),
-        contingency_table,
-        variance: calculate_variance(&contingency_table),
+// Bayesian test of whether the study proteins are annotated to the term more often than the background rate
+// k_bg / n_bg. The study count k of n proteins is Binomial(n, θ) with a Beta prior on θ, so its marginal
+// likelihood is Beta-Binomial and θ has the posterior Beta(alpha + k, beta + n - k). For the hypotheses
+// θ > k_bg / n_bg and θ <= k_bg / n_bg the Bayes factor is the posterior odds divided by the prior odds.
+// Returns (log Bayes factor, posterior P(θ <= k_bg / n_bg)). The table is expected to carry the pseudocount
+// of `create_contingency_table`, which is removed first.
+pub fn beta_binomial_test(counts: &ContingencyTable, prior: &BetaPrior) -> (f64, f64) {
+    let [a, b, c, d] = counts.map(|count| count.saturating_sub(1) as f64);
+    let (k, n) = (a, a + b);
+    let (k_bg, n_bg) = (a + c, a + b + c + d);
+
+    if n_bg == 0.0 || k_bg == 0.0 || k_bg >= n_bg {
+        return (0.0, 1.0);
+    }
+    let background_rate = k_bg / n_bg;
+
+    let (Ok(prior_dist), Ok(posterior_dist)) = (
+        Beta::new(prior.alpha, prior.beta),
+        Beta::new(prior.alpha + k, prior.beta + n - k),
+    ) else {
+        return (0.0, 1.0);
+    };
+
+    let posterior_null = posterior_dist.cdf(background_rate);
+    let posterior_enriched = posterior_dist.sf(background_rate);
+    let prior_null = prior_dist.cdf(background_rate);
+    let prior_enriched = prior_dist.sf(background_rate);
+
+    let ln = |probability: f64| probability.max(f64::MIN_POSITIVE).ln();
+    let log_bayes_factor = (ln(posterior_enriched) - ln(posterior_null)) - (ln(prior_enriched) - ln(prior_null));
+
+    (log_bayes_factor, posterior_null.clamp(0.0, 1.0))
+}
+
+// One-sided two-sample test of whether `term_scores` are shifted towards higher values than
+// `other_scores`. Both slices must be sorted in ascending order. Returns (D+, p-value), where the
+// p-value is Smirnov's asymptotic one-sided tail exp(-2·n·D+²) with n = n_term·n_other / (n_term + n_other).
+pub fn one_sided_kolmogorov_smirnov_test(term_scores: &[f64], other_scores: &[f64]) -> (f64, f64) {
+    let n_term = term_scores.len();
+    let n_other = other_scores.len();
+
+    if n_term == 0 || n_other == 0 {
+        return (0.0, 1.0);
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut d_statistic: f64 = 0.0;
+
+    while i < n_term && j < n_other {
+        let value = term_scores[i].min(other_scores[j]);
+        while i < n_term && term_scores[i] <= value {
+            i += 1;
+        }
+        while j < n_other && other_scores[j] <= value {
+            j += 1;
+        }
+        d_statistic = d_statistic.max(j as f64 / n_other as f64 - i as f64 / n_term as f64);
     }
+
+    let effective_n = (n_term * n_other) as f64 / (n_term + n_other) as f64;
+    let p_value = (-2.0 * effective_n * d_statistic * d_statistic).exp().min(1.0);
+
+    (d_statistic, p_value)
+}
+
+// Combines subsampling replicates: each term keeps the results of the first replicate it appears in,
+// with its p-value replaced by the median p-value across the replicates that tested it.
+pub fn median_replicate_p_values(
+    replicates: Vec<FxHashMap<TaxonID, FxHashMap<GOTermID, GOTermResults>>>
+) -> FxHashMap<TaxonID, FxHashMap<GOTermID, GOTermResults>> {
+    let mut combined: FxHashMap<TaxonID, FxHashMap<GOTermID, (GOTermResults, Vec<f64>)>> = FxHashMap::default();
+
+    for replicate in replicates {
+        for (taxon_id, go_terms) in replicate {
+            let taxon_terms = combined.entry(taxon_id).or_default();
+            for (go_term, results) in go_terms {
+                let p_value = results.p_value;
+                taxon_terms
+                    .entry(go_term)
+                    .or_insert_with(|| (results, Vec::new()))
+                    .1
+                    .push(p_value);
+            }
+        }
+    }
+
+    combined
+        .into_iter()
+        .map(|(taxon_id, go_terms)| {
+            let taxon_results = go_terms
+                .into_iter()
+                .map(|(go_term, (mut results, mut p_values))| {
+                    p_values.sort_by(f64::total_cmp);
+                    let middle = p_values.len() / 2;
+                    results.p_value = if p_values.len() % 2 == 0 {
+                        (p_values[middle - 1] + p_values[middle]) / 2.0
+                    } else {
+                        p_values[middle]
+                    };
+                    (go_term, results)
+                })
+                .collect();
+            (taxon_id, taxon_results)
+        })
+        .collect()
 }
 
 pub struct EnrichmentAnalysis {
     pub test_type: StatisticalTest,
+    pub beta_prior: BetaPrior,
 }
 
 impl EnrichmentAnalysis {
     pub fn new(
         test_type: StatisticalTest) -> Self {
-        Self { test_type }
+        Self { test_type, beta_prior: BetaPrior::default() }
+    }
+
+    pub fn with_beta_prior(mut self, beta_prior: BetaPrior) -> Self {
+        self.beta_prior = beta_prior;
+        self
+    }
+
+    // The Beta-Binomial test reports its log Bayes factor in place of the log(Odds Ratio).
+    pub fn term_results(&self, contingency_table: ContingencyTable) -> GOTermResults {
+        let (log_odds_ratio, p_value) = match self.test_type {
+            StatisticalTest::BetaBinomial => beta_binomial_test(&contingency_table, &self.beta_prior),
+            test_type => (
+                calculate_log_odds_ratio(&contingency_table),
+                calculate_p_value(&contingency_table, test_type)
+            ),
+        };
+
+        GOTermResults {
+            log_odds_ratio,
+            p_value,
+            contingency_table: Some(contingency_table),
+            study_count: contingency_table[0],
+            variance: calculate_variance(&contingency_table),
+        }
+    }
+
+    pub fn analyze_single_go_term(
+        &self,
+        study_with_go: usize,
+        background_with_go: usize,
+        total_study: usize,
+        total_background: usize,
+    ) -> GOTermResults {
+        self.term_results(create_contingency_table(
+            study_with_go,
+            background_with_go,
+            total_study,
+            total_background,
+        ))
     }
 
     pub fn classic(
@@ -140,6 +341,55 @@ impl EnrichmentAnalysis {
         self.calculate_statistics(&contingency_tables)
     }
 
+    pub fn kolmogorov_smirnov(
+        &self,
+        taxon_ids: &FxHashSet<TaxonID>,
+        study_pop: &StudyPop,
+    ) -> FxHashMap<TaxonID, FxHashMap<GOTermID, GOTermResults>> {
+        taxon_ids.par_iter()
+            .filter_map(|&taxon_id| {
+                let scores = study_pop.protein_scores.get(&taxon_id)?;
+                let go_term_proteins = study_pop.go_term_to_protein_set.get(&taxon_id)?;
+
+                let term_results = go_term_proteins
+                    .iter()
+                    .filter_map(|(&go_id, proteins)| {
+                        let mut term_scores = Vec::with_capacity(proteins.len());
+                        let mut other_scores = Vec::with_capacity(scores.len());
+
+                        for (protein, &score) in scores {
+                            if proteins.contains(protein) {
+                                term_scores.push(score);
+                            } else {
+                                other_scores.push(score);
+                            }
+                        }
+
+                        if term_scores.is_empty() || other_scores.is_empty() {
+                            return None;
+                        }
+
+                        term_scores.sort_by(f64::total_cmp);
+                        other_scores.sort_by(f64::total_cmp);
+
+                        let (d_statistic, p_value) = one_sided_kolmogorov_smirnov_test(&term_scores, &other_scores);
+                        let (n_term, n_other) = (term_scores.len(), other_scores.len());
+
+                        Some((go_id, GOTermResults {
+                            log_odds_ratio: d_statistic,
+                            p_value,
+                            contingency_table: None,
+                            study_count: n_term,
+                            variance: (n_term + n_other) as f64 / (n_term * n_other) as f64,
+                        }))
+                    })
+                    .collect();
+
+                Some((taxon_id, term_results))
+            })
+            .collect()
+    }
+
     fn create_contingency_tables(
         &self,
         taxon_ids: &FxHashSet<u32>,
@@ -213,18 +463,85 @@ impl EnrichmentAnalysis {
             .map(|(&taxon_id, go_terms)| {
                 let term_results = go_terms
                     .iter()
-                    .map(|(&go_id, counts)| {
-                        let stats = GOTermResults {
-                            log_odds_ratio: calculate_log_odds_ratio(counts),
-                            p_value: calculate_p_value(counts, self.test_type),
-                            contingency_table: *counts,
-                            variance: calculate_variance(counts),
-                        };
-                        (go_id, stats)
-                    })
+                    .map(|(&go_id, counts)| (go_id, self.term_results(*counts)))
                     .collect();
                 (taxon_id, term_results)
             })
             .collect()
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn two_tailed_fishers_doubles_a_symmetric_one_tailed_p_value() {
+        // Both margins are 10/10, so the null distribution of the first cell is symmetric around 5.
+        let counts = [8, 2, 2, 8];
+        let one_tailed = fishers_test(&counts);
+        let two_tailed = fishers_two_tailed_test(&counts);
+
+        assert!((one_tailed - 0.011507).abs() < 1e-6, "got {}", one_tailed);
+        assert!((two_tailed - 2.0 * one_tailed).abs() < 1e-9, "got {}", two_tailed);
+    }
+
+    #[test]
+    fn two_tailed_fishers_of_an_asymmetric_table() {
+        // Dieting example from Fisher's exact test references: two-sided p = 0.002759.
+        let two_tailed = fishers_two_tailed_test(&[1, 9, 11, 3]);
+        assert!((two_tailed - 0.002759).abs() < 1e-6, "got {}", two_tailed);
+    }
+
+    #[test]
+    fn two_tailed_fishers_detects_depletion() {
+        let counts = [1, 9, 11, 3];
+        assert!(fishers_test(&counts) > 0.99);
+        assert!(calculate_p_value(&counts, StatisticalTest::FishersTwoTailed) < 0.01);
+    }
+
+    #[test]
+    fn pearson_residuals_of_a_known_table() {
+        // Expected counts are [12, 18, 28, 42], so every cell is off by 2.
+        let residuals = calculate_pearson_residuals(&[10, 20, 30, 40]);
+        let expected = [
+            -2.0 / 12.0_f64.sqrt(),
+            2.0 / 18.0_f64.sqrt(),
+            2.0 / 28.0_f64.sqrt(),
+            -2.0 / 42.0_f64.sqrt(),
+        ];
+
+        for (residual, expected) in residuals.iter().zip(expected.iter()) {
+            assert!((residual - expected).abs() < 1e-12, "got {:?}", residuals);
+        }
+
+        // The squared residuals add up to Pearson's chi-squared statistic.
+        let chi_squared: f64 = residuals.iter().map(|residual| residual * residual).sum();
+        assert!((chi_squared - 0.7936507936507937).abs() < 1e-12, "got {}", chi_squared);
+    }
+
+    #[test]
+    fn pearson_residuals_of_an_empty_table() {
+        assert_eq!(calculate_pearson_residuals(&[0, 0, 0, 0]), [0.0; 4]);
+    }
+
+    #[test]
+    fn all_zero_cells_give_finite_estimates() {
+        let counts = [0, 0, 0, 0];
+        assert_eq!(calculate_log_odds_ratio(&counts), 0.0);
+        assert_eq!(calculate_variance(&counts), 8.0);
+    }
+
+    #[test]
+    fn a_single_zero_cell_is_replaced_by_one_half() {
+        let counts = [0, 5, 5, 5];
+        assert!((calculate_log_odds_ratio(&counts) - (0.5_f64 * 5.0 / 25.0).ln()).abs() < 1e-12);
+        assert!((calculate_variance(&counts) - 2.6).abs() < 1e-12);
+    }
+
+    #[test]
+    fn non_zero_cells_are_left_unchanged() {
+        let counts = [2, 4, 8, 16];
+        assert_eq!(calculate_log_odds_ratio(&counts), 0.0);
+        assert!((calculate_variance(&counts) - (0.5 + 0.25 + 0.125 + 0.0625)).abs() < 1e-12);
+    }
+}
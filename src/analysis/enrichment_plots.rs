@@ -2,17 +2,17 @@ use rustc_hash::{
     FxHashMap, 
     FxHashSet
 };
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::error::Error;
 use std::fs;
 use plotly::{
-    Plot, Bar, Layout, Scatter, Trace,
+    Plot, Bar, Layout, Scatter, Trace, HeatMap,
     common::{
         Title, Font, HoverInfo,
-        ColorScale, ColorScalePalette,
+        ColorScale, ColorScalePalette, ColorScaleElement,
         Marker, ColorBar, Anchor, Side,
         ThicknessMode, Orientation, Mode,
-        Line, LegendGroupTitle
+        Line, LegendGroupTitle, DashType
     },
     layout::{
         Axis, Margin, Legend,
@@ -25,6 +25,7 @@ use plotly::{
     ImageFormat
 };
 use textwrap::wrap;
+use serde_json::{json, Value};
 use std::cmp::Ordering::Equal;
 use crate::{
     parsers::{
@@ -34,7 +35,9 @@ use crate::{
     },
     analysis::{
         enrichment_analysis::*,
-        phylogenetic_meta_analysis::*
+        phylogenetic_meta_analysis::*,
+        network_centrality::calculate_betweenness_centrality,
+        write_results::sanitize_taxon_name
     }
 };
 use petgraph::{
@@ -67,6 +70,12 @@ pub enum PlotType {
     Both
 }
 
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq)]
+pub enum NetworkColor {
+    LogOddsRatio,
+    Betweenness
+}
+
 const PLOT_WIDTH: f32 = 10.0;
 const PLOT_HEIGHT: f32 = 6.0;
 const COLS: usize = 2;
@@ -83,6 +92,27 @@ const QUADRANT_DEFINITIONS: [(f32, f32); QUADRANTS] = [
     (1.0 * QUADRANT_WIDTH, 0.0 * QUADRANT_HEIGHT),
 ];
 
+// Fruchterman-Reingold settings for the network plots. Larger networks usually need a larger scale
+// and more iterations to avoid compressing the nodes together.
+#[derive(Debug, Clone, Copy)]
+pub struct FrLayoutConfig {
+    pub iterations: usize,
+    pub scale: f32,
+    pub dt: f32,
+    pub cooloff_factor: f32,
+}
+
+impl Default for FrLayoutConfig {
+    fn default() -> Self {
+        FrLayoutConfig {
+            iterations: 5000,
+            scale: 50.0,
+            dt: 0.02,
+            cooloff_factor: 0.975,
+        }
+    }
+}
+
 pub type JaccardIndex = f32;
 pub type GoTermNetworkGraph = StableGraph<GOTermPlotData, JaccardIndex, Directed>;
 pub type LayoutGraph = ForceGraph<f32, 2, GOTermPlotData, JaccardIndex, Directed>;
@@ -98,6 +128,19 @@ pub struct GOTermPlotData {
     pub size_statistic: usize,
     pub namespace: NameSpace,
     pub hover_text: String,
+    pub betweenness: f64,
+}
+
+// Jaccard index of the proteins annotated to two GO terms, or 0 when neither has any.
+pub fn jaccard_index(proteins1: &FxHashSet<Protein>, proteins2: &FxHashSet<Protein>) -> f64 {
+    let intersection_size = proteins1.intersection(proteins2).count();
+    let union_size = proteins1.len() + proteins2.len() - intersection_size;
+
+    if union_size == 0 {
+        return 0.0;
+    }
+
+    intersection_size as f64 / union_size as f64
 }
 
 pub trait EnrichmentResult {
@@ -115,7 +158,7 @@ impl EnrichmentResult for GOTermResults {
         self.p_value
     }
     fn size(&self) -> usize {
-        self.contingency_table[0]
+        self.study_count
     }
 }
 
@@ -145,7 +188,7 @@ pub enum ProteinDataProvider<'a> {
 }
 
 impl<'a> ProteinDataProvider<'a> {
-    fn get_proteins_for_taxon(
+    pub fn get_proteins_for_taxon(
         &self,
         taxon_name: &str,
         relevant_go_ids: &FxHashSet<GOTermID>,
@@ -227,13 +270,8 @@ fn create_edge_width_legend_trace(
 }
 
 
-fn get_namespace_subdir(namespace: &NameSpace, plots_dir: &PathBuf) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
-    let namespace_str: String = match namespace {
-        NameSpace::BiologicalProcess => "Biological_Process".to_string(),
-        NameSpace::MolecularFunction => "Molecular_Function".to_string(),
-        NameSpace::CellularComponent => "Cellular_Component".to_string(),
-    };
-    let namespace_subdir: PathBuf = plots_dir.join(&namespace_str);
+pub(crate) fn get_namespace_subdir(namespace: &NameSpace, plots_dir: &Path) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+    let namespace_subdir: PathBuf = plots_dir.join(namespace.file_label());
     fs::create_dir_all(&namespace_subdir)?;
     Ok(namespace_subdir)
 }
@@ -270,7 +308,9 @@ pub fn process_species_data(
 
 pub fn prepare_plot_data<R>(
     significant_results: &FxHashMap<String, FxHashMap<GOTermID, R>>,
-    ontology: &OboMap
+    ontology: &OboMap,
+    max_terms_per_namespace: usize,
+    effect_label: &str
 ) -> FxHashMap<String, FxHashMap<NameSpace, Vec<GOTermPlotData>>>
 where
     R: EnrichmentResult + Clone + Send + Sync
@@ -293,9 +333,10 @@ where
                     let term_namespace = obo_term.namespace.clone();
 
                     let hover_html_content = format!(
-                        "<b>Term Name:</b> {}<br><b>Term ID:</b> {}<br><b>log(Odds Ratio):</b> {:.3}<br><b>-log10(Stat. Sig.):</b> {:.3}",
+                        "<b>Term Name:</b> {}<br><b>Term ID:</b> {}<br><b>{}:</b> {:.3}<br><b>-log10(Stat. Sig.):</b> {:.3}",
                         original_name,
                         go_id_string,
+                        effect_label,
                         current_lor,
                         minus_log_10_p,
                     );
@@ -310,6 +351,7 @@ where
                         size_statistic: size_stat,
                         namespace: term_namespace.clone(),
                         hover_text: hover_html_content,
+                        betweenness: 0.0,
                     };
 
                     terms_by_namespace
@@ -323,6 +365,9 @@ where
                 terms_vec.sort_by(|a, b| {
                     a.stat_sig.partial_cmp(&b.stat_sig).unwrap_or(Equal)
                 });
+                if max_terms_per_namespace > 0 {
+                    terms_vec.truncate(max_terms_per_namespace);
+                }
             }
             Some((species_name.clone(), terms_by_namespace))
         })
@@ -331,8 +376,9 @@ where
 
 pub fn bar_plot(
     plot_data_map: &FxHashMap<String, FxHashMap<NameSpace, Vec<GOTermPlotData>>>,
-    plots_dir: &PathBuf,
-    plot_type: PlotType
+    plots_dir: &Path,
+    plot_type: PlotType,
+    effect_label: &str
 ) -> Result<(), Box<dyn Error + Send + Sync>> {
 
     plot_data_map
@@ -411,7 +457,7 @@ pub fn bar_plot(
                     .bottom(0))
                 .x_axis(
                     Axis::new()
-                        .title(Title::with_text("log(Odds Ratio)").font(Font::new().size(12)))
+                        .title(Title::with_text(effect_label).font(Font::new().size(12)))
                         .tick_font(Font::new().size(10))
                         .show_line(true)
                         .line_color(NamedColor::Black)
@@ -437,19 +483,19 @@ pub fn bar_plot(
             
             match plot_type {
                 PlotType::Interactive => {
-                    let html_file = namespace_subdir.join(format!("{}_bar_plot.html", sanitize_filename(&taxon_name)));
+                    let html_file = namespace_subdir.join(format!("{}_bar_plot.html", sanitize_taxon_name(&taxon_name)));
                     plot.write_html(html_file); 
                 }
                 PlotType::Static => {
-                    let svg_file = namespace_subdir.join(format!("{}_bar_plot.svg", taxon_name));
-                    plot.write_image(svg_file, ImageFormat::PDF, 940, 460, 1.0);
+                    let svg_file = namespace_subdir.join(format!("{}_bar_plot.svg", sanitize_taxon_name(&taxon_name)));
+                    plot.write_image(svg_file, ImageFormat::SVG, 940, 460, 1.0);
                 }
                 PlotType::Both => {
-                    let html_file = namespace_subdir.join(format!("{}_bar_plot.html", sanitize_filename(&taxon_name)));
+                    let html_file = namespace_subdir.join(format!("{}_bar_plot.html", sanitize_taxon_name(&taxon_name)));
                     plot.write_html(html_file); 
 
-                    let svg_file = namespace_subdir.join(format!("{}_bar_plot.svg", taxon_name));
-                    plot.write_image(svg_file, ImageFormat::PDF, 940, 460, 1.0);
+                    let svg_file = namespace_subdir.join(format!("{}_bar_plot.svg", sanitize_taxon_name(&taxon_name)));
+                    plot.write_image(svg_file, ImageFormat::SVG, 940, 460, 1.0);
                 }
                 PlotType::None => {}
 
@@ -461,9 +507,10 @@ pub fn bar_plot(
 }
 
 pub fn bubble_plot(
-    plot_data_map: FxHashMap<String, FxHashMap<NameSpace, Vec<GOTermPlotData>>>,
-    plots_dir: &PathBuf,
-    plot_type: PlotType
+    plot_data_map: &FxHashMap<String, FxHashMap<NameSpace, Vec<GOTermPlotData>>>,
+    plots_dir: &Path,
+    plot_type: PlotType,
+    effect_label: &str
 ) -> Result<(), Box<dyn Error + Send + Sync>> {
     plot_data_map
         .into_iter()
@@ -476,7 +523,7 @@ pub fn bubble_plot(
         })
         .par_bridge()
         .try_for_each(|(taxon_name, namespace, namespace_plot_data)| -> Result<(), Box<dyn Error + Send + Sync>> {
-            let namespace_subdir = get_namespace_subdir(&namespace, plots_dir)?;
+            let namespace_subdir = get_namespace_subdir(namespace, plots_dir)?;
 
             let enrichment_values: Vec<f64> = namespace_plot_data.iter().map(|t| t.lor).collect();
             let stat_sig_values: Vec<f64> = namespace_plot_data.iter().map(|t| t.minus_log10_p_value).collect();
@@ -597,7 +644,7 @@ pub fn bubble_plot(
                     .bottom(15))
                 .x_axis(
                     Axis::new()
-                        .title(Title::with_text("log(Odds Ratio)").font(Font::new().size(12)))
+                        .title(Title::with_text(effect_label).font(Font::new().size(12)))
                         .tick_font(Font::new().size(10))
                         .show_line(true)
                         .line_color(NamedColor::Black)
@@ -634,18 +681,18 @@ pub fn bubble_plot(
 
             match plot_type {
                 PlotType::Interactive => {
-                    let html_file = namespace_subdir.join(format!("{}_bubble_plot.html", sanitize_filename(&taxon_name)));
+                    let html_file = namespace_subdir.join(format!("{}_bubble_plot.html", sanitize_taxon_name(&taxon_name)));
                     plot.write_html(html_file); 
                 }
                 PlotType::Static => {
-                    let svg_file = namespace_subdir.join(format!("{}_bubble_plot.svg", taxon_name));
+                    let svg_file = namespace_subdir.join(format!("{}_bubble_plot.svg", sanitize_taxon_name(&taxon_name)));
                     plot.write_image(svg_file, ImageFormat::SVG, 940, 460, 1.0);
                 }
                 PlotType::Both => {
-                    let html_file = namespace_subdir.join(format!("{}_bubble_plot.html", sanitize_filename(&taxon_name)));
+                    let html_file = namespace_subdir.join(format!("{}_bubble_plot.html", sanitize_taxon_name(&taxon_name)));
                     plot.write_html(html_file); 
 
-                    let svg_file = namespace_subdir.join(format!("{}_bubble_plot.svg", taxon_name));
+                    let svg_file = namespace_subdir.join(format!("{}_bubble_plot.svg", sanitize_taxon_name(&taxon_name)));
                     plot.write_image(svg_file, ImageFormat::SVG, 940, 460, 1.0);
                 }
                 PlotType::None => {}
@@ -658,6 +705,337 @@ pub fn bubble_plot(
     Ok(())
 }
 
+fn namespace_color(namespace: &NameSpace) -> Rgb {
+    match namespace {
+        NameSpace::BiologicalProcess => Rgb::new(31, 119, 180),
+        NameSpace::MolecularFunction => Rgb::new(255, 127, 14),
+        NameSpace::CellularComponent => Rgb::new(44, 160, 44),
+    }
+}
+
+pub fn volcano_plot(
+    plot_data_map: &FxHashMap<String, FxHashMap<NameSpace, Vec<GOTermPlotData>>>,
+    plots_dir: &Path,
+    plot_type: PlotType,
+    significance_threshold: f64,
+    log_odds_ratio_threshold: f64,
+    effect_label: &str
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    plot_data_map
+        .into_iter()
+        .flat_map(|(taxon_name, namespace_map)| {
+            namespace_map
+                .into_iter()
+                .map(move |(namespace, current_plot_data)| {
+                    (taxon_name.clone(), namespace, current_plot_data)
+                })
+        })
+        .par_bridge()
+        .try_for_each(|(taxon_name, namespace, namespace_plot_data)| -> Result<(), Box<dyn Error + Send + Sync>> {
+            let namespace_subdir = get_namespace_subdir(namespace, plots_dir)?;
+
+            let enrichment_values: Vec<f64> = namespace_plot_data.iter().map(|t| t.lor).collect();
+            let stat_sig_values: Vec<f64> = namespace_plot_data.iter().map(|t| t.minus_log10_p_value).collect();
+            let hover_texts: Vec<String> = namespace_plot_data.iter().map(|t| t.hover_text.clone()).collect();
+
+            let minus_log10_alpha = if significance_threshold > 0.0 { -significance_threshold.log10() } else { 0.0 };
+
+            let min_lor = enrichment_values
+                .iter()
+                .copied()
+                .fold(log_odds_ratio_threshold, f64::min);
+            let max_lor = enrichment_values
+                .iter()
+                .copied()
+                .fold(log_odds_ratio_threshold, f64::max);
+            let max_stat_sig = stat_sig_values
+                .iter()
+                .copied()
+                .fold(minus_log10_alpha, f64::max);
+
+            let mut plot = Plot::new();
+
+            let scatter_trace = Scatter::new(enrichment_values, stat_sig_values)
+                .mode(Mode::Markers)
+                .marker(
+                    Marker::new()
+                        .color(namespace_color(namespace))
+                        .size(10)
+                        .opacity(0.8),
+                )
+                .hover_text_array(hover_texts)
+                .hover_info(HoverInfo::Text)
+                .show_legend(false);
+            plot.add_trace(scatter_trace);
+
+            let threshold_line = Line::new()
+                .width(1.5)
+                .color(NamedColor::Gray)
+                .dash(DashType::Dash);
+
+            let significance_trace = Scatter::new(vec![min_lor, max_lor], vec![minus_log10_alpha, minus_log10_alpha])
+                .mode(Mode::Lines)
+                .line(threshold_line.clone())
+                .hover_info(HoverInfo::Skip)
+                .show_legend(false);
+            plot.add_trace(significance_trace);
+
+            let score_trace = Scatter::new(vec![log_odds_ratio_threshold, log_odds_ratio_threshold], vec![0.0, max_stat_sig])
+                .mode(Mode::Lines)
+                .line(threshold_line)
+                .hover_info(HoverInfo::Skip)
+                .show_legend(false);
+            plot.add_trace(score_trace);
+
+            let mut terms_for_sorting = namespace_plot_data.clone();
+            terms_for_sorting.sort_by(|a, b| {
+                b.minus_log10_p_value
+                    .partial_cmp(&a.minus_log10_p_value)
+                    .unwrap_or(Equal)
+            });
+
+            let text_positions_cycle = vec![
+                (-30, 20),  // top left
+                (30, 10),   // top right
+                (-30, -20), // bottom left
+                (30, -10),  // bottom right
+            ];
+
+            let annotations: Vec<Annotation> = terms_for_sorting
+                .iter()
+                .take(5)
+                .enumerate()
+                .map(|(i, term)| {
+                    let (ax_offset, ay_offset) = text_positions_cycle[i % text_positions_cycle.len()];
+                    Annotation::new()
+                        .x(term.lor)
+                        .y(term.minus_log10_p_value)
+                        .text(format!("GO:{:07}", term.go_id))
+                        .show_arrow(true)
+                        .font(Font::new().size(10).color(NamedColor::Black))
+                        .arrow_head(2)
+                        .arrow_size(1.0)
+                        .arrow_width(1.1)
+                        .arrow_color(NamedColor::Black)
+                        .ax(ax_offset)
+                        .ay(ay_offset)
+                        .opacity(0.9)
+                })
+                .collect();
+
+            let layout = Layout::new()
+                .width(940)
+                .height(460)
+                .margin(Margin::new()
+                    .left(50)
+                    .right(0)
+                    .top(30)
+                    .bottom(15))
+                .x_axis(
+                    Axis::new()
+                        .title(Title::with_text(effect_label).font(Font::new().size(12)))
+                        .tick_font(Font::new().size(10))
+                        .show_line(true)
+                        .line_color(NamedColor::Black)
+                        .show_grid(true)
+                        .grid_color(Rgba::new(0, 0, 0, 0.05))
+                        .show_tick_labels(true)
+                        .auto_margin(true),
+                )
+                .y_axis(
+                    Axis::new()
+                        .title(Title::with_text("-log10(Stat. Sig.)").font(Font::new().size(12)))
+                        .tick_font(Font::new().size(10))
+                        .show_line(true)
+                        .line_color(NamedColor::Black)
+                        .show_grid(true)
+                        .grid_color(Rgba::new(0, 0, 0, 0.05))
+                        .show_tick_labels(true)
+                        .auto_margin(true)
+                        .range_mode(RangeMode::ToZero),
+                )
+                .annotations(annotations);
+
+            plot.set_layout(layout);
+
+            match plot_type {
+                PlotType::Interactive => {
+                    let html_file = namespace_subdir.join(format!("{}_volcano_plot.html", sanitize_taxon_name(&taxon_name)));
+                    plot.write_html(html_file);
+                }
+                PlotType::Static => {
+                    let svg_file = namespace_subdir.join(format!("{}_volcano_plot.svg", sanitize_taxon_name(&taxon_name)));
+                    plot.write_image(svg_file, ImageFormat::SVG, 940, 460, 1.0);
+                }
+                PlotType::Both => {
+                    let html_file = namespace_subdir.join(format!("{}_volcano_plot.html", sanitize_taxon_name(&taxon_name)));
+                    plot.write_html(html_file);
+
+                    let svg_file = namespace_subdir.join(format!("{}_volcano_plot.svg", sanitize_taxon_name(&taxon_name)));
+                    plot.write_image(svg_file, ImageFormat::SVG, 940, 460, 1.0);
+                }
+                PlotType::None => {}
+            }
+
+            Ok(())
+        })?;
+
+    Ok(())
+}
+
+const HEATMAP_TOP_TERMS: usize = 20;
+
+// Cross-species view of the GO terms that are significant in the most species. Cells hold the log(Odds Ratio)
+// of a term in a species, or 0 when the term is not significant there. Each namespace gets its own panel of one
+// figure, written as `multi_species_heatmap.{html|svg}` in `results_dir`.
+pub fn multi_species_heatmap(
+    significant_results_by_name: &FxHashMap<String, FxHashMap<GOTermID, GOTermResults>>,
+    ontology: &OboMap,
+    results_dir: &Path,
+    plot_type: PlotType,
+    effect_label: &str
+) -> Result<Vec<PathBuf>, Box<dyn Error + Send + Sync>> {
+    let mut species_names: Vec<&String> = significant_results_by_name.keys().collect();
+    species_names.sort();
+    let species_labels: Vec<String> = species_names.iter().map(|species| species.to_string()).collect();
+
+    let mut panels: Vec<(NameSpace, Vec<String>, Vec<Vec<f64>>)> = Vec::new();
+
+    for namespace in [NameSpace::BiologicalProcess, NameSpace::MolecularFunction, NameSpace::CellularComponent] {
+        let mut significance_frequency: FxHashMap<GOTermID, usize> = FxHashMap::default();
+        for go_terms in significant_results_by_name.values() {
+            for go_term in go_terms.keys() {
+                if ontology.get(go_term).is_some_and(|term| term.namespace == namespace) {
+                    *significance_frequency.entry(*go_term).or_insert(0) += 1;
+                }
+            }
+        }
+        if significance_frequency.is_empty() {
+            continue;
+        }
+
+        let mut top_terms: Vec<(GOTermID, usize)> = significance_frequency.into_iter().collect();
+        top_terms.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        top_terms.truncate(HEATMAP_TOP_TERMS);
+
+        let matrix: Vec<Vec<f64>> = top_terms
+            .iter()
+            .map(|(go_term, _)| {
+                species_names
+                    .iter()
+                    .map(|species| {
+                        significant_results_by_name[*species]
+                            .get(go_term)
+                            .map_or(0.0, |result| result.log_odds_ratio)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let term_labels: Vec<String> = top_terms
+            .iter()
+            .map(|(go_term, _)| {
+                let name = ontology.get(go_term).map_or("", |term| term.name.as_str());
+                format!("{} (GO:{:07})", name, go_term)
+            })
+            .collect();
+
+        panels.push((namespace, term_labels, matrix));
+    }
+
+    if panels.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let max_abs_lor = panels
+        .iter()
+        .flat_map(|(_, _, matrix)| matrix.iter().flatten())
+        .fold(0.0_f64, |max, lor| max.max(lor.abs()))
+        .max(f64::EPSILON);
+
+    // Panels are stacked from the top, each taking a share of the height proportional to its number of terms.
+    let panel_gap = 0.04;
+    let total_rows: usize = panels.iter().map(|(_, term_labels, _)| term_labels.len()).sum();
+    let row_fraction = (1.0 - panel_gap * (panels.len() - 1) as f64) / total_rows as f64;
+
+    let width = 400 + 40 * species_labels.len();
+    let height = 200 + 30 * total_rows + 60 * (panels.len() - 1);
+
+    let mut layout = Layout::new()
+        .title(Title::with_text(format!("{} of the most frequently significant GO terms", effect_label)).font(Font::new().size(14)))
+        .width(width)
+        .height(height);
+
+    let mut plot = Plot::new();
+    let mut domain_top = 1.0;
+    let last_panel = panels.len() - 1;
+
+    for (panel_idx, (namespace, term_labels, matrix)) in panels.into_iter().enumerate() {
+        let domain_bottom = (domain_top - row_fraction * term_labels.len() as f64).max(0.0);
+        let axis_suffix = if panel_idx == 0 { String::new() } else { (panel_idx + 1).to_string() };
+
+        let trace = HeatMap::new(species_labels.clone(), term_labels, matrix)
+            .x_axis(format!("x{}", axis_suffix))
+            .y_axis(format!("y{}", axis_suffix))
+            .color_scale(ColorScale::Vector(vec![
+                ColorScaleElement(0.0, "rgb(33,102,172)".to_string()),
+                ColorScaleElement(0.5, "rgb(255,255,255)".to_string()),
+                ColorScaleElement(1.0, "rgb(178,24,43)".to_string()),
+            ]))
+            .zmin(-max_abs_lor)
+            .zmax(max_abs_lor)
+            .show_scale(panel_idx == 0)
+            .hover_template(format!("%{{y}}<br>%{{x}}<br>{}: %{{z:.3f}}<extra></extra>", effect_label));
+        plot.add_trace(trace);
+
+        let x_axis = Axis::new()
+            .anchor(format!("y{}", axis_suffix))
+            .tick_angle(-45.0)
+            .show_tick_labels(panel_idx == last_panel)
+            .auto_margin(true);
+        let y_axis = Axis::new()
+            .anchor(format!("x{}", axis_suffix))
+            .domain(&[domain_bottom, domain_top])
+            .title(Title::with_text(namespace.file_label().replace('_', " ")).font(Font::new().size(12)))
+            .auto_margin(true);
+
+        layout = match panel_idx {
+            0 => layout.x_axis(x_axis).y_axis(y_axis),
+            1 => layout.x_axis2(x_axis).y_axis2(y_axis),
+            _ => layout.x_axis3(x_axis).y_axis3(y_axis),
+        };
+
+        domain_top = domain_bottom - panel_gap;
+    }
+
+    plot.set_layout(layout);
+
+    fs::create_dir_all(results_dir)?;
+    let html_file = results_dir.join("multi_species_heatmap.html");
+    let svg_file = results_dir.join("multi_species_heatmap.svg");
+    let mut written_files: Vec<PathBuf> = Vec::new();
+
+    match plot_type {
+        PlotType::Interactive => {
+            plot.write_html(&html_file);
+            written_files.push(html_file);
+        }
+        PlotType::Static => {
+            plot.write_image(&svg_file, ImageFormat::SVG, width, height, 1.0);
+            written_files.push(svg_file);
+        }
+        PlotType::Both => {
+            plot.write_html(&html_file);
+            plot.write_image(&svg_file, ImageFormat::SVG, width, height, 1.0);
+            written_files.push(html_file);
+            written_files.push(svg_file);
+        }
+        PlotType::None => {}
+    }
+
+    Ok(written_files)
+}
+
 pub fn prepare_network_data<R>(
     significant_results: &FxHashMap<String, FxHashMap<GOTermID, R>>,
     protein_provider: &ProteinDataProvider,
@@ -701,6 +1079,8 @@ pub fn build_networks<R>(
     network_data: &FxHashMap<String, FxHashMap<NameSpace, GOTermToProteinSet>>,
     enrichment_results: &FxHashMap<String, FxHashMap<GOTermID, R>>,
     ontology: &OboMap,
+    jaccard_threshold: f64,
+    effect_label: &str,
 ) -> FxHashMap<String, FxHashMap<NameSpace, Vec<GoTermNetworkGraph>>> 
 where 
     R: EnrichmentResult + Clone + Send + Sync
@@ -737,8 +1117,8 @@ where
                                 let size = enrichment_detail.size();
 
                                 let hover_text = format!(
-                                    "<b>Term Name:</b> {}<br><b>Term ID:</b> {}<br><b>log(Odds Ratio):</b> {:.3}<br><b>-log10(Stat. Sig.):</b> {:.3}",
-                                    name, go_id_str, lor, minus_log10_p
+                                    "<b>Term Name:</b> {}<br><b>Term ID:</b> {}<br><b>{}:</b> {:.3}<br><b>-log10(Stat. Sig.):</b> {:.3}",
+                                    name, go_id_str, effect_label, lor, minus_log10_p
                                 );
 
                                 let node_data = GOTermPlotData {
@@ -751,6 +1131,7 @@ where
                                     size_statistic: size,
                                     namespace: obo_term.namespace.clone(), 
                                     hover_text,
+                                    betweenness: 0.0,
                                 };
 
                             let node_idx = current_namespace_network.add_node(node_data);
@@ -761,10 +1142,8 @@ where
                         
 
                         let mut protein_to_terms_map: FxHashMap<&Protein, FxHashSet<GOTermID>> = FxHashMap::default();
-                        let mut term_node_sizes: FxHashMap<GOTermID, usize> = FxHashMap::default();
 
                         for (term_id, protein_set) in &term_to_proteins_map_for_nodes {
-                            term_node_sizes.insert(*term_id, protein_set.len());
                             for protein in protein_set.iter() {
                                 protein_to_terms_map
                                     .entry(protein)
@@ -790,19 +1169,12 @@ where
                         }
 
                         for (term1_id, term2_id) in candidate_go_pairs {
-                            let size1 = *term_node_sizes.get(&term1_id).unwrap();
-                            let size2 = *term_node_sizes.get(&term2_id).unwrap();
-
                             let proteins1 = term_to_proteins_map_for_nodes.get(&term1_id).unwrap();
                             let proteins2 = term_to_proteins_map_for_nodes.get(&term2_id).unwrap();
 
-                            let intersection_size = proteins1.intersection(proteins2).count();
-                            let union_size = size1 + size2 - intersection_size;
-
-                            let jaccard_similarity: JaccardIndex =
-                                (intersection_size as f32) / (union_size as f32);
+                            let jaccard_similarity = jaccard_index(proteins1, proteins2) as JaccardIndex;
 
-                            if jaccard_similarity >= 0.25 {
+                            if jaccard_similarity as f64 >= jaccard_threshold {
                                 let &node_idx1= term_to_node_index_map.get(&term1_id).unwrap();
                                 let &node_idx2= term_to_node_index_map.get(&term2_id).unwrap();
                                 
@@ -811,7 +1183,23 @@ where
 
                         }
                         
-                        let top_k_subgraphs = extract_top_k_communities(&current_namespace_network, 4);
+                        let mut top_k_subgraphs = extract_top_k_communities(&current_namespace_network, 4);
+
+                        let modularity = network_stats(&top_k_subgraphs).modularity;
+                        for subgraph in top_k_subgraphs.iter_mut() {
+                            let betweenness = calculate_betweenness_centrality(subgraph);
+                            for (node_idx, node_betweenness) in betweenness {
+                                if let Some(node_data) = subgraph.node_weight_mut(node_idx) {
+                                    node_data.betweenness = node_betweenness;
+                                }
+                            }
+                            for node_data in subgraph.node_weights_mut() {
+                                node_data.hover_text.push_str(&format!(
+                                    "<br><b>Betweenness:</b> {:.3}<br><b>Network modularity:</b> {:.3}",
+                                    node_data.betweenness, modularity
+                                ));
+                            }
+                        }
                         taxon_networks_graphs.insert(current_namespace.clone(), top_k_subgraphs);
                     }
                 }
@@ -837,7 +1225,7 @@ fn get_all_connected_components(
 
             while let Some(u_idx) = queue.pop_front() {
                 current_component_nodes.push(u_idx);
-                for neighbor_idx in graph.neighbors(u_idx) {
+                for neighbor_idx in graph.neighbors_undirected(u_idx) {
                     if !visited_nodes.contains(&neighbor_idx) {
                         visited_nodes.insert(neighbor_idx);
                         queue.push_back(neighbor_idx);
@@ -885,11 +1273,9 @@ fn extract_top_k_communities(
             for edge_ref in graph.edges(old_u_idx) {
                 let old_v_idx = edge_ref.target();
                 if component_node_set.contains(&old_v_idx) {
-                    if old_u_idx <= old_v_idx {
-                        if let Some(&new_v_idx) = old_to_new_node_map.get(&old_v_idx) {
-                            if let Some(edge_weight) = graph.edge_weight(edge_ref.id()) {
-                                subgraph.add_edge(new_u_idx, new_v_idx, *edge_weight);
-                            }
+                    if let Some(&new_v_idx) = old_to_new_node_map.get(&old_v_idx) {
+                        if let Some(edge_weight) = graph.edge_weight(edge_ref.id()) {
+                            subgraph.add_edge(new_u_idx, new_v_idx, *edge_weight);
                         }
                     }
                 }
@@ -913,9 +1299,74 @@ fn extract_top_k_communities(
     top_k_graphs
 }
 
-fn apply_fruchterman_reingold_layout(
+#[derive(Debug, Clone)]
+pub struct NetworkStats {
+    pub modularity: f64,
+    // (GO term, community, degree) for every node, with communities numbered from 1.
+    pub node_communities: Vec<(GOTermID, usize, usize)>,
+}
+
+// Modularity Q of the partition of the plotted network into its communities. The communities are
+// connected components, so no edges run between them and Q reduces to Σ_c [L_c / m - (d_c / 2m)²].
+pub fn network_stats(communities: &[GoTermNetworkGraph]) -> NetworkStats {
+    let total_edges: usize = communities.iter().map(|graph| graph.edge_count()).sum();
+    let mut modularity = 0.0;
+    let mut node_communities = Vec::new();
+
+    for (community, graph) in communities.iter().enumerate() {
+        let mut community_degree = 0;
+
+        for node_idx in graph.node_indices() {
+            let degree = graph.neighbors_undirected(node_idx).count();
+            community_degree += degree;
+            node_communities.push((graph[node_idx].go_id, community + 1, degree));
+        }
+
+        if total_edges > 0 {
+            let m = total_edges as f64;
+            modularity += graph.edge_count() as f64 / m - (community_degree as f64 / (2.0 * m)).powi(2);
+        }
+    }
+
+    NetworkStats { modularity, node_communities }
+}
+
+pub fn write_network_communities(
+    top_networks_map: &FxHashMap<String, FxHashMap<NameSpace, Vec<GoTermNetworkGraph>>>,
+    results_dir: &PathBuf
+) -> Result<(), Box<dyn Error>> {
+    fs::create_dir_all(results_dir)?;
+
+    for (taxon_name, namespace_map) in top_networks_map {
+        for (namespace, networks_vec) in namespace_map {
+            if networks_vec.is_empty() {
+                continue;
+            }
+
+            let namespace_str = namespace.file_label();
+
+            let stats = network_stats(networks_vec);
+
+            let mut content = String::from("GO_ID\tCommunity_ID\tDegree\n");
+            for (go_id, community, degree) in &stats.node_communities {
+                content.push_str(&format!("GO:{:07}\t{}\t{}\n", go_id, community, degree));
+            }
+
+            let communities_file = results_dir.join(format!(
+                "{}_{}_communities.tsv",
+                sanitize_taxon_name(taxon_name),
+                namespace_str
+            ));
+            fs::write(communities_file, content)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) fn apply_fruchterman_reingold_layout(
     original_graph: &GoTermNetworkGraph,
-    iterations: usize,
+    layout_config: &FrLayoutConfig,
 ) -> ForceGraph<f32, 2, GOTermPlotData, JaccardIndex, Directed> {
 
     let mut force_layout_graph=
@@ -926,14 +1377,14 @@ fn apply_fruchterman_reingold_layout(
 
     let mut fr_force = FruchtermanReingold {
         conf: FruchtermanReingoldConfiguration {
-            dt: 0.02,
-            cooloff_factor: 0.975,
-            scale: 50.0,
+            dt: layout_config.dt,
+            cooloff_factor: layout_config.cooloff_factor,
+            scale: layout_config.scale,
         },
         ..Default::default()
     };
 
-    fr_force.apply_many(&mut force_layout_graph, iterations);
+    fr_force.apply_many(&mut force_layout_graph, layout_config.iterations);
     Center::default().apply(&mut force_layout_graph);
 
     force_layout_graph
@@ -941,8 +1392,11 @@ fn apply_fruchterman_reingold_layout(
 
 pub fn network_plot(
     top_networks_map: &FxHashMap<String, FxHashMap<NameSpace, Vec<GoTermNetworkGraph>>>,
-    plots_dir: &PathBuf,
-    plot_type: PlotType
+    plots_dir: &Path,
+    plot_type: PlotType,
+    color_by: NetworkColor,
+    layout_config: &FrLayoutConfig,
+    effect_label: &str
 ) -> Result<(), Box<dyn Error + Send + Sync>> {
 
     let mut network_layouts_map: FxHashMap<String, FxHashMap<NameSpace, Vec<LayoutGraph>>> =
@@ -956,7 +1410,7 @@ pub fn network_plot(
                             let layouts_for_namespace: Vec<LayoutGraph> = networks_vec
                                 .iter()
                                 .map(|network_graph| {
-                                    apply_fruchterman_reingold_layout(network_graph, 5000)
+                                    apply_fruchterman_reingold_layout(network_graph, layout_config)
                                 })
                                 .collect();
                             (*namespace, layouts_for_namespace)
@@ -1144,7 +1598,10 @@ pub fn network_plot(
                             all_nodes_x.push(location.x);
                             all_nodes_y.push(location.y);
                             all_nodes_hover_text.push(node_plot_data.hover_text.clone());
-                            all_nodes_color_values.push(node_plot_data.lor);
+                            all_nodes_color_values.push(match color_by {
+                                NetworkColor::LogOddsRatio => node_plot_data.lor,
+                                NetworkColor::Betweenness => node_plot_data.betweenness,
+                            });
                             all_nodes_sizes.push(node_plot_data.size_statistic as f64);
                             
                             node_info_for_sorting_annotations.push(NodeAnnotationInfo {
@@ -1250,7 +1707,10 @@ pub fn network_plot(
 
                     let color_bar = ColorBar::new()
                         .title(
-                            Title::from("log(Odds Ratio)")
+                            Title::from(match color_by {
+                                NetworkColor::LogOddsRatio => effect_label,
+                                NetworkColor::Betweenness => "Betweenness",
+                            })
                                 .side(Side::Right)
                                 .font(Font::new().size(12)),
                         )
@@ -1336,18 +1796,18 @@ pub fn network_plot(
                     
                     match plot_type {
                         PlotType::Interactive => {
-                            let html_file = namespace_subdir.join(format!("{}_network_plot.html", sanitize_filename(&taxon_name)));
+                            let html_file = namespace_subdir.join(format!("{}_network_plot.html", sanitize_taxon_name(taxon_name)));
                             plot.write_html(html_file); 
                         }
                         PlotType::Static => {
-                            let svg_file = namespace_subdir.join(format!("{}_network_plot.svg", taxon_name));
+                            let svg_file = namespace_subdir.join(format!("{}_network_plot.svg", sanitize_taxon_name(taxon_name)));
                             plot.write_image(svg_file, ImageFormat::SVG, 940, 460, 1.0);
                         }
                         PlotType::Both => {
-                            let html_file = namespace_subdir.join(format!("{}_network_plot.html", sanitize_filename(&taxon_name)));
+                            let html_file = namespace_subdir.join(format!("{}_network_plot.html", sanitize_taxon_name(taxon_name)));
                             plot.write_html(html_file); 
         
-                            let svg_file = namespace_subdir.join(format!("{}_network_plot.svg", taxon_name));
+                            let svg_file = namespace_subdir.join(format!("{}_network_plot.svg", sanitize_taxon_name(taxon_name)));
                             plot.write_image(svg_file, ImageFormat::SVG, 940, 460, 1.0);
                         }
                         PlotType::None => {}
@@ -1361,14 +1821,278 @@ pub fn network_plot(
 Ok(())
 }
 
-fn sanitize_filename(name: &str) -> String {
-    name.replace("/", "_")
-        .replace(":", "_")
-        .replace("\\", "_")
-        .replace("*", "_")
-        .replace("?", "_")
-        .replace("\"", "_")
-        .replace("<", "_")
-        .replace(">", "_")
-        .replace("|", "_")
-}
\ No newline at end of file
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+pub fn export_network_graphml(
+    top_networks_map: &FxHashMap<String, FxHashMap<NameSpace, Vec<GoTermNetworkGraph>>>,
+    plots_dir: &Path
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+
+    top_networks_map
+        .par_iter()
+        .try_for_each(|(taxon_name, namespace_map)| {
+            namespace_map
+                .iter()
+                .try_for_each(|(namespace, networks_vec)| {
+                    if networks_vec.is_empty() {
+                        return Ok(());
+                    }
+
+                    let namespace_str = namespace.obo_name();
+
+                    let mut graphml = String::new();
+                    graphml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+                    graphml.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+                    graphml.push_str("  <key id=\"go_id\" for=\"node\" attr.name=\"go_id\" attr.type=\"string\"/>\n");
+                    graphml.push_str("  <key id=\"name\" for=\"node\" attr.name=\"name\" attr.type=\"string\"/>\n");
+                    graphml.push_str("  <key id=\"namespace\" for=\"node\" attr.name=\"namespace\" attr.type=\"string\"/>\n");
+                    graphml.push_str("  <key id=\"lor\" for=\"node\" attr.name=\"lor\" attr.type=\"double\"/>\n");
+                    graphml.push_str("  <key id=\"p_value\" for=\"node\" attr.name=\"p_value\" attr.type=\"double\"/>\n");
+                    graphml.push_str("  <key id=\"size\" for=\"node\" attr.name=\"size\" attr.type=\"int\"/>\n");
+                    graphml.push_str("  <key id=\"jaccard_index\" for=\"edge\" attr.name=\"jaccard_index\" attr.type=\"double\"/>\n");
+                    graphml.push_str(&format!(
+                        "  <graph id=\"{}\" edgedefault=\"undirected\">\n",
+                        escape_xml(taxon_name)
+                    ));
+
+                    for (community, graph) in networks_vec.iter().enumerate() {
+                        let node_id = |node_idx: NodeIndex| format!("c{}_n{}", community, node_idx.index());
+
+                        for node_idx in graph.node_indices() {
+                            let node = &graph[node_idx];
+                            graphml.push_str(&format!("    <node id=\"{}\">\n", node_id(node_idx)));
+                            graphml.push_str(&format!("      <data key=\"go_id\">GO:{:07}</data>\n", node.go_id));
+                            graphml.push_str(&format!("      <data key=\"name\">{}</data>\n", escape_xml(&node.name)));
+                            graphml.push_str(&format!("      <data key=\"namespace\">{}</data>\n", namespace_str));
+                            graphml.push_str(&format!("      <data key=\"lor\">{}</data>\n", node.lor));
+                            graphml.push_str(&format!("      <data key=\"p_value\">{:e}</data>\n", node.stat_sig));
+                            graphml.push_str(&format!("      <data key=\"size\">{}</data>\n", node.size_statistic));
+                            graphml.push_str("    </node>\n");
+                        }
+
+                        for edge in graph.edge_references() {
+                            graphml.push_str(&format!(
+                                "    <edge source=\"{}\" target=\"{}\">\n",
+                                node_id(edge.source()),
+                                node_id(edge.target())
+                            ));
+                            graphml.push_str(&format!("      <data key=\"jaccard_index\">{}</data>\n", edge.weight()));
+                            graphml.push_str("    </edge>\n");
+                        }
+                    }
+
+                    graphml.push_str("  </graph>\n");
+                    graphml.push_str("</graphml>\n");
+
+                    let namespace_subdir = get_namespace_subdir(namespace, plots_dir)?;
+                    let graphml_file = namespace_subdir.join(format!("{}_network.graphml", sanitize_taxon_name(taxon_name)));
+                    fs::write(graphml_file, graphml)?;
+
+                    Ok::<(), Box<dyn Error + Send + Sync>>(())
+                })
+        })
+}
+
+pub const CYTOSCAPE_FILE_SUFFIX: &str = "_network.cyjs";
+
+// Cytoscape.js `elements` of one taxon and namespace. GO terms only belong to one community, so their IDs are unique.
+pub fn cytoscape_elements(networks_vec: &[GoTermNetworkGraph], namespace: &NameSpace) -> Value {
+    let namespace_str = namespace.obo_name();
+
+    let mut nodes: Vec<Value> = Vec::new();
+    let mut edges: Vec<Value> = Vec::new();
+
+    for (community, graph) in networks_vec.iter().enumerate() {
+        for node in graph.node_weights() {
+            nodes.push(json!({"data": {
+                "id": format!("GO:{:07}", node.go_id),
+                "name": node.name,
+                "lor": node.lor,
+                "pvalue": node.stat_sig,
+                "size": node.size_statistic,
+                "namespace": namespace_str,
+                "community": community + 1,
+            }}));
+        }
+
+        for edge in graph.edge_references() {
+            edges.push(json!({"data": {
+                "source": format!("GO:{:07}", graph[edge.source()].go_id),
+                "target": format!("GO:{:07}", graph[edge.target()].go_id),
+                "jaccard": edge.weight(),
+            }}));
+        }
+    }
+
+    json!({"nodes": nodes, "edges": edges})
+}
+
+pub fn export_network_cytoscape(
+    top_networks_map: &FxHashMap<String, FxHashMap<NameSpace, Vec<GoTermNetworkGraph>>>,
+    plots_dir: &Path
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    for (taxon_name, namespace_map) in top_networks_map {
+        for (namespace, networks_vec) in namespace_map {
+            if networks_vec.is_empty() {
+                continue;
+            }
+
+            let bundle = json!({"elements": cytoscape_elements(networks_vec, namespace)});
+
+            let namespace_subdir = get_namespace_subdir(namespace, plots_dir)?;
+            let cytoscape_file = namespace_subdir.join(format!("{}{}", sanitize_taxon_name(taxon_name), CYTOSCAPE_FILE_SUFFIX));
+            fs::write(cytoscape_file, serde_json::to_string(&bundle)?)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use compact_str::CompactString;
+    use std::sync::Arc;
+
+    #[derive(Clone)]
+    struct TestResult;
+
+    impl EnrichmentResult for TestResult {
+        fn log_odds_ratio(&self) -> f64 { 1.0 }
+        fn p_value(&self) -> f64 { 0.01 }
+        fn size(&self) -> usize { 2 }
+    }
+
+    // Node and edge counts of the non-empty communities built at the given Jaccard threshold.
+    fn network_sizes(jaccard_threshold: f64) -> Vec<(usize, usize)> {
+        let protein = |name: &str| Arc::new(CompactString::new(name));
+        let go_term_to_proteins: GOTermToProteinSet = [
+            (1, [protein("P1"), protein("P2")].into_iter().collect()),
+            (2, [protein("P1"), protein("P2")].into_iter().collect()),
+            (3, [protein("P2"), protein("P3")].into_iter().collect()),
+            (4, [protein("P4")].into_iter().collect()),
+        ].into_iter().collect();
+
+        let ontology: OboMap = go_term_to_proteins.keys().map(|&go_id| (go_id, OboTerm::new())).collect();
+        let enrichment_results = FxHashMap::from_iter([(
+            "Homo sapiens".to_string(),
+            go_term_to_proteins.keys().map(|&go_id| (go_id, TestResult)).collect::<FxHashMap<GOTermID, TestResult>>()
+        )]);
+        let network_data = FxHashMap::from_iter([(
+            "Homo sapiens".to_string(),
+            FxHashMap::from_iter([(NameSpace::BiologicalProcess, go_term_to_proteins)])
+        )]);
+
+        let networks = build_networks(&network_data, &enrichment_results, &ontology, jaccard_threshold, "log(Odds Ratio)");
+        let mut sizes: Vec<(usize, usize)> = networks["Homo sapiens"][&NameSpace::BiologicalProcess]
+            .iter()
+            .filter(|graph| graph.node_count() > 0)
+            .map(|graph| (graph.node_count(), graph.edge_count()))
+            .collect();
+        sizes.sort_unstable();
+        sizes
+    }
+
+    #[test]
+    fn jaccard_threshold_of_one_only_connects_identical_protein_sets() {
+        assert_eq!(network_sizes(1.0), vec![(2, 1)]);
+    }
+
+    #[test]
+    fn jaccard_threshold_of_zero_connects_terms_sharing_any_protein() {
+        assert_eq!(network_sizes(0.0), vec![(3, 3)]);
+    }
+
+    // A community of `node_count` GO terms numbered from `first_go_id`, with edges given as pairs of node positions.
+    fn community(first_go_id: GOTermID, node_count: u32, edges: &[(usize, usize)]) -> GoTermNetworkGraph {
+        let mut graph = GoTermNetworkGraph::default();
+        let nodes: Vec<NodeIndex> = (first_go_id..first_go_id + node_count)
+            .map(|go_id| graph.add_node(GOTermPlotData {
+                go_id,
+                name: String::new(),
+                wrapped_name: String::new(),
+                lor: 1.0,
+                stat_sig: 0.01,
+                minus_log10_p_value: 2.0,
+                size_statistic: 1,
+                namespace: NameSpace::BiologicalProcess,
+                hover_text: String::new(),
+                betweenness: 0.0,
+            }))
+            .collect();
+        for &(source, target) in edges {
+            graph.add_edge(nodes[source], nodes[target], 0.5);
+        }
+        graph
+    }
+
+    // Two triangles: m = 6 and each community has L = 3 and d = 6, so Q = 2 * (3/6 - (6/12)²) = 0.5.
+    #[test]
+    fn modularity_of_two_triangles() {
+        let triangle = [(0, 1), (1, 2), (2, 0)];
+        let stats = network_stats(&[community(1, 3, &triangle), community(4, 3, &triangle)]);
+
+        assert!((stats.modularity - 0.5).abs() < 1e-12);
+    }
+
+    // A triangle and an edge: m = 4, Q = (3/4 - (6/8)²) + (1/4 - (2/8)²) = 0.1875 + 0.1875 = 0.375.
+    #[test]
+    fn modularity_of_communities_of_different_sizes() {
+        let stats = network_stats(&[community(1, 3, &[(0, 1), (1, 2), (2, 0)]), community(4, 2, &[(0, 1)])]);
+
+        assert!((stats.modularity - 0.375).abs() < 1e-12);
+        assert_eq!(stats.node_communities, vec![(1, 1, 2), (2, 1, 2), (3, 1, 2), (4, 2, 1), (5, 2, 1)]);
+    }
+
+    #[test]
+    fn modularity_of_a_single_community_is_zero() {
+        let stats = network_stats(&[community(1, 3, &[(0, 1), (1, 2)])]);
+
+        assert!(stats.modularity.abs() < 1e-12);
+    }
+
+    #[test]
+    fn multi_species_heatmap_writes_one_file_for_all_namespaces() {
+        let namespaces = [NameSpace::BiologicalProcess, NameSpace::MolecularFunction, NameSpace::CellularComponent];
+        let ontology: OboMap = (1..=3)
+            .map(|go_id| {
+                let mut term = OboTerm::new();
+                term.namespace = namespaces[go_id as usize - 1];
+                (go_id, term)
+            })
+            .collect();
+        let result = |log_odds_ratio: f64| GOTermResults {
+            log_odds_ratio,
+            p_value: 0.01,
+            contingency_table: None,
+            study_count: 2,
+            variance: 0.1
+        };
+        let significant_results_by_name = FxHashMap::from_iter([
+            ("Homo sapiens".to_string(), FxHashMap::from_iter([(1, result(1.5)), (2, result(-0.5))])),
+            ("Mus musculus".to_string(), FxHashMap::from_iter([(1, result(2.0)), (3, result(0.8))])),
+        ]);
+
+        let results_dir = std::env::temp_dir().join(format!("taxago_heatmap_{}", std::process::id()));
+        let written_files = multi_species_heatmap(
+            &significant_results_by_name,
+            &ontology,
+            &results_dir,
+            PlotType::Interactive,
+            "log(Odds Ratio)"
+        ).unwrap();
+
+        assert_eq!(written_files, vec![results_dir.join("multi_species_heatmap.html")]);
+        let html = fs::read_to_string(&written_files[0]).unwrap();
+        assert!(html.contains("Biological Process"));
+        assert!(html.contains("Cellular Component"));
+
+        fs::remove_dir_all(&results_dir).unwrap();
+    }
+}
@@ -0,0 +1,332 @@
+use rustc_hash::FxHashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::error::Error;
+use std::path::PathBuf;
+use std::cmp::Ordering::Equal;
+use rand::{
+    seq::index::sample,
+    SeedableRng,
+    rngs::StdRng
+};
+use rayon::prelude::*;
+use crate::parsers::{
+    background_parser::*,
+    obo_parser::*
+};
+
+#[derive(Debug, Clone)]
+pub struct GSEAResult {
+    pub set_size: usize,
+    pub enrichment_score: f64,
+    pub normalized_enrichment_score: f64,
+    pub p_value: f64,
+    pub fdr_q_value: f64,
+}
+
+// Running-sum enrichment score computed from the sorted (0-based) ranks of the proteins in the term.
+// The running sum only changes direction at hits, so its extremes are found just before and at each hit.
+pub fn enrichment_score(hit_positions: &[usize], total_proteins: usize) -> f64 {
+    let set_size = hit_positions.len();
+    if set_size == 0 || set_size >= total_proteins {
+        return 0.0;
+    }
+
+    let hit_step = ((total_proteins - set_size) as f64 / set_size as f64).sqrt();
+    let miss_step = (set_size as f64 / (total_proteins - set_size) as f64).sqrt();
+
+    let mut max_deviation: f64 = 0.0;
+
+    for (hits_before, &position) in hit_positions.iter().enumerate() {
+        let misses_before = (position - hits_before) as f64;
+        let before_hit = hits_before as f64 * hit_step - misses_before * miss_step;
+        let at_hit = before_hit + hit_step;
+
+        for running_sum in [before_hit, at_hit] {
+            if running_sum.abs() > max_deviation.abs() {
+                max_deviation = running_sum;
+            }
+        }
+    }
+
+    max_deviation
+}
+
+fn null_enrichment_scores(
+    set_size: usize,
+    total_proteins: usize,
+    permutations: u32,
+    seed: u64
+) -> Vec<f64> {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    (0..permutations)
+        .map(|_| {
+            let mut hit_positions = sample(&mut rng, total_proteins, set_size).into_vec();
+            hit_positions.sort_unstable();
+            enrichment_score(&hit_positions, total_proteins)
+        })
+        .collect()
+}
+
+fn mean_abs_of_sign(scores: &[f64], positive: bool) -> Option<f64> {
+    let same_sign: Vec<f64> = scores
+        .iter()
+        .filter(|&&score| if positive { score >= 0.0 } else { score < 0.0 })
+        .map(|score| score.abs())
+        .collect();
+
+    if same_sign.is_empty() {
+        return None;
+    }
+
+    Some(same_sign.iter().sum::<f64>() / same_sign.len() as f64)
+}
+
+// Absolute values of the non-negative and negative scores, each sorted ascending, so the tail
+// fraction of any score can be found with a binary search.
+fn split_by_sign(scores: &[f64]) -> (Vec<f64>, Vec<f64>) {
+    let (mut positive, mut negative): (Vec<f64>, Vec<f64>) = scores
+        .iter()
+        .partition(|&&score| score >= 0.0);
+
+    for side in [&mut positive, &mut negative] {
+        side.iter_mut().for_each(|score| *score = score.abs());
+        side.sort_unstable_by(|a, b| a.total_cmp(b));
+    }
+
+    (positive, negative)
+}
+
+fn fraction_at_least_as_extreme(sorted_abs_scores: &[f64], value: f64) -> f64 {
+    if sorted_abs_scores.is_empty() {
+        return 0.0;
+    }
+
+    let less_extreme = sorted_abs_scores.partition_point(|&score| score < value.abs());
+    (sorted_abs_scores.len() - less_extreme) as f64 / sorted_abs_scores.len() as f64
+}
+
+pub fn gsea(
+    protein_scores: &FxHashMap<Protein, f64>,
+    go_term_to_proteins: &GOTermToProteinSet,
+    min_set_size: usize,
+    max_set_size: usize,
+    permutations: u32,
+    seed: u64
+) -> FxHashMap<GOTermID, GSEAResult> {
+    let mut ranked_proteins: Vec<(&Protein, f64)> = protein_scores
+        .iter()
+        .map(|(protein, &score)| (protein, score))
+        .collect();
+    ranked_proteins.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Equal));
+
+    let protein_rank: FxHashMap<&Protein, usize> = ranked_proteins
+        .iter()
+        .enumerate()
+        .map(|(rank, (protein, _))| (*protein, rank))
+        .collect();
+    let total_proteins = ranked_proteins.len();
+
+    let term_scores: Vec<(GOTermID, usize, f64, Vec<f64>)> = go_term_to_proteins
+        .par_iter()
+        .filter_map(|(&go_term, proteins)| {
+            let mut hit_positions: Vec<usize> = proteins
+                .iter()
+                .filter_map(|protein| protein_rank.get(protein).copied())
+                .collect();
+
+            let set_size = hit_positions.len();
+            if set_size < min_set_size || set_size > max_set_size || set_size >= total_proteins {
+                return None;
+            }
+            hit_positions.sort_unstable();
+
+            let observed = enrichment_score(&hit_positions, total_proteins);
+            let null_scores = null_enrichment_scores(
+                set_size,
+                total_proteins,
+                permutations,
+                seed.wrapping_add(go_term as u64)
+            );
+
+            Some((go_term, set_size, observed, null_scores))
+        })
+        .collect();
+
+    let mut observed_normalized = Vec::with_capacity(term_scores.len());
+    let mut null_normalized = Vec::with_capacity(term_scores.len() * permutations as usize);
+    let mut results = Vec::with_capacity(term_scores.len());
+
+    for (go_term, set_size, observed, null_scores) in term_scores {
+        let positive_mean = mean_abs_of_sign(&null_scores, true);
+        let negative_mean = mean_abs_of_sign(&null_scores, false);
+
+        let normalize = |score: f64| {
+            let mean = if score >= 0.0 { positive_mean } else { negative_mean };
+            match mean {
+                Some(mean) if mean > 0.0 => score / mean,
+                _ => 0.0,
+            }
+        };
+
+        let normalized_enrichment_score = normalize(observed);
+        observed_normalized.push(normalized_enrichment_score);
+        null_normalized.extend(null_scores.iter().map(|&score| normalize(score)));
+
+        let same_sign_nulls = null_scores
+            .iter()
+            .filter(|&&score| (score >= 0.0) == (observed >= 0.0))
+            .count();
+        let extreme_nulls = null_scores
+            .iter()
+            .filter(|&&score| (score >= 0.0) == (observed >= 0.0) && score.abs() >= observed.abs())
+            .count();
+        let p_value = (extreme_nulls + 1) as f64 / (same_sign_nulls + 1) as f64;
+
+        results.push((go_term, GSEAResult {
+            set_size,
+            enrichment_score: observed,
+            normalized_enrichment_score,
+            p_value,
+            fdr_q_value: 1.0,
+        }));
+    }
+
+    let (null_positive, null_negative) = split_by_sign(&null_normalized);
+    let (observed_positive, observed_negative) = split_by_sign(&observed_normalized);
+
+    for (_, result) in results.iter_mut() {
+        let nes = result.normalized_enrichment_score;
+        let (null_side, observed_side) = if nes >= 0.0 {
+            (&null_positive, &observed_positive)
+        } else {
+            (&null_negative, &observed_negative)
+        };
+        let null_fraction = fraction_at_least_as_extreme(null_side, nes);
+        let observed_fraction = fraction_at_least_as_extreme(observed_side, nes);
+
+        if observed_fraction > 0.0 {
+            result.fdr_q_value = (null_fraction / observed_fraction).min(1.0);
+        }
+    }
+
+    results.into_iter().collect()
+}
+
+pub fn write_gsea_results(
+    gsea_results: &FxHashMap<GOTermID, GSEAResult>,
+    ontology: &OboMap,
+    output_file: &PathBuf,
+) -> Result<(), Box<dyn Error>> {
+    println!("Writing GSEA results to: {}\n", output_file.display());
+
+    let file = File::create(output_file)?;
+    let mut writer = BufWriter::new(file);
+
+    writer.write_all(b"GO Term ID\tName\tNamespace\tSize\tES\tNES\tp-value\tFDR q-value\n")?;
+
+    let mut sorted_results: Vec<(&GOTermID, &GSEAResult)> = gsea_results.iter().collect();
+    sorted_results.sort_by(|a, b| {
+        a.1.fdr_q_value.partial_cmp(&b.1.fdr_q_value).unwrap_or(Equal)
+            .then(b.1.normalized_enrichment_score.abs().partial_cmp(&a.1.normalized_enrichment_score.abs()).unwrap_or(Equal))
+    });
+
+    for (go_term, result) in sorted_results {
+        let Some(term) = ontology.get(go_term) else { continue };
+        if term.is_obsolete {
+            continue;
+        }
+
+        let namespace = term.namespace.label();
+
+        writeln!(
+            writer,
+            "GO:{:07}\t{}\t{}\t{}\t{:.3}\t{:.3}\t{:.5e}\t{:.5e}",
+            go_term,
+            term.name,
+            namespace,
+            result.set_size,
+            result.enrichment_score,
+            result.normalized_enrichment_score,
+            result.p_value,
+            result.fdr_q_value
+        )?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustc_hash::FxHashSet;
+    use std::sync::Arc;
+    use compact_str::CompactString;
+
+    fn protein(name: &str) -> Protein {
+        Arc::new(CompactString::from(name))
+    }
+
+    fn protein_set(names: &[&str]) -> FxHashSet<Protein> {
+        names.iter().map(|name| protein(name)).collect()
+    }
+
+    #[test]
+    fn enrichment_score_matches_the_hand_computed_running_sum() {
+        // With 2 of 4 proteins in the set, hits add 1 and misses subtract 1.
+        assert_eq!(enrichment_score(&[0, 1], 4), 2.0);
+        assert_eq!(enrichment_score(&[2, 3], 4), -2.0);
+        // The running sum is 1, 0, -1, 0; the first of two equal deviations is kept.
+        assert_eq!(enrichment_score(&[0, 3], 4), 1.0);
+        // With 1 of 5 proteins, a hit adds 2 and a miss subtracts 0.5: -0.5, 1.5, 1.0, 0.5, 0.0.
+        assert_eq!(enrichment_score(&[1], 5), 1.5);
+        assert_eq!(enrichment_score(&[], 5), 0.0);
+    }
+
+    // Of the 6 ways to place 2 hits among 4 proteins, one has an ES of 2, two have an ES of 1 and three are negative.
+    // A term at the top of the list therefore has p = 1/3 among the positive nulls, and its ES is normalized by
+    // the mean positive null ES of (2 + 1 + 1) / 3 = 4/3.
+    #[test]
+    fn p_values_and_normalized_scores_follow_the_exact_null_distribution() {
+        let protein_scores: FxHashMap<Protein, f64> = [("P1", 4.0), ("P2", 3.0), ("P3", 2.0), ("P4", 1.0)]
+            .into_iter()
+            .map(|(name, score)| (protein(name), score))
+            .collect();
+        let go_term_to_proteins: GOTermToProteinSet = [
+            (1, protein_set(&["P1", "P2"])),
+            (2, protein_set(&["P3", "P4"])),
+        ].into_iter().collect();
+
+        let results = gsea(&protein_scores, &go_term_to_proteins, 1, 10, 5000, 42);
+
+        let top = &results[&1];
+        assert_eq!(top.set_size, 2);
+        assert_eq!(top.enrichment_score, 2.0);
+        assert!((top.p_value - 1.0 / 3.0).abs() < 0.03, "p-value {}", top.p_value);
+        assert!((top.normalized_enrichment_score - 1.5).abs() < 0.05, "NES {}", top.normalized_enrichment_score);
+
+        let bottom = &results[&2];
+        assert_eq!(bottom.enrichment_score, -2.0);
+        assert!((bottom.p_value - 1.0 / 3.0).abs() < 0.03, "p-value {}", bottom.p_value);
+        assert!((bottom.normalized_enrichment_score + 1.5).abs() < 0.05, "NES {}", bottom.normalized_enrichment_score);
+    }
+
+    #[test]
+    fn set_size_only_counts_ranked_proteins() {
+        let protein_scores: FxHashMap<Protein, f64> = [("P1", 2.0), ("P2", 1.0), ("P3", 0.5)]
+            .into_iter()
+            .map(|(name, score)| (protein(name), score))
+            .collect();
+        let go_term_to_proteins: GOTermToProteinSet = [
+            (1, protein_set(&["P1", "UNRANKED"])),
+            (2, protein_set(&["P1", "P2"])),
+        ].into_iter().collect();
+
+        let results = gsea(&protein_scores, &go_term_to_proteins, 2, 10, 100, 42);
+
+        assert!(!results.contains_key(&1));
+        assert_eq!(results[&2].set_size, 2);
+    }
+}
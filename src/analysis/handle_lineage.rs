@@ -1,10 +1,45 @@
 use rustc_hash::{FxHashMap, FxHashSet};
 use std::fs::File;
-use std::io::{BufRead, BufReader, Result};
+use std::io::{BufRead, BufReader, Error, ErrorKind, Result};
 use std::path::Path;
 use crate::analysis::enrichment_analysis::*;
 use crate::parsers::background_parser::TaxonID;
 
+// Taxon ID, species name and the seven levels from Genus to Superkingdom.
+pub const LINEAGE_COLUMNS: usize = 9;
+
+pub fn validate_lineage_file<P: AsRef<Path>>(path: P) -> Result<()> {
+    let path = path.as_ref();
+    if !path.is_file() {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!("Lineage file not found: {}", path.display())
+        ));
+    }
+
+    let file = File::open(path)?;
+    let reader = BufReader::with_capacity(32 * 1024, file);
+
+    for (line_number, line) in reader.lines().enumerate().skip(1) {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let columns = line.split('\t').count();
+        if columns < LINEAGE_COLUMNS {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "Line {} of lineage file '{}' has {} tab-separated columns, expected at least {}",
+                    line_number + 1, path.display(), columns, LINEAGE_COLUMNS
+                )
+            ));
+        }
+    }
+    Ok(())
+}
+
 pub fn read_lineage<P: AsRef<Path>>(path: P) -> Result<FxHashMap<TaxonID, Vec<String>>> {
     let file = File::open(path)?;
     let reader = BufReader::with_capacity(32 * 1024, file);
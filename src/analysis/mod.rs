@@ -7,4 +7,15 @@ pub mod elim_algorithm;
 pub mod count_propagation;
 pub mod phylogenetic_meta_analysis;
 pub mod enrichment_plots;
-pub mod weight_algorithm;
\ No newline at end of file
+pub mod weight_algorithm;
+pub mod taxon_enrichment;
+pub mod term_clustering;
+pub mod differential_enrichment;
+pub mod gsea;
+pub mod results_summary;
+pub mod species_comparison;
+pub mod timeline;
+pub mod pathway_overlap;
+pub mod network_centrality;
+pub mod plot_backend;
+pub mod cutoff_analysis;
\ No newline at end of file
@@ -17,6 +17,7 @@ pub enum AdjustmentMethod {
     Bonferroni,
     BenjaminiHochberg,
     BenjaminiYekutieli,
+    HolmSidak,
 }
 
 impl AdjustmentMethod {
@@ -26,8 +27,37 @@ impl AdjustmentMethod {
             AdjustmentMethod::Bonferroni => Some(Procedure::Bonferroni),
             AdjustmentMethod::BenjaminiHochberg => Some(Procedure::BenjaminiHochberg),
             AdjustmentMethod::BenjaminiYekutieli => Some(Procedure::BenjaminiYekutieli),
+            AdjustmentMethod::HolmSidak => None,
         }
     }
+
+    pub fn adjust(&self, pvals: Vec<f64>) -> Vec<f64> {
+        match self {
+            AdjustmentMethod::HolmSidak => holm_sidak(&pvals),
+            _ => match self.to_procedure() {
+                Some(procedure) => adjust(&pvals, procedure),
+                None => pvals,
+            },
+        }
+    }
+}
+
+pub fn holm_sidak(pvals: &[f64]) -> Vec<f64> {
+    let n = pvals.len();
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&a, &b| pvals[a].partial_cmp(&pvals[b]).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut adjusted = vec![0.0; n];
+    let mut running_max: f64 = 0.0;
+
+    for (rank, &idx) in order.iter().enumerate() {
+        let remaining_tests = (n - rank) as i32;
+        let sidak = 1.0 - (1.0 - pvals[idx]).powi(remaining_tests);
+        running_max = running_max.max(sidak).min(1.0);
+        adjusted[idx] = running_max;
+    }
+
+    adjusted
 }
 
 trait PValueAdjustable {
@@ -49,6 +79,7 @@ impl PValueAdjustable for GOTermResults {
             log_odds_ratio: self.log_odds_ratio,
             p_value: new_p_value,
             contingency_table: self.contingency_table,
+            study_count: self.study_count,
             variance: self.variance,
         }
     }
@@ -62,9 +93,8 @@ impl PValueAdjustable for TaxonomyGOResult {
 
     fn with_adjusted_p_value(&self, new_p_value: f64) -> Self {
         Self {
-            log_odds_ratio: self.log_odds_ratio,
             p_value: new_p_value,
-            species_number: self.species_number,
+            ..self.clone()
         }
     }
 }
@@ -111,11 +141,7 @@ where
         let pvals: Vec<f64> = rows.iter().map(|(_, r)| r.extract_p_value()).collect();
         
         // Apply multiple testing correction across all GO terms for this species
-        let adj = if let Some(proc_) = method.to_procedure() {
-            adjust(&pvals, proc_)
-        } else {
-            pvals
-        };
+        let adj = method.adjust(pvals);
 
         // Filter results based on adjusted p-values and effect size
         for ((go_id, res), q) in rows.into_iter().zip(adj.into_iter()) {
@@ -137,7 +163,7 @@ pub fn adjust_species_p_values(
     significance_threshold: Option<f64>,
     log_odds_ratio_threshold: f64,
 ) -> SpeciesResults {
-    println!("Adjusting single taxon p-values using method: {:?} (grouped by species)\n", adjustment_method);
+    crate::progress!("Adjusting single taxon p-values using method: {:?} (grouped by species)\n", adjustment_method);
     adjust_p_values_grouped(
         results,
         adjustment_method,
@@ -146,6 +172,19 @@ pub fn adjust_species_p_values(
     )
 }
 
+// Adjusts the p-values of every tested term without filtering, for writing the full test results.
+pub fn adjust_all_species_p_values(
+    results: &SpeciesResults,
+    adjustment_method: AdjustmentMethod,
+) -> SpeciesResults {
+    adjust_p_values_grouped(
+        results,
+        adjustment_method,
+        None,
+        f64::NEG_INFINITY,
+    )
+}
+
 pub fn adjust_taxonomy_p_values(
     results: &TaxonomyResults,
     adjustment_method: AdjustmentMethod,
@@ -153,11 +192,70 @@ pub fn adjust_taxonomy_p_values(
     log_odds_ratio_threshold: f64,
     level: &String,
 ) -> TaxonomyResults {
-    println!("Adjusting p-values at {} level using method: {:?} (grouped by taxonomy)\n", level, adjustment_method);
+    crate::progress!("Adjusting p-values at {} level using method: {:?} (grouped by taxonomy)\n", level, adjustment_method);
     adjust_p_values_grouped(
         results,
         adjustment_method,
         significance_threshold,
         log_odds_ratio_threshold,
     )
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // One species with 100 tested GO terms: term 1 has `p_value`, the others p = 0.5.
+    fn species_results(p_value: f64) -> SpeciesResults {
+        let go_terms = (1..=100)
+            .map(|go_term| (go_term, GOTermResults {
+                log_odds_ratio: 1.0,
+                p_value: if go_term == 1 { p_value } else { 0.5 },
+                contingency_table: Some([5, 5, 5, 5]),
+                study_count: 5,
+                variance: 0.8,
+            }))
+            .collect();
+
+        let mut results = SpeciesResults::default();
+        results.insert(9606, go_terms);
+        results
+    }
+
+    #[test]
+    fn bonferroni_multiplies_by_the_number_of_tested_terms() {
+        let adjusted = adjust_species_p_values(&species_results(0.0004), AdjustmentMethod::Bonferroni, Some(0.05), 0.0);
+
+        let significant = &adjusted[&9606];
+        assert_eq!(significant.len(), 1);
+        assert!((significant[&1].p_value - 0.04).abs() < 1e-12);
+    }
+
+    #[test]
+    fn bonferroni_rejects_terms_above_alpha() {
+        let adjusted = adjust_all_species_p_values(&species_results(0.0006), AdjustmentMethod::Bonferroni);
+        assert!((adjusted[&9606][&1].p_value - 0.06).abs() < 1e-12);
+        assert_eq!(adjusted[&9606][&2].p_value, 1.0);
+
+        let significant = adjust_species_p_values(&species_results(0.0006), AdjustmentMethod::Bonferroni, Some(0.05), 0.0);
+        assert!(significant.is_empty());
+    }
+
+    #[test]
+    fn holm_sidak_matches_the_reference_values() {
+        // Reference values from statsmodels' multipletests(method="holm-sidak").
+        let adjusted = AdjustmentMethod::HolmSidak.adjust(vec![0.02, 0.001, 0.2, 0.01, 0.03]);
+        let expected = [0.058808, 0.004990010, 0.2, 0.03940399, 0.0591];
+
+        for (value, expected) in adjusted.iter().zip(expected.iter()) {
+            assert!((value - expected).abs() < 1e-8, "got {:?}", adjusted);
+        }
+    }
+
+    #[test]
+    fn holm_sidak_is_monotone() {
+        let adjusted = holm_sidak(&[0.04, 0.001, 0.04, 0.045]);
+        assert!(adjusted[1] <= adjusted[0]);
+        assert_eq!(adjusted[0], adjusted[2]);
+        assert!(adjusted[0] <= adjusted[3]);
+    }
+}
@@ -0,0 +1,170 @@
+use rustc_hash::FxHashMap;
+use std::collections::{VecDeque, hash_map::Entry};
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use petgraph::graph::NodeIndex;
+use crate::parsers::obo_parser::*;
+use crate::analysis::{
+    enrichment_plots::*,
+    write_results::sanitize_taxon_name
+};
+
+// Brandes' algorithm on the unweighted network. Edges are stored once per pair of GO terms but represent a
+// symmetric Jaccard index, so they are traversed in both directions. Accumulating over every source counts
+// each pair twice, which cancels against the undirected normalisation 2 / ((n-1)(n-2)), leaving (n-1)(n-2).
+pub fn calculate_betweenness_centrality(graph: &GoTermNetworkGraph) -> FxHashMap<NodeIndex, f64> {
+    let nodes: Vec<NodeIndex> = graph.node_indices().collect();
+    let mut betweenness: FxHashMap<NodeIndex, f64> = nodes.iter().map(|&node| (node, 0.0)).collect();
+
+    for &source in &nodes {
+        let mut stack: Vec<NodeIndex> = Vec::with_capacity(nodes.len());
+        let mut predecessors: FxHashMap<NodeIndex, Vec<NodeIndex>> = FxHashMap::default();
+        let mut path_counts: FxHashMap<NodeIndex, f64> = FxHashMap::default();
+        let mut distances: FxHashMap<NodeIndex, usize> = FxHashMap::default();
+        let mut queue: VecDeque<NodeIndex> = VecDeque::new();
+
+        path_counts.insert(source, 1.0);
+        distances.insert(source, 0);
+        queue.push_back(source);
+
+        while let Some(node) = queue.pop_front() {
+            stack.push(node);
+            let node_distance = distances[&node];
+            let node_paths = path_counts[&node];
+
+            for neighbor in graph.neighbors_undirected(node) {
+                if let Entry::Vacant(entry) = distances.entry(neighbor) {
+                    entry.insert(node_distance + 1);
+                    queue.push_back(neighbor);
+                }
+                if distances[&neighbor] == node_distance + 1 {
+                    *path_counts.entry(neighbor).or_insert(0.0) += node_paths;
+                    predecessors.entry(neighbor).or_default().push(node);
+                }
+            }
+        }
+
+        let mut dependencies: FxHashMap<NodeIndex, f64> = FxHashMap::default();
+        while let Some(node) = stack.pop() {
+            let node_dependency = dependencies.get(&node).copied().unwrap_or(0.0);
+            if let Some(node_predecessors) = predecessors.get(&node) {
+                for &predecessor in node_predecessors {
+                    *dependencies.entry(predecessor).or_insert(0.0) +=
+                        path_counts[&predecessor] / path_counts[&node] * (1.0 + node_dependency);
+                }
+            }
+            if node != source {
+                *betweenness.entry(node).or_insert(0.0) += node_dependency;
+            }
+        }
+    }
+
+    let n = nodes.len();
+    if n > 2 {
+        let normalization = ((n - 1) * (n - 2)) as f64;
+        for value in betweenness.values_mut() {
+            *value /= normalization;
+        }
+    }
+
+    betweenness
+}
+
+pub fn write_network_centrality(
+    top_networks_map: &FxHashMap<String, FxHashMap<NameSpace, Vec<GoTermNetworkGraph>>>,
+    results_dir: &PathBuf
+) -> Result<(), Box<dyn Error>> {
+    fs::create_dir_all(results_dir)?;
+
+    for (taxon_name, namespace_map) in top_networks_map {
+        for (namespace, networks_vec) in namespace_map {
+            if networks_vec.is_empty() {
+                continue;
+            }
+
+            let namespace_str = namespace.file_label();
+
+            let mut content = String::from("GO_ID\tCommunity_ID\tBetweenness\n");
+            for (community, graph) in networks_vec.iter().enumerate() {
+                let mut nodes: Vec<&GOTermPlotData> = graph.node_weights().collect();
+                nodes.sort_by(|a, b| b.betweenness.total_cmp(&a.betweenness));
+
+                for node in nodes {
+                    content.push_str(&format!("GO:{:07}\t{}\t{:.5}\n", node.go_id, community + 1, node.betweenness));
+                }
+            }
+
+            let centrality_file = results_dir.join(format!(
+                "{}_{}_centrality.tsv",
+                sanitize_taxon_name(taxon_name),
+                namespace_str
+            ));
+            fs::write(centrality_file, content)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::background_parser::GOTermID;
+
+    fn node(go_id: GOTermID) -> GOTermPlotData {
+        GOTermPlotData {
+            go_id,
+            name: String::new(),
+            wrapped_name: String::new(),
+            lor: 1.0,
+            stat_sig: 0.01,
+            minus_log10_p_value: 2.0,
+            size_statistic: 1,
+            namespace: NameSpace::BiologicalProcess,
+            hover_text: String::new(),
+            betweenness: 0.0,
+        }
+    }
+
+    // Betweenness of the nodes in insertion order, for a network whose edges are given as pairs of node positions.
+    fn betweenness(node_count: u32, edges: &[(usize, usize)]) -> Vec<f64> {
+        let mut graph = GoTermNetworkGraph::default();
+        let nodes: Vec<NodeIndex> = (1..=node_count).map(|go_id| graph.add_node(node(go_id))).collect();
+        for &(source, target) in edges {
+            graph.add_edge(nodes[source], nodes[target], 0.5);
+        }
+
+        let centrality = calculate_betweenness_centrality(&graph);
+        nodes.iter().map(|node| centrality[node]).collect()
+    }
+
+    fn assert_close(actual: &[f64], expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-12, "expected {:?}, got {:?}", expected, actual);
+        }
+    }
+
+    // In the path 0 - 1 - 2 - 3, nodes 1 and 2 each lie on 2 of the 3 pairs of other nodes.
+    #[test]
+    fn path_betweenness_is_normalised_over_pairs_of_other_nodes() {
+        assert_close(&betweenness(4, &[(0, 1), (1, 2), (2, 3)]), &[0.0, 2.0 / 3.0, 2.0 / 3.0, 0.0]);
+    }
+
+    #[test]
+    fn edge_direction_does_not_change_betweenness() {
+        assert_close(&betweenness(4, &[(1, 0), (1, 2), (3, 2)]), &[0.0, 2.0 / 3.0, 2.0 / 3.0, 0.0]);
+    }
+
+    #[test]
+    fn star_centre_lies_on_every_shortest_path() {
+        assert_close(&betweenness(4, &[(0, 1), (0, 2), (0, 3)]), &[1.0, 0.0, 0.0, 0.0]);
+    }
+
+    // In a 4-cycle, opposite nodes are joined by two shortest paths, so each node gets half of one pair.
+    #[test]
+    fn equal_shortest_paths_share_the_dependency() {
+        assert_close(&betweenness(4, &[(0, 1), (1, 2), (2, 3), (3, 0)]), &[1.0 / 6.0; 4]);
+    }
+}
@@ -0,0 +1,178 @@
+use rustc_hash::FxHashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::error::Error;
+use std::path::PathBuf;
+use std::cmp::Ordering::Equal;
+use statrs::distribution::{Hypergeometric, DiscreteCDF};
+use crate::parsers::{
+    background_parser::*,
+    gmt_parser::*,
+    obo_parser::*
+};
+
+#[derive(Debug, Clone)]
+pub struct PathwayOverlap {
+    pub go_term: GOTermID,
+    pub pathway: String,
+    pub shared_proteins: usize,
+    pub jaccard: f64,
+    pub p_value: f64,
+}
+
+// P(X >= shared) when drawing the proteins of a GO term from `universe_size` proteins, `pathway_size` of which are in the pathway.
+fn overlap_p_value(shared: usize, term_size: usize, pathway_size: usize, universe_size: usize) -> f64 {
+    if shared == 0 {
+        return 1.0;
+    }
+
+    match Hypergeometric::new(universe_size as u64, pathway_size as u64, term_size as u64) {
+        Ok(dist) => dist.sf(shared as u64 - 1),
+        Err(_) => 1.0,
+    }
+}
+
+// The background proteins of the taxon are the universe of the test, so pathway members outside it are ignored.
+// Only GO term and pathway pairs sharing at least one protein are returned.
+pub fn pathway_overlap(
+    go_terms: &[GOTermID],
+    term_proteins: &GOTermToProteinSet,
+    gene_sets: &[GeneSet],
+    universe: &ProteinToGO,
+) -> Vec<PathwayOverlap> {
+    let pathways: Vec<(&GeneSet, usize)> = gene_sets
+        .iter()
+        .map(|gene_set| {
+            let size = gene_set.members.iter().filter(|member| universe.contains_key(*member)).count();
+            (gene_set, size)
+        })
+        .filter(|&(_, size)| size > 0)
+        .collect();
+
+    let mut overlaps = Vec::new();
+
+    for &go_term in go_terms {
+        let Some(proteins) = term_proteins.get(&go_term) else { continue };
+
+        for &(gene_set, pathway_size) in &pathways {
+            let shared_proteins = proteins
+                .iter()
+                .filter(|protein| gene_set.members.contains(protein.as_ref()))
+                .count();
+            if shared_proteins == 0 {
+                continue;
+            }
+
+            let union_size = proteins.len() + pathway_size - shared_proteins;
+
+            overlaps.push(PathwayOverlap {
+                go_term,
+                pathway: gene_set.name.clone(),
+                shared_proteins,
+                jaccard: shared_proteins as f64 / union_size as f64,
+                p_value: overlap_p_value(shared_proteins, proteins.len(), pathway_size, universe.len()),
+            });
+        }
+    }
+
+    overlaps.sort_by(|a, b| a.p_value.partial_cmp(&b.p_value).unwrap_or(Equal));
+    overlaps
+}
+
+pub fn write_pathway_overlap(
+    overlaps: &[PathwayOverlap],
+    ontology: &OboMap,
+    pathway_source: &str,
+    output_file: &PathBuf,
+) -> Result<(), Box<dyn Error>> {
+    println!("Writing pathway overlap to: {}\n", output_file.display());
+
+    let file = File::create(output_file)?;
+    let mut writer = BufWriter::new(file);
+
+    writer.write_all(b"GO_ID\tGO_Name\tPathway_Name\tPathway_Source\tJaccard_Overlap\tp_value\n")?;
+
+    let mut term_names: FxHashMap<GOTermID, &str> = FxHashMap::default();
+
+    for overlap in overlaps {
+        let name = *term_names
+            .entry(overlap.go_term)
+            .or_insert_with(|| ontology.get(&overlap.go_term).map_or("", |term| term.name.as_str()));
+
+        writeln!(
+            writer,
+            "GO:{:07}\t{}\t{}\t{}\t{:.4}\t{:.5e}",
+            overlap.go_term,
+            name,
+            overlap.pathway,
+            pathway_source,
+            overlap.jaccard,
+            overlap.p_value
+        )?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use compact_str::CompactString;
+    use rustc_hash::FxHashSet;
+    use std::sync::Arc;
+
+    fn gene_set(name: &str, members: &[&str]) -> GeneSet {
+        GeneSet {
+            name: name.to_string(),
+            description: String::new(),
+            members: members.iter().map(|member| CompactString::from(*member)).collect(),
+        }
+    }
+
+    // A universe of 10 proteins, where GO term 1 has the proteins P1, P2 and P3.
+    fn overlaps(gene_sets: &[GeneSet]) -> Vec<PathwayOverlap> {
+        let universe: ProteinToGO = (1..=10)
+            .map(|idx| (CompactString::from(format!("P{}", idx)), FxHashSet::default()))
+            .collect();
+        let term_proteins: GOTermToProteinSet = [(
+            1,
+            ["P1", "P2", "P3"].into_iter().map(|protein| Arc::new(CompactString::from(protein))).collect()
+        )].into_iter().collect();
+
+        pathway_overlap(&[1, 2], &term_proteins, gene_sets, &universe)
+    }
+
+    #[test]
+    fn overlap_matches_the_hand_computed_jaccard_and_hypergeometric_tail() {
+        let results = overlaps(&[
+            gene_set("broad", &["P3", "P4", "P5", "P6"]),
+            gene_set("specific", &["P1", "P2", "OUTSIDE"]),
+        ]);
+
+        assert_eq!(results.len(), 2);
+
+        // The pathway member outside the universe is ignored, leaving 2 members that are both shared.
+        // P(X >= 2) for 3 draws from 10 proteins with 2 in the pathway is C(2,2) C(8,1) / C(10,3) = 8/120.
+        assert_eq!(results[0].pathway, "specific");
+        assert_eq!(results[0].shared_proteins, 2);
+        assert!((results[0].jaccard - 2.0 / 3.0).abs() < 1e-12);
+        assert!((results[0].p_value - 8.0 / 120.0).abs() < 1e-9);
+
+        // P(X >= 1) with 4 of the 10 proteins in the pathway is 1 - C(6,3) / C(10,3) = 1 - 20/120.
+        assert_eq!(results[1].pathway, "broad");
+        assert_eq!(results[1].shared_proteins, 1);
+        assert!((results[1].jaccard - 1.0 / 6.0).abs() < 1e-12);
+        assert!((results[1].p_value - 100.0 / 120.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn pathways_without_shared_proteins_are_left_out() {
+        let results = overlaps(&[
+            gene_set("disjoint", &["P7", "P8"]),
+            gene_set("outside_universe", &["P1_ISOFORM", "OUTSIDE"]),
+        ]);
+
+        assert!(results.is_empty());
+    }
+}
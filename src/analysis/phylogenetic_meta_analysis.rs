@@ -10,12 +10,17 @@ use rand::{
     rngs::StdRng
 };
 use rayon::prelude::*;
+use statrs::distribution::{ChiSquared, ContinuousCDF};
 use std::path::PathBuf;
 #[derive(Debug, Clone)]
 pub struct TaxonomyGOResult {
     pub log_odds_ratio: f64,
     pub p_value: f64,
-    pub species_number: usize
+    pub species_number: usize,
+    pub combined_variance: f64,
+    pub q_statistic: f64,
+    pub q_p_value: f64,
+    pub i_squared: f64
 }
 fn ndarray2_to_nalgebra(arr: &Array2<f64>) -> DMatrix<f64> {
     let (nrows, ncols) = arr.dim();
@@ -26,6 +31,44 @@ fn nalgebra_to_ndarray2(mat: &DMatrix<f64>) -> Array2<f64> {
     Array2::from_shape_fn((nrows, ncols), |(r, c)| mat[(r, c)])
 }
 
+// Cochran's Q with fixed-effect weights 1 / v_i, its chi-squared p-value on k - 1 degrees of freedom and I² (%).
+pub fn cochrans_q(
+    log_odds_ratios: &[f64],
+    variances: &[f64]
+) -> (f64, f64, f64) {
+    let k = log_odds_ratios.len();
+    if k < 2 {
+        return (0.0, 1.0, 0.0);
+    }
+
+    let weights: Vec<f64> = variances.iter().map(|variance| 1.0 / variance).collect();
+    let weight_sum: f64 = weights.iter().sum();
+    let fixed_effect_mean = log_odds_ratios
+        .iter()
+        .zip(weights.iter())
+        .map(|(lor, weight)| lor * weight)
+        .sum::<f64>() / weight_sum;
+
+    let q_statistic: f64 = log_odds_ratios
+        .iter()
+        .zip(weights.iter())
+        .map(|(lor, weight)| weight * (lor - fixed_effect_mean).powi(2))
+        .sum();
+
+    let degrees_of_freedom = (k - 1) as f64;
+    let q_p_value = match ChiSquared::new(degrees_of_freedom) {
+        Ok(distribution) => distribution.sf(q_statistic),
+        Err(_) => 1.0,
+    };
+    let i_squared = if q_statistic > 0.0 {
+        ((q_statistic - degrees_of_freedom) / q_statistic).max(0.0) * 100.0
+    } else {
+        0.0
+    };
+
+    (q_statistic, q_p_value, i_squared)
+}
+
 pub fn read_vcv_matrix(
     matrix_path: PathBuf,
 ) -> Result<DataFrame, PolarsError> {
@@ -116,7 +159,7 @@ pub fn phylogenetic_meta_analysis_calculation(
     vcv_matrix: &DataFrame,
     permutations: u32,
     seed: u64
-) -> (f64, f64) {
+) -> (f64, f64, f64) {
     let weights = variance_array.mapv(|x| 1.0 / x);
     
     let n = log_odds_array.len();
@@ -186,7 +229,8 @@ pub fn phylogenetic_meta_analysis_calculation(
     let p_bpma = exceeds_count + 1;
     let p_value = p_bpma as f64 / (permutations as f64 + 1.0);
     
-    (b_pma, p_value)
+    // Variance of the generalized least squares estimate, (X'WX)^-1.
+    (b_pma, p_value, xt_w_x_inv)
 }
 
 
@@ -194,7 +238,8 @@ pub fn phylogenetic_meta_analysis(
     taxon_ids: &FxHashSet<TaxonID>,
     lineage_results: FxHashMap<String, FxHashMap<TaxonID, FxHashMap<GOTermID, GOTermResults>>>,
     superkingdom_vcv_matrix: DataFrame,
-    permutations: u32
+    permutations: u32,
+    permutation_seed: u64
 
 ) -> FxHashMap<String, FxHashMap<GOTermID, TaxonomyGOResult>> {
     
@@ -240,34 +285,43 @@ pub fn phylogenetic_meta_analysis(
                 continue;
             }
             
+            let (q_statistic, q_p_value, i_squared) = cochrans_q(&log_odds_ratios, &variances);
+
             let b_pma: f64;
             let p_value: f64;
+            let combined_variance: f64;
             
             if log_odds_ratios.len() == 1 {
                 b_pma = log_odds_ratios[0];
                 p_value = p_values[0];
+                combined_variance = variances[0];
             } else {
                 let go_term_vcv_matrix = filter_vcv_matrix(vcv_matrix.clone(), &relevant_taxon_ids).unwrap();
                 
                 let log_odds_array = Array1::from(log_odds_ratios);
                 let variance_array = Array1::from(variances);
                 
-                let (b_pma_result, p_value_result) = phylogenetic_meta_analysis_calculation(
+                let (b_pma_result, p_value_result, variance_result) = phylogenetic_meta_analysis_calculation(
                     &log_odds_array,
                     &variance_array,
                     &go_term_vcv_matrix,
                     permutations,
-                    42
+                    permutation_seed
                 );
                 
                 b_pma = b_pma_result;
                 p_value = p_value_result;
+                combined_variance = variance_result;
             }
             let num_species_with_go_term = relevant_taxon_ids.len() as u32;
             let go_result = TaxonomyGOResult {
                 log_odds_ratio: b_pma,
                 p_value: p_value,
                 species_number: (num_species_with_go_term as usize),
+                combined_variance,
+                q_statistic,
+                q_p_value,
+                i_squared,
             };
 
             level_results.insert(go_term, go_result);
@@ -280,4 +334,52 @@ pub fn phylogenetic_meta_analysis(
     }
     
     results
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cochrans_q_of_heterogeneous_estimates() {
+        // Three studies with equal variances 0.1 (w = 10) around a fixed-effect mean of 1.0:
+        // Q = 10 * (0.25 + 0 + 0.25) = 5, df = 2, I² = (5 - 2) / 5 = 60%, p = exp(-5 / 2).
+        let (q_statistic, q_p_value, i_squared) = cochrans_q(&[0.5, 1.0, 1.5], &[0.1, 0.1, 0.1]);
+
+        assert!((q_statistic - 5.0).abs() < 1e-9, "got Q = {}", q_statistic);
+        assert!((q_p_value - (-2.5_f64).exp()).abs() < 1e-9, "got p = {}", q_p_value);
+        assert!((i_squared - 60.0).abs() < 1e-9, "got I² = {}", i_squared);
+    }
+
+    #[test]
+    fn cochrans_q_of_homogeneous_estimates() {
+        let (q_statistic, q_p_value, i_squared) = cochrans_q(&[0.8, 0.8, 0.8, 0.8], &[0.2, 0.1, 0.4, 0.3]);
+
+        assert!(q_statistic.abs() < 1e-12);
+        assert!((q_p_value - 1.0).abs() < 1e-9);
+        assert_eq!(i_squared, 0.0);
+    }
+
+    #[test]
+    fn cochrans_q_needs_two_estimates() {
+        assert_eq!(cochrans_q(&[1.2], &[0.1]), (0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn permutation_p_values_are_reproducible_with_a_seed() {
+        let vcv_matrix = df!(
+            "1" => [1.0, 0.5, 0.2, 0.1],
+            "2" => [0.5, 1.0, 0.2, 0.1],
+            "3" => [0.2, 0.2, 1.0, 0.3],
+            "4" => [0.1, 0.1, 0.3, 1.0]
+        ).unwrap();
+        let log_odds_array = Array1::from(vec![1.2, 0.4, -0.3, 0.9]);
+        let variance_array = Array1::from(vec![0.2, 0.1, 0.3, 0.25]);
+
+        let first = phylogenetic_meta_analysis_calculation(&log_odds_array, &variance_array, &vcv_matrix, 500, 42);
+        let second = phylogenetic_meta_analysis_calculation(&log_odds_array, &variance_array, &vcv_matrix, 500, 42);
+
+        assert_eq!(first, second);
+        assert!(first.1 > 0.0 && first.1 <= 1.0);
+    }
+}
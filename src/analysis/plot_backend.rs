@@ -0,0 +1,248 @@
+use rustc_hash::FxHashMap;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use clap::ValueEnum;
+use serde_json::{json, Value};
+use petgraph::visit::{EdgeRef, IntoEdgeReferences};
+use crate::parsers::obo_parser::*;
+use crate::analysis::{
+    enrichment_plots::*,
+    write_results::sanitize_taxon_name
+};
+
+const VEGA_LITE_SCHEMA: &str = "https://vega.github.io/schema/vega-lite/v5.json";
+
+pub type PlotDataMap = FxHashMap<String, FxHashMap<NameSpace, Vec<GOTermPlotData>>>;
+pub type NetworkMap = FxHashMap<String, FxHashMap<NameSpace, Vec<GoTermNetworkGraph>>>;
+
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq)]
+pub enum PlotBackendType {
+    Plotly,
+    VegaLite
+}
+
+// Each method draws one plot per taxon and namespace, under `{plots_dir}/{Namespace}/`.
+pub trait PlotBackend: Sync {
+    fn render_bar(&self, plot_data_map: &PlotDataMap, plots_dir: &Path) -> Result<(), Box<dyn Error + Send + Sync>>;
+    fn render_bubble(&self, plot_data_map: &PlotDataMap, plots_dir: &Path) -> Result<(), Box<dyn Error + Send + Sync>>;
+    fn render_network(&self, networks_map: &NetworkMap, plots_dir: &Path) -> Result<(), Box<dyn Error + Send + Sync>>;
+}
+
+pub fn plot_backend(
+    backend_type: PlotBackendType,
+    plot_type: PlotType,
+    color_by: NetworkColor,
+    layout_config: FrLayoutConfig,
+    effect_label: &'static str
+) -> Box<dyn PlotBackend> {
+    match backend_type {
+        PlotBackendType::Plotly => Box::new(PlotlyBackend { plot_type, color_by, layout_config, effect_label }),
+        PlotBackendType::VegaLite => Box::new(VegaLiteBackend { color_by, layout_config, effect_label }),
+    }
+}
+
+pub struct PlotlyBackend {
+    pub plot_type: PlotType,
+    pub color_by: NetworkColor,
+    pub layout_config: FrLayoutConfig,
+    pub effect_label: &'static str,
+}
+
+impl PlotBackend for PlotlyBackend {
+    fn render_bar(&self, plot_data_map: &PlotDataMap, plots_dir: &Path) -> Result<(), Box<dyn Error + Send + Sync>> {
+        bar_plot(plot_data_map, plots_dir, self.plot_type, self.effect_label)
+    }
+
+    fn render_bubble(&self, plot_data_map: &PlotDataMap, plots_dir: &Path) -> Result<(), Box<dyn Error + Send + Sync>> {
+        bubble_plot(plot_data_map, plots_dir, self.plot_type, self.effect_label)
+    }
+
+    fn render_network(&self, networks_map: &NetworkMap, plots_dir: &Path) -> Result<(), Box<dyn Error + Send + Sync>> {
+        network_plot(networks_map, plots_dir, self.plot_type, self.color_by, &self.layout_config, self.effect_label)
+    }
+}
+
+// Writes Vega-Lite specifications (`.vl.json`) with the data inlined, so the plots can be rendered
+// by any Vega-Lite viewer without a browser or Kaleido at analysis time.
+pub struct VegaLiteBackend {
+    pub color_by: NetworkColor,
+    pub layout_config: FrLayoutConfig,
+    pub effect_label: &'static str,
+}
+
+fn term_values(term: &GOTermPlotData) -> Value {
+    json!({
+        "go_id": format!("GO:{:07}", term.go_id),
+        "name": term.name,
+        "lor": term.lor,
+        "minus_log10_p": term.minus_log10_p_value,
+        "size": term.size_statistic,
+        "betweenness": term.betweenness
+    })
+}
+
+fn term_tooltip(effect_label: &str) -> Value {
+    json!([
+        {"field": "name", "type": "nominal", "title": "Term Name"},
+        {"field": "go_id", "type": "nominal", "title": "Term ID"},
+        {"field": "lor", "type": "quantitative", "title": effect_label, "format": ".3f"},
+        {"field": "minus_log10_p", "type": "quantitative", "title": "-log10(Stat. Sig.)", "format": ".3f"}
+    ])
+}
+
+fn write_spec(
+    spec: &Value,
+    namespace: &NameSpace,
+    plots_dir: &Path,
+    taxon_name: &str,
+    plot_name: &str
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let namespace_subdir = get_namespace_subdir(namespace, plots_dir)?;
+    let spec_file = namespace_subdir.join(format!("{}_{}.vl.json", sanitize_taxon_name(taxon_name), plot_name));
+    fs::write(spec_file, serde_json::to_string_pretty(spec)?)?;
+    Ok(())
+}
+
+impl PlotBackend for VegaLiteBackend {
+    fn render_bar(&self, plot_data_map: &PlotDataMap, plots_dir: &Path) -> Result<(), Box<dyn Error + Send + Sync>> {
+        for (taxon_name, namespace_map) in plot_data_map {
+            for (namespace, namespace_plot_data) in namespace_map {
+                let values: Vec<Value> = namespace_plot_data.iter().take(20).map(term_values).collect();
+
+                let spec = json!({
+                    "$schema": VEGA_LITE_SCHEMA,
+                    "width": 360,
+                    "height": 880,
+                    "data": {"values": values},
+                    "mark": "bar",
+                    "encoding": {
+                        "x": {"field": "lor", "type": "quantitative", "title": self.effect_label},
+                        "y": {"field": "name", "type": "nominal", "title": null, "sort": "x"},
+                        "color": {
+                            "field": "minus_log10_p",
+                            "type": "quantitative",
+                            "title": "-log10(Stat. Sig.)",
+                            "scale": {"scheme": "cividis"}
+                        },
+                        "tooltip": term_tooltip(self.effect_label)
+                    }
+                });
+                write_spec(&spec, namespace, plots_dir, taxon_name, "bar_plot")?;
+            }
+        }
+        Ok(())
+    }
+
+    fn render_bubble(&self, plot_data_map: &PlotDataMap, plots_dir: &Path) -> Result<(), Box<dyn Error + Send + Sync>> {
+        for (taxon_name, namespace_map) in plot_data_map {
+            for (namespace, namespace_plot_data) in namespace_map {
+                let values: Vec<Value> = namespace_plot_data.iter().map(term_values).collect();
+
+                let spec = json!({
+                    "$schema": VEGA_LITE_SCHEMA,
+                    "width": 840,
+                    "height": 420,
+                    "data": {"values": values},
+                    "mark": {"type": "circle", "color": "rgb(156, 148, 120)", "opacity": 0.9},
+                    "encoding": {
+                        "x": {"field": "lor", "type": "quantitative", "title": self.effect_label},
+                        "y": {"field": "minus_log10_p", "type": "quantitative", "title": "-log10(Stat. Sig.)"},
+                        "size": {"field": "size", "type": "quantitative", "title": "GO Term size"},
+                        "tooltip": term_tooltip(self.effect_label)
+                    }
+                });
+                write_spec(&spec, namespace, plots_dir, taxon_name, "bubble_plot")?;
+            }
+        }
+        Ok(())
+    }
+
+    // Vega-Lite has no force-directed layout, so node positions come from the same Fruchterman-Reingold
+    // layout as the Plotly networks. Communities are drawn as separate facets.
+    fn render_network(&self, networks_map: &NetworkMap, plots_dir: &Path) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let (color_field, color_title) = match self.color_by {
+            NetworkColor::LogOddsRatio => ("lor", self.effect_label),
+            NetworkColor::Betweenness => ("betweenness", "Betweenness"),
+        };
+
+        for (taxon_name, namespace_map) in networks_map {
+            for (namespace, networks_vec) in namespace_map {
+                if networks_vec.is_empty() {
+                    continue;
+                }
+
+                let mut values: Vec<Value> = Vec::new();
+                for (community, network_graph) in networks_vec.iter().enumerate() {
+                    let graph = apply_fruchterman_reingold_layout(network_graph, &self.layout_config);
+
+                    for edge_ref in graph.edge_references() {
+                        if let (Some(source), Some(target)) = (graph.node_weight(edge_ref.source()), graph.node_weight(edge_ref.target())) {
+                            values.push(json!({
+                                "kind": "edge",
+                                "community": community + 1,
+                                "x": source.1.x,
+                                "y": source.1.y,
+                                "x2": target.1.x,
+                                "y2": target.1.y,
+                                "jaccard": edge_ref.weight()
+                            }));
+                        }
+                    }
+
+                    for (node_plot_data, location) in graph.node_weights() {
+                        let mut node = term_values(node_plot_data);
+                        node["kind"] = json!("node");
+                        node["community"] = json!(community + 1);
+                        node["x"] = json!(location.x);
+                        node["y"] = json!(location.y);
+                        values.push(node);
+                    }
+                }
+
+                let spec = json!({
+                    "$schema": VEGA_LITE_SCHEMA,
+                    "data": {"values": values},
+                    "facet": {"field": "community", "type": "ordinal", "title": "Community"},
+                    "columns": 2,
+                    "resolve": {"scale": {"x": "independent", "y": "independent"}},
+                    "spec": {
+                        "width": 420,
+                        "height": 210,
+                        "layer": [
+                            {
+                                "transform": [{"filter": "datum.kind === 'edge'"}],
+                                "mark": {"type": "rule", "color": "rgba(200, 200, 200, 0.5)"},
+                                "encoding": {
+                                    "x": {"field": "x", "type": "quantitative", "axis": null},
+                                    "y": {"field": "y", "type": "quantitative", "axis": null},
+                                    "x2": {"field": "x2"},
+                                    "y2": {"field": "y2"},
+                                    "strokeWidth": {"field": "jaccard", "type": "quantitative", "title": "Jaccard Index", "scale": {"range": [2, 8]}}
+                                }
+                            },
+                            {
+                                "transform": [{"filter": "datum.kind === 'node'"}],
+                                "mark": {"type": "circle", "opacity": 1.0},
+                                "encoding": {
+                                    "x": {"field": "x", "type": "quantitative", "axis": null},
+                                    "y": {"field": "y", "type": "quantitative", "axis": null},
+                                    "size": {"field": "size", "type": "quantitative", "title": "GO Term size"},
+                                    "color": {
+                                        "field": color_field,
+                                        "type": "quantitative",
+                                        "title": color_title,
+                                        "scale": {"scheme": "viridis"}
+                                    },
+                                    "tooltip": term_tooltip(self.effect_label)
+                                }
+                            }
+                        ]
+                    }
+                });
+                write_spec(&spec, namespace, plots_dir, taxon_name, "network_plot")?;
+            }
+        }
+        Ok(())
+    }
+}
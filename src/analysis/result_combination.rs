@@ -1,12 +1,24 @@
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
+use polars::prelude::*;
+use statrs::distribution::{ChiSquared, Normal, ContinuousCDF};
+use clap::ValueEnum;
 use crate::{
     analysis::enrichment_analysis::*, 
+    analysis::phylogenetic_meta_analysis::*,
     parsers::background_parser::*};
 
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq)]
+pub enum CombinationMethod {
+    Phylogenetic,
+    Stouffer,
+    Fishers,
+}
+
 pub fn group_results_by_taxonomy(
     family_taxa: &FxHashMap<String, Vec<TaxonID>>,
     fisher_results: &FxHashMap<TaxonID, FxHashMap<GOTermID, GOTermResults>>,
     threshold: f64,
+    min_species_count: usize,
 ) -> FxHashMap<String, FxHashMap<TaxonID, FxHashMap<GOTermID, GOTermResults>>> {
     let mut result = FxHashMap::default();
     let mut go_term_counts: FxHashMap<String, FxHashMap<GOTermID, usize>> = FxHashMap::default();
@@ -14,7 +26,7 @@ pub fn group_results_by_taxonomy(
 
     count_species_and_go_terms(family_taxa, fisher_results, &mut actual_species_counts, &mut go_term_counts);
 
-    process_and_filter_results(family_taxa, fisher_results, threshold, &actual_species_counts, &go_term_counts, &mut result);
+    process_and_filter_results(family_taxa, fisher_results, threshold, min_species_count, &actual_species_counts, &go_term_counts, &mut result);
 
     result
 }
@@ -43,6 +55,7 @@ fn process_and_filter_results(
     family_taxa: &FxHashMap<String, Vec<u32>>,
     fisher_results: &FxHashMap<u32, FxHashMap<u32, GOTermResults>>,
     threshold: f64,
+    min_species_count: usize,
     actual_species_counts: &FxHashMap<String, usize>,
     go_term_counts: &FxHashMap<String, FxHashMap<u32, usize>>,
     result: &mut FxHashMap<String, FxHashMap<TaxonID, FxHashMap<GOTermID, GOTermResults>>>,
@@ -54,7 +67,8 @@ fn process_and_filter_results(
             return;
         }
 
-        let min_required_count = (threshold * species_count as f64).ceil() as usize;
+        // Both the fraction of the group and the absolute number of species must be reached.
+        let min_required_count = ((threshold * species_count as f64).ceil() as usize).max(min_species_count);
 
         let empty_map = FxHashMap::default();
         let family_go_terms = go_term_counts.get(family).unwrap_or(&empty_map);
@@ -88,4 +102,319 @@ fn process_and_filter_results(
             result.insert(family.clone(), taxa_map);
         }
     });
-}
\ No newline at end of file
+}
+
+pub fn vcv_diagonal_weights(
+    vcv_matrix: &DataFrame
+) -> Result<FxHashMap<TaxonID, f64>, PolarsError> {
+    let taxa_column = vcv_matrix.column("taxa")?.str()?;
+    let mut weights = FxHashMap::default();
+
+    for (row, taxon) in taxa_column.into_iter().enumerate() {
+        let Some(taxon) = taxon else { continue };
+        let Ok(taxon_id) = taxon.parse::<TaxonID>() else { continue };
+
+        let value = vcv_matrix
+            .column(taxon)?
+            .cast(&DataType::Float64)?
+            .f64()?
+            .get(row);
+
+        if let Some(weight) = value {
+            weights.insert(taxon_id, weight);
+        }
+    }
+
+    Ok(weights)
+}
+
+pub fn stouffer_z_score(
+    p_values: &[f64],
+    weights: &[f64]
+) -> f64 {
+    let standard_normal = Normal::new(0.0, 1.0).unwrap();
+
+    let (weighted_z_sum, squared_weight_sum) = p_values
+        .iter()
+        .zip(weights.iter())
+        .fold((0.0, 0.0), |(z_sum, w_sum), (&p_value, &weight)| {
+            let clamped_p = p_value.clamp(f64::MIN_POSITIVE, 1.0 - f64::EPSILON);
+            let z_score = -standard_normal.inverse_cdf(clamped_p);
+            (z_sum + weight * z_score, w_sum + weight * weight)
+        });
+
+    if squared_weight_sum == 0.0 {
+        return 0.0;
+    }
+
+    weighted_z_sum / squared_weight_sum.sqrt()
+}
+
+pub fn stouffer_p_value(
+    p_values: &[f64],
+    weights: &[f64]
+) -> f64 {
+    let standard_normal = Normal::new(0.0, 1.0).unwrap();
+    standard_normal.sf(stouffer_z_score(p_values, weights))
+}
+
+// Variance of a weighted mean of independent estimates: sum(w_i^2 * v_i) / (sum(w_i))^2.
+pub fn weighted_mean_variance(variances: &[f64], weights: &[f64]) -> f64 {
+    let weight_sum: f64 = weights.iter().sum();
+    variances
+        .iter()
+        .zip(weights.iter())
+        .map(|(variance, weight)| weight * weight * variance)
+        .sum::<f64>() / (weight_sum * weight_sum)
+}
+
+pub fn stouffer_combination(
+    taxon_ids: &FxHashSet<TaxonID>,
+    lineage_results: FxHashMap<String, FxHashMap<TaxonID, FxHashMap<GOTermID, GOTermResults>>>,
+    superkingdom_vcv_matrix: DataFrame,
+) -> FxHashMap<String, FxHashMap<GOTermID, TaxonomyGOResult>> {
+    let vcv_matrix = filter_vcv_matrix(superkingdom_vcv_matrix, taxon_ids).unwrap();
+    let taxon_weights = vcv_diagonal_weights(&vcv_matrix).unwrap();
+
+    let mut results = FxHashMap::default();
+
+    for (level, taxon_map) in lineage_results {
+        let mut level_results = FxHashMap::default();
+
+        let all_go_terms: FxHashSet<GOTermID> = taxon_map
+            .values()
+            .flat_map(|go_term_map| go_term_map.keys().cloned())
+            .collect();
+
+        for go_term in all_go_terms {
+            let mut log_odds_ratios = Vec::new();
+            let mut p_values = Vec::new();
+            let mut variances = Vec::new();
+            let mut weights = Vec::new();
+
+            for (taxon_id, go_term_map) in &taxon_map {
+                let (Some(result), Some(&weight)) = (go_term_map.get(&go_term), taxon_weights.get(taxon_id)) else {
+                    continue;
+                };
+                log_odds_ratios.push(result.log_odds_ratio);
+                p_values.push(result.p_value);
+                variances.push(result.variance);
+                weights.push(weight);
+            }
+
+            if p_values.is_empty() {
+                continue;
+            }
+
+            let (log_odds_ratio, p_value, combined_variance) = if p_values.len() == 1 {
+                (log_odds_ratios[0], p_values[0], variances[0])
+            } else {
+                let weight_sum: f64 = weights.iter().sum();
+                let (weighted_log_odds_ratio, combined_variance) = if weight_sum > 0.0 {
+                    (
+                        log_odds_ratios
+                            .iter()
+                            .zip(weights.iter())
+                            .map(|(lor, weight)| lor * weight)
+                            .sum::<f64>() / weight_sum,
+                        weighted_mean_variance(&variances, &weights)
+                    )
+                } else {
+                    (
+                        log_odds_ratios.iter().sum::<f64>() / log_odds_ratios.len() as f64,
+                        weighted_mean_variance(&variances, &vec![1.0; variances.len()])
+                    )
+                };
+                (weighted_log_odds_ratio, stouffer_p_value(&p_values, &weights), combined_variance)
+            };
+
+            let (q_statistic, q_p_value, i_squared) = cochrans_q(&log_odds_ratios, &variances);
+
+            level_results.insert(go_term, TaxonomyGOResult {
+                log_odds_ratio,
+                p_value,
+                species_number: p_values.len(),
+                combined_variance,
+                q_statistic,
+                q_p_value,
+                i_squared,
+            });
+        }
+
+        if !level_results.is_empty() {
+            results.insert(level, level_results);
+        }
+    }
+
+    results
+}
+
+pub fn fishers_combined_p_value(p_values: &[f64]) -> f64 {
+    let chi_squared_statistic: f64 = p_values
+        .iter()
+        .map(|&p_value| -2.0 * p_value.clamp(f64::MIN_POSITIVE, 1.0).ln())
+        .sum();
+
+    match ChiSquared::new(2.0 * p_values.len() as f64) {
+        Ok(distribution) => distribution.sf(chi_squared_statistic),
+        Err(_) => 1.0,
+    }
+}
+
+pub fn fishers_combination(
+    lineage_results: FxHashMap<String, FxHashMap<TaxonID, FxHashMap<GOTermID, GOTermResults>>>,
+) -> FxHashMap<String, FxHashMap<GOTermID, TaxonomyGOResult>> {
+    let mut results = FxHashMap::default();
+
+    for (level, taxon_map) in lineage_results {
+        let mut level_results = FxHashMap::default();
+
+        let all_go_terms: FxHashSet<GOTermID> = taxon_map
+            .values()
+            .flat_map(|go_term_map| go_term_map.keys().cloned())
+            .collect();
+
+        for go_term in all_go_terms {
+            let go_term_results: Vec<&GOTermResults> = taxon_map
+                .values()
+                .filter_map(|go_term_map| go_term_map.get(&go_term))
+                .collect();
+
+            if go_term_results.is_empty() {
+                continue;
+            }
+
+            let p_values: Vec<f64> = go_term_results.iter().map(|result| result.p_value).collect();
+            let variances: Vec<f64> = go_term_results.iter().map(|result| result.variance).collect();
+            let log_odds_ratios: Vec<f64> = go_term_results.iter().map(|result| result.log_odds_ratio).collect();
+            let log_odds_ratio = log_odds_ratios.iter().sum::<f64>() / log_odds_ratios.len() as f64;
+            let (q_statistic, q_p_value, i_squared) = cochrans_q(&log_odds_ratios, &variances);
+
+            level_results.insert(go_term, TaxonomyGOResult {
+                log_odds_ratio,
+                p_value: fishers_combined_p_value(&p_values),
+                species_number: p_values.len(),
+                combined_variance: weighted_mean_variance(&variances, &vec![1.0; variances.len()]),
+                q_statistic,
+                q_p_value,
+                i_squared,
+            });
+        }
+
+        if !level_results.is_empty() {
+            results.insert(level, level_results);
+        }
+    }
+
+    results
+}
+
+// Drops combined GO terms that are significant in fewer than `min_species_percentage` percent of the species in their group.
+pub fn filter_by_species_percentage(
+    taxonomy_results: &mut FxHashMap<String, FxHashMap<GOTermID, TaxonomyGOResult>>,
+    grouped_species: &FxHashMap<String, Vec<TaxonID>>,
+    significant_species_terms: &FxHashMap<TaxonID, FxHashSet<GOTermID>>,
+    min_species_percentage: f64,
+) {
+    for (level, go_terms) in taxonomy_results.iter_mut() {
+        let Some(taxa) = grouped_species.get(level).filter(|taxa| !taxa.is_empty()) else { continue };
+
+        go_terms.retain(|go_term, _| {
+            let significant_species = taxa
+                .iter()
+                .filter(|taxon_id| significant_species_terms
+                    .get(taxon_id)
+                    .is_some_and(|terms| terms.contains(go_term)))
+                .count();
+            significant_species as f64 * 100.0 / taxa.len() as f64 >= min_species_percentage
+        });
+    }
+
+    taxonomy_results.retain(|_, go_terms| !go_terms.is_empty());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stouffer_of_uninformative_p_values_is_one_half() {
+        let p_value = stouffer_p_value(&[0.5, 0.5, 0.5], &[1.0, 2.0, 0.5]);
+        assert!((p_value - 0.5).abs() < 1e-9, "got {}", p_value);
+    }
+
+    #[test]
+    fn stouffer_of_tiny_p_values_is_tiny() {
+        let p_value = stouffer_p_value(&[1e-12, 1e-10, 1e-15], &[1.0, 1.0, 1.0]);
+        assert!(p_value < 1e-15, "got {}", p_value);
+    }
+
+    #[test]
+    fn stouffer_without_weights_returns_zero_z() {
+        assert_eq!(stouffer_z_score(&[0.01, 0.02], &[0.0, 0.0]), 0.0);
+    }
+
+    #[test]
+    fn fishers_method_with_three_p_values_of_five_percent() {
+        let chi_squared_statistic = -2.0 * 3.0 * 0.05_f64.ln();
+        assert!((chi_squared_statistic - 17.97).abs() < 0.01);
+
+        let p_value = fishers_combined_p_value(&[0.05, 0.05, 0.05]);
+        assert!((p_value - 0.0063).abs() < 1e-4, "got {}", p_value);
+    }
+
+    #[test]
+    fn fishers_method_of_a_single_p_value_returns_it() {
+        let p_value = fishers_combined_p_value(&[0.2]);
+        assert!((p_value - 0.2).abs() < 1e-12, "got {}", p_value);
+    }
+
+    fn taxonomy_result() -> TaxonomyGOResult {
+        TaxonomyGOResult {
+            log_odds_ratio: 1.0,
+            p_value: 0.001,
+            species_number: 4,
+            combined_variance: 0.1,
+            q_statistic: 0.0,
+            q_p_value: 1.0,
+            i_squared: 0.0,
+        }
+    }
+
+    #[test]
+    fn terms_below_the_species_percentage_are_removed() {
+        let mut taxonomy_results: FxHashMap<String, FxHashMap<GOTermID, TaxonomyGOResult>> = FxHashMap::default();
+        taxonomy_results.insert(
+            "Metazoa".to_string(),
+            [(1, taxonomy_result()), (2, taxonomy_result())].into_iter().collect(),
+        );
+
+        let mut grouped_species: FxHashMap<String, Vec<TaxonID>> = FxHashMap::default();
+        grouped_species.insert("Metazoa".to_string(), vec![10, 20, 30, 40]);
+
+        // GO term 1 is significant in 3 of 4 species (75%), GO term 2 in 1 of 4 (25%).
+        let mut significant_species_terms: FxHashMap<TaxonID, FxHashSet<GOTermID>> = FxHashMap::default();
+        significant_species_terms.insert(10, [1, 2].into_iter().collect());
+        significant_species_terms.insert(20, [1].into_iter().collect());
+        significant_species_terms.insert(30, [1].into_iter().collect());
+
+        filter_by_species_percentage(&mut taxonomy_results, &grouped_species, &significant_species_terms, 50.0);
+
+        let metazoa = &taxonomy_results["Metazoa"];
+        assert!(metazoa.contains_key(&1));
+        assert!(!metazoa.contains_key(&2));
+    }
+
+    #[test]
+    fn groups_without_remaining_terms_are_removed() {
+        let mut taxonomy_results: FxHashMap<String, FxHashMap<GOTermID, TaxonomyGOResult>> = FxHashMap::default();
+        taxonomy_results.insert("Fungi".to_string(), [(1, taxonomy_result())].into_iter().collect());
+
+        let mut grouped_species: FxHashMap<String, Vec<TaxonID>> = FxHashMap::default();
+        grouped_species.insert("Fungi".to_string(), vec![10, 20]);
+
+        filter_by_species_percentage(&mut taxonomy_results, &grouped_species, &FxHashMap::default(), 10.0);
+
+        assert!(taxonomy_results.is_empty());
+    }
+}
@@ -0,0 +1,290 @@
+use rustc_hash::FxHashMap;
+use std::fs::{read_dir, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::error::Error;
+use std::path::Path;
+use serde_json::Value;
+use crate::parsers::{
+    background_parser::*,
+    obo_parser::*
+};
+use crate::analysis::{
+    enrichment_analysis::StatisticalTest,
+    write_results::RESULT_COLUMNS,
+};
+
+const RESULTS_FILE_SUFFIX: &str = "_GOEA_results";
+// Every extension `--output-format` can write.
+pub const RESULTS_FILE_EXTENSIONS: [&str; 3] = ["txt", "csv", "json"];
+const LOG_ODDS_RATIO_COLUMN: usize = 3;
+const SIGNIFICANCE_COLUMN: usize = 4;
+
+#[derive(Debug, Clone)]
+pub struct TermSummary {
+    pub significant_species: usize,
+    pub mean_log_odds_ratio: f64,
+    pub std_dev_log_odds_ratio: f64,
+    pub min_log_odds_ratio: f64,
+    pub max_log_odds_ratio: f64,
+}
+
+// Returns the taxon name of a `{taxon}_GOEA_results.{txt,csv,json}` file name.
+pub fn results_file_taxon(file_name: &str) -> Option<&str> {
+    let (stem, extension) = file_name.rsplit_once('.')?;
+    if !RESULTS_FILE_EXTENSIONS.contains(&extension) {
+        return None;
+    }
+    stem.strip_suffix(RESULTS_FILE_SUFFIX)
+}
+
+fn parse_go_term(go_term: &str) -> Option<GOTermID> {
+    go_term.trim().strip_prefix("GO:")?.parse::<GOTermID>().ok()
+}
+
+// (GO term, log(Odds Ratio), p-value) of one tested term.
+pub type TermResult = (GOTermID, f64, f64);
+// Single taxon results keyed by species name.
+pub type SpeciesTermResults = FxHashMap<String, Vec<TermResult>>;
+
+// One row of a `{taxon}_GOEA_results` file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResultRow {
+    pub go_term: GOTermID,
+    pub name: String,
+    pub namespace: String,
+    pub log_odds_ratio: f64,
+    pub p_value: f64,
+}
+
+// Reads a `{taxon}_GOEA_results` file into (GO term, log(Odds Ratio), p-value) rows.
+pub fn read_single_taxon_results(results_file: &Path) -> Result<Vec<TermResult>, Box<dyn Error>> {
+    Ok(read_result_rows(results_file)?
+        .into_iter()
+        .map(|row| (row.go_term, row.log_odds_ratio, row.p_value))
+        .collect())
+}
+
+// Reads every row of a `{taxon}_GOEA_results` file.
+// The format (TSV, CSV or JSON) is taken from the file extension, as written by `--output-format`.
+pub fn read_result_rows(results_file: &Path) -> Result<Vec<ResultRow>, Box<dyn Error>> {
+    match results_file.extension().and_then(|extension| extension.to_str()) {
+        Some("csv") => read_csv_results(results_file),
+        Some("json") => read_json_results(results_file),
+        _ => read_tsv_results(results_file),
+    }
+}
+
+// Column names of a `{taxon}_GOEA_results` file. JSON files have no header, so theirs are taken from the first row.
+pub fn read_results_header(results_file: &Path) -> Result<Vec<String>, Box<dyn Error>> {
+    match results_file.extension().and_then(|extension| extension.to_str()) {
+        Some("csv") => {
+            let mut reader = csv::ReaderBuilder::new()
+                .has_headers(true)
+                .flexible(true)
+                .from_path(results_file)?;
+            Ok(reader.headers()?.iter().map(|column| column.to_string()).collect())
+        }
+        Some("json") => {
+            let document: Value = serde_json::from_reader(BufReader::new(File::open(results_file)?))?;
+            let first_row = document.get("results").and_then(Value::as_array).and_then(|rows| rows.first());
+            let effect_label = effect_keys()
+                .into_iter()
+                .find(|key| first_row.is_some_and(|row| row.get(*key).is_some()))
+                .unwrap_or(RESULT_COLUMNS[LOG_ODDS_RATIO_COLUMN]);
+
+            let mut columns: Vec<String> = RESULT_COLUMNS.iter().map(|column| column.to_string()).collect();
+            columns[LOG_ODDS_RATIO_COLUMN] = effect_label.to_string();
+            Ok(columns)
+        }
+        _ => {
+            let mut header = String::new();
+            BufReader::new(File::open(results_file)?).read_line(&mut header)?;
+            Ok(header.trim_end().split('\t').map(|column| column.to_string()).collect())
+        }
+    }
+}
+
+// The effect size key depends on the test that produced the file.
+fn effect_keys() -> [&'static str; 2] {
+    [RESULT_COLUMNS[LOG_ODDS_RATIO_COLUMN], StatisticalTest::BetaBinomial.effect_size_label()]
+}
+
+fn read_tsv_results(results_file: &Path) -> Result<Vec<ResultRow>, Box<dyn Error>> {
+    let file = File::open(results_file)?;
+    let mut results = Vec::new();
+
+    for line in BufReader::new(file).lines().skip(1) {
+        let line = line?;
+        let columns: Vec<&str> = line.split('\t').collect();
+
+        let Some(go_term) = columns.first().and_then(|id| parse_go_term(id)) else { continue };
+
+        let parse_column = |index: usize| columns.get(index).and_then(|value| value.trim().parse::<f64>().ok());
+        let (Some(log_odds_ratio), Some(p_value)) = (parse_column(LOG_ODDS_RATIO_COLUMN), parse_column(SIGNIFICANCE_COLUMN)) else {
+            continue
+        };
+
+        results.push(ResultRow {
+            go_term,
+            name: columns.get(1).unwrap_or(&"").to_string(),
+            namespace: columns.get(2).unwrap_or(&"").to_string(),
+            log_odds_ratio,
+            p_value,
+        });
+    }
+
+    Ok(results)
+}
+
+fn read_csv_results(results_file: &Path) -> Result<Vec<ResultRow>, Box<dyn Error>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(true)
+        .flexible(true)
+        .from_path(results_file)?;
+    let mut results = Vec::new();
+
+    for record in reader.records() {
+        let record = record?;
+
+        let Some(go_term) = record.get(0).and_then(parse_go_term) else { continue };
+
+        let parse_column = |index: usize| record.get(index).and_then(|value| value.trim().parse::<f64>().ok());
+        let (Some(log_odds_ratio), Some(p_value)) = (parse_column(LOG_ODDS_RATIO_COLUMN), parse_column(SIGNIFICANCE_COLUMN)) else {
+            continue
+        };
+
+        results.push(ResultRow {
+            go_term,
+            name: record.get(1).unwrap_or_default().to_string(),
+            namespace: record.get(2).unwrap_or_default().to_string(),
+            log_odds_ratio,
+            p_value,
+        });
+    }
+
+    Ok(results)
+}
+
+fn read_json_results(results_file: &Path) -> Result<Vec<ResultRow>, Box<dyn Error>> {
+    let document: Value = serde_json::from_reader(BufReader::new(File::open(results_file)?))?;
+    let rows = document
+        .get("results")
+        .and_then(Value::as_array)
+        .ok_or_else(|| format!("{} has no \"results\" array", results_file.display()))?;
+
+    let effect_keys = effect_keys();
+    let text_column = |row: &Value, index: usize| row.get(RESULT_COLUMNS[index]).and_then(Value::as_str).unwrap_or_default().to_string();
+
+    let results = rows
+        .iter()
+        .filter_map(|row| {
+            let go_term = row.get(RESULT_COLUMNS[0]).and_then(Value::as_str).and_then(parse_go_term)?;
+            let log_odds_ratio = effect_keys.iter().find_map(|key| row.get(*key).and_then(Value::as_f64))?;
+            let p_value = row.get(RESULT_COLUMNS[SIGNIFICANCE_COLUMN]).and_then(Value::as_f64)?;
+            Some(ResultRow {
+                go_term,
+                name: text_column(row, 1),
+                namespace: text_column(row, 2),
+                log_odds_ratio,
+                p_value,
+            })
+        })
+        .collect();
+
+    Ok(results)
+}
+
+// Maps each species name (taken from the file name) to its single taxon results.
+pub fn read_results_dir(results_dir: &Path) -> Result<SpeciesTermResults, Box<dyn Error>> {
+    let mut species_results = FxHashMap::default();
+
+    for entry in read_dir(results_dir)? {
+        let path = entry?.path();
+        let Some(species_name) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(results_file_taxon)
+            .map(|name| name.to_string()) else { continue };
+
+        species_results.insert(species_name, read_single_taxon_results(&path)?);
+    }
+
+    Ok(species_results)
+}
+
+pub fn summarize_results(
+    species_results: &SpeciesTermResults
+) -> FxHashMap<GOTermID, TermSummary> {
+    let mut term_log_odds_ratios: FxHashMap<GOTermID, Vec<f64>> = FxHashMap::default();
+
+    for results in species_results.values() {
+        for &(go_term, log_odds_ratio, _) in results {
+            term_log_odds_ratios.entry(go_term).or_default().push(log_odds_ratio);
+        }
+    }
+
+    term_log_odds_ratios
+        .into_iter()
+        .map(|(go_term, log_odds_ratios)| {
+            let n = log_odds_ratios.len();
+            let mean = log_odds_ratios.iter().sum::<f64>() / n as f64;
+            let std_dev = if n > 1 {
+                (log_odds_ratios.iter().map(|lor| (lor - mean).powi(2)).sum::<f64>() / (n - 1) as f64).sqrt()
+            } else {
+                0.0
+            };
+
+            (go_term, TermSummary {
+                significant_species: n,
+                mean_log_odds_ratio: mean,
+                std_dev_log_odds_ratio: std_dev,
+                min_log_odds_ratio: log_odds_ratios.iter().copied().fold(f64::INFINITY, f64::min),
+                max_log_odds_ratio: log_odds_ratios.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            })
+        })
+        .collect()
+}
+
+pub fn write_results_summary(
+    summary: &FxHashMap<GOTermID, TermSummary>,
+    ontology: &OboMap,
+    output_file: &Path,
+) -> Result<(), Box<dyn Error>> {
+    println!("Writing results summary to: {}\n", output_file.display());
+
+    let file = File::create(output_file)?;
+    let mut writer = BufWriter::new(file);
+
+    writer.write_all(b"GO_ID\tName\tNamespace\tN_significant_species\tMean_LOR\tStdDev_LOR\tMin_LOR\tMax_LOR\n")?;
+
+    let mut sorted_summary: Vec<(&GOTermID, &TermSummary)> = summary.iter().collect();
+    sorted_summary.sort_by(|a, b| {
+        b.1.significant_species.cmp(&a.1.significant_species).then(a.0.cmp(b.0))
+    });
+
+    for (go_term, term_summary) in sorted_summary {
+        let (name, namespace) = match ontology.get(go_term) {
+            Some(term) => (
+                term.name.as_str(),
+                term.namespace.label()
+            ),
+            None => ("", ""),
+        };
+
+        writeln!(
+            writer,
+            "GO:{:07}\t{}\t{}\t{}\t{:.3}\t{:.3}\t{:.3}\t{:.3}",
+            go_term,
+            name,
+            namespace,
+            term_summary.significant_species,
+            term_summary.mean_log_odds_ratio,
+            term_summary.std_dev_log_odds_ratio,
+            term_summary.min_log_odds_ratio,
+            term_summary.max_log_odds_ratio
+        )?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
@@ -0,0 +1,173 @@
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::error::Error;
+use std::path::PathBuf;
+use rayon::prelude::*;
+use crate::parsers::background_parser::*;
+
+pub struct SpeciesCorrelation {
+    pub species: Vec<String>,
+    pub matrix: Vec<Vec<f64>>,
+}
+
+pub fn pearson_correlation(x: &[f64], y: &[f64]) -> f64 {
+    let n = x.len() as f64;
+    if n == 0.0 {
+        return 0.0;
+    }
+
+    let mean_x = x.iter().sum::<f64>() / n;
+    let mean_y = y.iter().sum::<f64>() / n;
+
+    let (covariance, variance_x, variance_y) = x
+        .iter()
+        .zip(y.iter())
+        .fold((0.0, 0.0, 0.0), |(cov, var_x, var_y), (&xi, &yi)| {
+            let (dx, dy) = (xi - mean_x, yi - mean_y);
+            (cov + dx * dy, var_x + dx * dx, var_y + dy * dy)
+        });
+
+    let denominator = (variance_x * variance_y).sqrt();
+    if denominator == 0.0 {
+        return 0.0;
+    }
+
+    covariance / denominator
+}
+
+// Each species becomes a log(Odds Ratio) vector over the union of GO terms, with 0.0 where the term was not significant.
+pub fn species_correlation(
+    species_results: &FxHashMap<String, Vec<(GOTermID, f64, f64)>>
+) -> SpeciesCorrelation {
+    let mut species: Vec<String> = species_results.keys().cloned().collect();
+    species.sort();
+
+    let mut go_terms: Vec<GOTermID> = species_results
+        .values()
+        .flat_map(|results| results.iter().map(|&(go_term, _, _)| go_term))
+        .collect::<FxHashSet<GOTermID>>()
+        .into_iter()
+        .collect();
+    go_terms.sort_unstable();
+
+    let term_index: FxHashMap<GOTermID, usize> = go_terms
+        .iter()
+        .enumerate()
+        .map(|(index, &go_term)| (go_term, index))
+        .collect();
+
+    let profiles: Vec<Vec<f64>> = species
+        .iter()
+        .map(|name| {
+            let mut profile = vec![0.0; go_terms.len()];
+            for &(go_term, log_odds_ratio, _) in &species_results[name] {
+                profile[term_index[&go_term]] = log_odds_ratio;
+            }
+            profile
+        })
+        .collect();
+
+    let n = species.len();
+    let pairs: Vec<(usize, usize, f64)> = (0..n)
+        .flat_map(|i| (i + 1..n).map(move |j| (i, j)))
+        .collect::<Vec<_>>()
+        .into_par_iter()
+        .map(|(i, j)| (i, j, pearson_correlation(&profiles[i], &profiles[j])))
+        .collect();
+
+    let mut matrix = vec![vec![1.0; n]; n];
+    for (i, j, correlation) in pairs {
+        matrix[i][j] = correlation;
+        matrix[j][i] = correlation;
+    }
+
+    SpeciesCorrelation { species, matrix }
+}
+
+fn escape_newick_label(label: &str) -> String {
+    if label.chars().any(|c| "()[]:;,' \t".contains(c)) {
+        format!("'{}'", label.replace('\'', "''"))
+    } else {
+        label.to_string()
+    }
+}
+
+// Average linkage (UPGMA) clustering on 1 - r distances, returned as a Newick tree with branch lengths.
+pub fn correlation_newick(correlation: &SpeciesCorrelation) -> String {
+    let n = correlation.species.len();
+    if n == 0 {
+        return ";".to_string();
+    }
+
+    let mut distances: Vec<Vec<f64>> = correlation.matrix
+        .iter()
+        .map(|row| row.iter().map(|r| 1.0 - r).collect())
+        .collect();
+
+    let mut clusters: Vec<Option<(String, usize, f64)>> = correlation.species
+        .iter()
+        .map(|name| Some((escape_newick_label(name), 1, 0.0)))
+        .collect();
+
+    loop {
+        let mut closest: Option<(usize, usize, f64)> = None;
+        for i in 0..n {
+            if clusters[i].is_none() {
+                continue;
+            }
+            for j in (i + 1)..n {
+                if clusters[j].is_none() {
+                    continue;
+                }
+                if closest.is_none_or(|(_, _, d)| distances[i][j] < d) {
+                    closest = Some((i, j, distances[i][j]));
+                }
+            }
+        }
+
+        let Some((i, j, distance)) = closest else { break };
+
+        let (tree_i, size_i, height_i) = clusters[i].take().unwrap();
+        let (tree_j, size_j, height_j) = clusters[j].take().unwrap();
+        let height = (distance / 2.0).max(height_i).max(height_j);
+
+        for k in 0..n {
+            if k == i || clusters[k].is_none() {
+                continue;
+            }
+            let average = (distances[i][k] * size_i as f64 + distances[j][k] * size_j as f64)
+                / (size_i + size_j) as f64;
+            distances[i][k] = average;
+            distances[k][i] = average;
+        }
+
+        clusters[i] = Some((
+            format!("({}:{:.6},{}:{:.6})", tree_i, height - height_i, tree_j, height - height_j),
+            size_i + size_j,
+            height
+        ));
+    }
+
+    let (tree, _, _) = clusters.into_iter().flatten().next().unwrap();
+    format!("{};", tree)
+}
+
+pub fn write_species_correlation(
+    correlation: &SpeciesCorrelation,
+    output_file: &PathBuf,
+) -> Result<(), Box<dyn Error>> {
+    println!("Writing species correlation matrix to: {}\n", output_file.display());
+
+    let file = File::create(output_file)?;
+    let mut writer = BufWriter::new(file);
+
+    writeln!(writer, "Species\t{}", correlation.species.join("\t"))?;
+    for (name, row) in correlation.species.iter().zip(correlation.matrix.iter()) {
+        let values: Vec<String> = row.iter().map(|r| format!("{:.4}", r)).collect();
+        writeln!(writer, "{}\t{}", name, values.join("\t"))?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
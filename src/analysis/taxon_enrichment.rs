@@ -0,0 +1,124 @@
+use rustc_hash::{FxHashMap, FxHashSet};
+use daggy::NodeIndex;
+use crate::parsers::{
+    background_parser::*,
+    study_parser::*,
+    obo_parser::*
+};
+use crate::analysis::{
+    enrichment_analysis::*,
+    count_propagation::*
+};
+
+// How GO terms are tested against each other. Elim and weight walk the ontology from its most specific
+// levels upwards and expect propagated counts.
+pub enum EnrichmentAlgorithm<'a> {
+    Classic,
+    Elim {
+        elim_threshold: f64,
+        level_to_go_term: &'a LevelToTerms,
+        ancestor_cache: &'a GOAncestorCache,
+    },
+    Weight {
+        level_to_go_term: &'a LevelToTerms,
+        ontology_graph: &'a OntologyGraph,
+        go_id_to_node_index: &'a FxHashMap<GOTermID, NodeIndex>,
+        node_index_to_go_id: &'a FxHashMap<NodeIndex, GOTermID>,
+    },
+}
+
+// Tests the GO terms of every taxon in a study population against its background population.
+// Both populations must already be propagated and filtered; the background is only read, so it can be
+// shared by several study populations. The Kolmogorov-Smirnov test ranks the study proteins instead,
+// so it ignores the background and the algorithm.
+pub fn run_taxon_enrichment(
+    analysis: &EnrichmentAnalysis,
+    algorithm: &EnrichmentAlgorithm,
+    taxon_ids: &FxHashSet<TaxonID>,
+    study_population: &StudyPop,
+    background_population: &BackgroundPop,
+) -> FxHashMap<TaxonID, FxHashMap<GOTermID, GOTermResults>> {
+    if matches!(analysis.test_type, StatisticalTest::KolmogorovSmirnov) {
+        return analysis.kolmogorov_smirnov(taxon_ids, study_population);
+    }
+
+    match algorithm {
+        EnrichmentAlgorithm::Classic => analysis.classic(
+            taxon_ids,
+            &background_population.go_term_count,
+            &study_population.go_term_count,
+            &background_population.taxon_protein_count,
+            &study_population.taxon_protein_count,
+        ),
+        EnrichmentAlgorithm::Elim { elim_threshold, level_to_go_term, ancestor_cache } => analysis.elim_analysis(
+            taxon_ids,
+            *elim_threshold,
+            study_population,
+            background_population,
+            level_to_go_term,
+            ancestor_cache
+        ),
+        EnrichmentAlgorithm::Weight { level_to_go_term, ontology_graph, go_id_to_node_index, node_index_to_go_id } => analysis.weight(
+            taxon_ids,
+            study_population,
+            background_population,
+            level_to_go_term,
+            ontology_graph,
+            go_id_to_node_index,
+            node_index_to_go_id
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use compact_str::CompactString;
+    use std::sync::Arc;
+
+    fn study_population() -> StudyPop {
+        let proteins: Vec<Protein> = (0..10).map(|i| Arc::new(CompactString::new(format!("P{}", i)))).collect();
+
+        let mut study_pop = StudyPop::default();
+        study_pop.taxon_map.insert(9606, proteins.iter().cloned().collect());
+        study_pop.taxon_protein_count.insert(9606, proteins.len());
+        study_pop.go_term_count.insert(9606, [(1, 5)].into_iter().collect());
+        study_pop.go_term_to_protein_set.insert(9606, [(1, proteins[..5].iter().cloned().collect())].into_iter().collect());
+        study_pop.protein_scores.insert(9606, proteins.iter().enumerate().map(|(i, protein)| (protein.clone(), 10.0 - i as f64)).collect());
+        study_pop
+    }
+
+    #[test]
+    fn classic_algorithm_matches_the_classic_analysis() {
+        let taxon_ids: FxHashSet<TaxonID> = [9606].into_iter().collect();
+        let study_pop = study_population();
+        let mut background_pop = BackgroundPop::default();
+        background_pop.taxon_protein_count.insert(9606, 100);
+        background_pop.go_term_count.insert(9606, [(1, 10)].into_iter().collect());
+
+        let analysis = EnrichmentAnalysis::new(StatisticalTest::Fishers);
+        let results = run_taxon_enrichment(&analysis, &EnrichmentAlgorithm::Classic, &taxon_ids, &study_pop, &background_pop);
+        let expected = analysis.classic(
+            &taxon_ids,
+            &background_pop.go_term_count,
+            &study_pop.go_term_count,
+            &background_pop.taxon_protein_count,
+            &study_pop.taxon_protein_count,
+        );
+
+        assert_eq!(results[&9606][&1].p_value, expected[&9606][&1].p_value);
+        assert_eq!(results[&9606][&1].log_odds_ratio, expected[&9606][&1].log_odds_ratio);
+    }
+
+    #[test]
+    fn kolmogorov_smirnov_ignores_the_background_population() {
+        let taxon_ids: FxHashSet<TaxonID> = [9606].into_iter().collect();
+        let analysis = EnrichmentAnalysis::new(StatisticalTest::KolmogorovSmirnov);
+
+        let results = run_taxon_enrichment(&analysis, &EnrichmentAlgorithm::Classic, &taxon_ids, &study_population(), &BackgroundPop::default());
+
+        assert_eq!(results[&9606][&1].log_odds_ratio, 1.0);
+        assert_eq!(results[&9606][&1].contingency_table, None);
+        assert_eq!(results[&9606][&1].study_count, 5);
+    }
+}
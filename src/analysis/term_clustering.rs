@@ -0,0 +1,286 @@
+use rustc_hash::FxHashMap;
+use std::fs::{File, create_dir_all};
+use std::io::{BufWriter, Write};
+use std::error::Error;
+use std::hash::Hash;
+use std::path::Path;
+use std::cmp::Ordering::{self, Equal};
+use rayon::prelude::*;
+use crate::parsers::{
+    background_parser::*,
+    obo_parser::*
+};
+use crate::analysis::{
+    enrichment_plots::{EnrichmentResult, jaccard_index},
+    write_results::sanitize_taxon_name
+};
+
+#[derive(Debug, Clone)]
+pub struct TermCluster {
+    pub representative: GOTermID,
+    pub members: Vec<GOTermID>,
+}
+
+// Clusters of each taxon (or taxonomy group) and namespace.
+pub type TermClusters<K> = FxHashMap<K, FxHashMap<NameSpace, Vec<TermCluster>>>;
+// Results of each taxon (or taxonomy group), keyed by GO term.
+pub type KeyedResults<K, R> = FxHashMap<K, FxHashMap<GOTermID, R>>;
+
+fn is_more_significant<R: EnrichmentResult>(term: &R, other: &R) -> bool {
+    match term.p_value().partial_cmp(&other.p_value()).unwrap_or(Equal) {
+        Ordering::Less => true,
+        Ordering::Greater => false,
+        Equal => term.log_odds_ratio() > other.log_odds_ratio(),
+    }
+}
+
+// Complete-linkage clustering on the Jaccard index of the proteins of each term. Two clusters are merged while
+// every pair of their terms has a Jaccard index of at least `threshold`. Each cluster is represented by its
+// most significant term, and clusters are sorted by the significance of their representative.
+pub fn cluster_go_terms<R: EnrichmentResult>(
+    terms: &[(GOTermID, &R)],
+    go_term_to_proteins: &GOTermToProteinSet,
+    threshold: f64
+) -> Vec<TermCluster> {
+    let n = terms.len();
+
+    let mut linkage: Vec<Vec<f64>> = vec![vec![0.0; n]; n];
+    for i in 0..n {
+        for j in (i + 1)..n {
+            let similarity = match (go_term_to_proteins.get(&terms[i].0), go_term_to_proteins.get(&terms[j].0)) {
+                (Some(proteins1), Some(proteins2)) => jaccard_index(proteins1, proteins2),
+                _ => 0.0,
+            };
+            linkage[i][j] = similarity;
+            linkage[j][i] = similarity;
+        }
+    }
+
+    let mut clusters: Vec<Option<Vec<usize>>> = (0..n).map(|i| Some(vec![i])).collect();
+
+    loop {
+        let mut best: Option<(usize, usize, f64)> = None;
+
+        for i in 0..n {
+            if clusters[i].is_none() {
+                continue;
+            }
+            for j in (i + 1)..n {
+                if clusters[j].is_none() {
+                    continue;
+                }
+                let similarity = linkage[i][j];
+                if similarity >= threshold && best.is_none_or(|(_, _, s)| similarity > s) {
+                    best = Some((i, j, similarity));
+                }
+            }
+        }
+
+        let Some((i, j, _)) = best else { break };
+
+        let merged = clusters[j].take().unwrap();
+        clusters[i].as_mut().unwrap().extend(merged);
+
+        for k in 0..n {
+            if k == i || clusters[k].is_none() {
+                continue;
+            }
+            let complete_linkage = linkage[i][k].min(linkage[j][k]);
+            linkage[i][k] = complete_linkage;
+            linkage[k][i] = complete_linkage;
+        }
+    }
+
+    let mut term_clusters: Vec<(usize, TermCluster)> = clusters
+        .into_iter()
+        .flatten()
+        .map(|members| {
+            let representative_idx = members
+                .iter()
+                .copied()
+                .reduce(|best, idx| if is_more_significant(terms[idx].1, terms[best].1) { idx } else { best })
+                .unwrap();
+
+            let mut member_ids: Vec<GOTermID> = members.iter().map(|&idx| terms[idx].0).collect();
+            member_ids.sort_unstable();
+
+            (representative_idx, TermCluster {
+                representative: terms[representative_idx].0,
+                members: member_ids,
+            })
+        })
+        .collect();
+
+    term_clusters.sort_by(|a, b| terms[a.0].1.p_value().partial_cmp(&terms[b.0].1.p_value()).unwrap_or(Equal));
+
+    term_clusters.into_iter().map(|(_, cluster)| cluster).collect()
+}
+
+// Clusters the significant terms of each namespace and keeps only the representative of each cluster,
+// so the written results and the plots show one term per cluster.
+pub fn cluster_results<K, R>(
+    significant_results: &KeyedResults<K, R>,
+    go_term_to_proteins: &FxHashMap<K, GOTermToProteinSet>,
+    ontology: &OboMap,
+    threshold: f64
+) -> (KeyedResults<K, R>, TermClusters<K>)
+where
+    K: Eq + Hash + Clone + Send + Sync,
+    R: EnrichmentResult + Clone + Send + Sync,
+{
+    let empty_proteins = GOTermToProteinSet::default();
+
+    significant_results
+        .par_iter()
+        .map(|(key, go_terms)| {
+            let mut terms_by_namespace: FxHashMap<NameSpace, Vec<(GOTermID, &R)>> = FxHashMap::default();
+            for (go_term, result) in go_terms {
+                let Some(term) = ontology.get(go_term) else { continue };
+                terms_by_namespace.entry(term.namespace).or_default().push((*go_term, result));
+            }
+
+            let proteins = go_term_to_proteins.get(key).unwrap_or(&empty_proteins);
+            let namespace_clusters: FxHashMap<NameSpace, Vec<TermCluster>> = terms_by_namespace
+                .into_iter()
+                .map(|(namespace, mut terms)| {
+                    terms.sort_unstable_by_key(|(go_term, _)| *go_term);
+                    (namespace, cluster_go_terms(&terms, proteins, threshold))
+                })
+                .collect();
+
+            let representatives: FxHashMap<GOTermID, R> = namespace_clusters
+                .values()
+                .flatten()
+                .map(|cluster| (cluster.representative, go_terms[&cluster.representative].clone()))
+                .collect();
+
+            ((key.clone(), representatives), (key.clone(), namespace_clusters))
+        })
+        .unzip()
+}
+
+pub fn write_term_clusters(
+    term_clusters: &TermClusters<String>,
+    ontology: &OboMap,
+    results_dir: &Path
+) -> Result<(), Box<dyn Error>> {
+    create_dir_all(results_dir)?;
+
+    crate::progress!("Writing GO term clusters to: {}\n", results_dir.display());
+
+    for (taxon_name, namespace_map) in term_clusters {
+        for (namespace, clusters) in namespace_map {
+            let namespace_str = namespace.file_label();
+
+            let filename = results_dir.join(format!(
+                "clusters_{}_{}.tsv",
+                sanitize_taxon_name(taxon_name),
+                namespace_str
+            ));
+            let file = File::create(&filename)?;
+            let mut writer = BufWriter::new(file);
+
+            writer.write_all(b"Cluster\tRepresentative\tGO Term ID\tName\n")?;
+
+            for (cluster_idx, cluster) in clusters.iter().enumerate() {
+                for go_term in &cluster.members {
+                    let name = ontology
+                        .get(go_term)
+                        .map(|term| term.name.as_str())
+                        .unwrap_or("");
+
+                    writeln!(
+                        writer,
+                        "{}\tGO:{:07}\tGO:{:07}\t{}",
+                        cluster_idx + 1,
+                        cluster.representative,
+                        go_term,
+                        name
+                    )?;
+                }
+            }
+
+            writer.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustc_hash::FxHashSet;
+    use std::sync::Arc;
+    use compact_str::CompactString;
+
+    struct TestResult {
+        log_odds_ratio: f64,
+        p_value: f64,
+    }
+
+    impl EnrichmentResult for TestResult {
+        fn log_odds_ratio(&self) -> f64 { self.log_odds_ratio }
+        fn p_value(&self) -> f64 { self.p_value }
+        fn size(&self) -> usize { 1 }
+    }
+
+    fn proteins(names: &[&str]) -> FxHashSet<Protein> {
+        names.iter().map(|name| Arc::new(CompactString::from(*name))).collect()
+    }
+
+    // Terms 1 and 2 and terms 2 and 3 share 3 of 5 proteins (J = 0.6), but terms 1 and 3 only share 2 of 6 (J = 1/3).
+    fn chained_terms() -> GOTermToProteinSet {
+        [
+            (1, proteins(&["a", "b", "c", "d"])),
+            (2, proteins(&["b", "c", "d", "e"])),
+            (3, proteins(&["c", "d", "e", "f"])),
+        ].into_iter().collect()
+    }
+
+    fn cluster(results: &[(GOTermID, TestResult)], threshold: f64) -> Vec<TermCluster> {
+        let terms: Vec<(GOTermID, &TestResult)> = results.iter().map(|(go_term, result)| (*go_term, result)).collect();
+        cluster_go_terms(&terms, &chained_terms(), threshold)
+    }
+
+    fn result(p_value: f64, log_odds_ratio: f64) -> TestResult {
+        TestResult { log_odds_ratio, p_value }
+    }
+
+    #[test]
+    fn complete_linkage_does_not_chain_terms_through_a_shared_neighbour() {
+        let results = [(1, result(0.01, 1.0)), (2, result(0.02, 1.0)), (3, result(0.03, 1.0))];
+
+        let clusters = cluster(&results, 0.5);
+        let members: Vec<Vec<GOTermID>> = clusters.iter().map(|cluster| cluster.members.clone()).collect();
+
+        assert_eq!(members, vec![vec![1, 2], vec![3]]);
+    }
+
+    #[test]
+    fn threshold_controls_which_terms_are_merged() {
+        let results = [(1, result(0.01, 1.0)), (2, result(0.02, 1.0)), (3, result(0.03, 1.0))];
+
+        assert_eq!(cluster(&results, 0.7).len(), 3);
+        assert_eq!(cluster(&results, 0.6).len(), 2);
+        assert_eq!(cluster(&results, 0.1).len(), 1);
+    }
+
+    #[test]
+    fn representative_is_the_most_significant_member() {
+        let results = [(1, result(0.04, 2.0)), (2, result(0.001, 0.5)), (3, result(0.2, 1.0))];
+
+        let clusters = cluster(&results, 0.5);
+
+        assert_eq!(clusters[0].representative, 2);
+        assert_eq!(clusters[0].members, vec![1, 2]);
+        assert_eq!(clusters[1].representative, 3);
+    }
+
+    #[test]
+    fn ties_in_significance_go_to_the_larger_log_odds_ratio() {
+        let results = [(1, result(0.01, 0.5)), (2, result(0.01, 1.5))];
+
+        assert_eq!(cluster(&results, 0.5)[0].representative, 2);
+    }
+}
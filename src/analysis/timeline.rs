@@ -0,0 +1,289 @@
+use rustc_hash::FxHashMap;
+use std::fs::{create_dir_all, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::cmp::Ordering::Equal;
+use statrs::distribution::{StudentsT, ContinuousCDF};
+use plotly::{
+    Plot, Scatter, Layout,
+    common::{Title, Font, Mode},
+    layout::Axis
+};
+use crate::parsers::{
+    background_parser::*,
+    obo_parser::*
+};
+use crate::analysis::enrichment_analysis::*;
+
+const MIN_TIME_POINTS: usize = 3;
+
+// Enrichment results of every taxon at one time point.
+pub type TimePointResults = (i64, FxHashMap<TaxonID, FxHashMap<GOTermID, GOTermResults>>);
+// Time points and log(Odds Ratio) values of one GO term.
+type TermSeries = (Vec<f64>, Vec<f64>);
+
+#[derive(Debug, Clone)]
+pub struct TimelineRegression {
+    pub time_points: Vec<f64>,
+    pub log_odds_ratios: Vec<f64>,
+    pub slope: f64,
+    pub intercept: f64,
+    pub r_squared: f64,
+    pub p_value: f64,
+}
+
+// Reads `filename,time_point` rows. Rows whose time point is not an integer (e.g. a header) are skipped.
+pub fn read_time_metadata(metadata_file: &Path) -> Result<Vec<(String, i64)>, Box<dyn Error>> {
+    let file = File::open(metadata_file)?;
+    let mut time_points = Vec::new();
+
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        let Some((filename, time_point)) = line.split_once(',') else { continue };
+        let Ok(time_point) = time_point.trim().parse::<i64>() else { continue };
+        time_points.push((filename.trim().to_string(), time_point));
+    }
+
+    time_points.sort_by_key(|&(_, time_point)| time_point);
+    Ok(time_points)
+}
+
+// Ordinary least squares fit of y on x, with a two-sided t-test for a non-zero slope.
+pub fn linear_regression(x: &[f64], y: &[f64]) -> Option<(f64, f64, f64, f64)> {
+    let n = x.len();
+    if n < MIN_TIME_POINTS {
+        return None;
+    }
+
+    let mean_x = x.iter().sum::<f64>() / n as f64;
+    let mean_y = y.iter().sum::<f64>() / n as f64;
+
+    let (sxx, sxy, syy) = x
+        .iter()
+        .zip(y.iter())
+        .fold((0.0, 0.0, 0.0), |(sxx, sxy, syy), (&xi, &yi)| {
+            let (dx, dy) = (xi - mean_x, yi - mean_y);
+            (sxx + dx * dx, sxy + dx * dy, syy + dy * dy)
+        });
+
+    if sxx == 0.0 {
+        return None;
+    }
+
+    let slope = sxy / sxx;
+    let intercept = mean_y - slope * mean_x;
+    let residual_sum_of_squares = (syy - slope * sxy).max(0.0);
+    let r_squared = if syy > 0.0 { 1.0 - residual_sum_of_squares / syy } else { 0.0 };
+
+    let degrees_of_freedom = (n - 2) as f64;
+    let standard_error = (residual_sum_of_squares / degrees_of_freedom / sxx).sqrt();
+    let p_value = if standard_error > 0.0 {
+        match StudentsT::new(0.0, 1.0, degrees_of_freedom) {
+            Ok(distribution) => 2.0 * distribution.sf((slope / standard_error).abs()),
+            Err(_) => 1.0,
+        }
+    } else if slope != 0.0 {
+        0.0
+    } else {
+        1.0
+    };
+
+    Some((slope, intercept, r_squared, p_value))
+}
+
+// Regresses the log(Odds Ratio) of every GO term on time for each taxon, using the time points in which the term was tested.
+pub fn timeline_regression(
+    time_point_results: &[TimePointResults]
+) -> FxHashMap<TaxonID, FxHashMap<GOTermID, TimelineRegression>> {
+    let mut series: FxHashMap<TaxonID, FxHashMap<GOTermID, TermSeries>> = FxHashMap::default();
+
+    for (time_point, results) in time_point_results {
+        for (taxon_id, go_terms) in results {
+            let taxon_series = series.entry(*taxon_id).or_default();
+            for (go_term, result) in go_terms {
+                let (time_points, log_odds_ratios) = taxon_series.entry(*go_term).or_default();
+                time_points.push(*time_point as f64);
+                log_odds_ratios.push(result.log_odds_ratio);
+            }
+        }
+    }
+
+    series
+        .into_iter()
+        .map(|(taxon_id, go_terms)| {
+            let regressions = go_terms
+                .into_iter()
+                .filter_map(|(go_term, (time_points, log_odds_ratios))| {
+                    let (slope, intercept, r_squared, p_value) = linear_regression(&time_points, &log_odds_ratios)?;
+                    Some((go_term, TimelineRegression {
+                        time_points,
+                        log_odds_ratios,
+                        slope,
+                        intercept,
+                        r_squared,
+                        p_value,
+                    }))
+                })
+                .collect();
+            (taxon_id, regressions)
+        })
+        .collect()
+}
+
+fn sorted_regressions(
+    regressions: &FxHashMap<TaxonID, FxHashMap<GOTermID, TimelineRegression>>
+) -> Vec<(TaxonID, GOTermID, &TimelineRegression)> {
+    regressions
+        .iter()
+        .flat_map(|(&taxon_id, go_terms)| go_terms
+            .iter()
+            .map(move |(&go_term, regression)| (taxon_id, go_term, regression)))
+        .collect()
+}
+
+pub fn write_timeline_regression(
+    regressions: &FxHashMap<TaxonID, FxHashMap<GOTermID, TimelineRegression>>,
+    ontology: &OboMap,
+    output_file: &PathBuf,
+) -> Result<(), Box<dyn Error>> {
+    println!("Writing timeline regression results to: {}\n", output_file.display());
+
+    let file = File::create(output_file)?;
+    let mut writer = BufWriter::new(file);
+
+    writer.write_all(b"Taxon\tGO Term ID\tName\tNamespace\tTime points\tSlope\tIntercept\tR2\tp-value\n")?;
+
+    let mut sorted = sorted_regressions(regressions);
+    sorted.sort_by(|a, b| a.2.p_value.partial_cmp(&b.2.p_value).unwrap_or(Equal));
+
+    for (taxon_id, go_term, regression) in sorted {
+        let Some(term) = ontology.get(&go_term) else { continue };
+
+        let namespace = term.namespace.label();
+
+        writeln!(
+            writer,
+            "{}\tGO:{:07}\t{}\t{}\t{}\t{:.4}\t{:.4}\t{:.4}\t{:.5e}",
+            taxon_id,
+            go_term,
+            term.name,
+            namespace,
+            regression.time_points.len(),
+            regression.slope,
+            regression.intercept,
+            regression.r_squared,
+            regression.p_value
+        )?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+// One line plot of log(Odds Ratio) against time for each of the `top_terms` regressions with the largest |slope|.
+pub fn timeline_plots(
+    regressions: &FxHashMap<TaxonID, FxHashMap<GOTermID, TimelineRegression>>,
+    ontology: &OboMap,
+    plots_dir: &PathBuf,
+    top_terms: usize,
+) -> Result<(), Box<dyn Error>> {
+    create_dir_all(plots_dir)?;
+
+    let mut sorted = sorted_regressions(regressions);
+    sorted.sort_by(|a, b| b.2.slope.abs().partial_cmp(&a.2.slope.abs()).unwrap_or(Equal));
+
+    for (taxon_id, go_term, regression) in sorted.into_iter().take(top_terms) {
+        let term_name = ontology.get(&go_term).map_or(String::new(), |term| term.name.clone());
+        let title = format!("GO:{:07} {} (taxon {})", go_term, term_name, taxon_id);
+
+        let trace = Scatter::new(regression.time_points.clone(), regression.log_odds_ratios.clone())
+            .mode(Mode::LinesMarkers)
+            .name("log(Odds Ratio)");
+
+        let mut plot = Plot::new();
+        plot.add_trace(trace);
+        plot.set_layout(Layout::new()
+            .title(Title::with_text(&title).font(Font::new().size(14)))
+            .x_axis(Axis::new().title(Title::with_text("Time point")))
+            .y_axis(Axis::new().title(Title::with_text("log(Odds Ratio)"))));
+
+        let html_file = plots_dir.join(format!("{}_GO_{:07}_timeline.html", taxon_id, go_term));
+        plot.write_html(html_file);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term_results(log_odds_ratio: f64) -> GOTermResults {
+        GOTermResults {
+            log_odds_ratio,
+            p_value: 0.5,
+            contingency_table: Some([5, 5, 5, 5]),
+            study_count: 5,
+            variance: 0.8,
+        }
+    }
+
+    #[test]
+    fn linear_regression_matches_a_hand_computed_fit() {
+        // Sxx = 5, Sxy = 4 and Syy = 5, so t = 0.8 / sqrt(0.18) on 2 degrees of freedom, where p = 1 - t / sqrt(2 + t²).
+        let (slope, intercept, r_squared, p_value) = linear_regression(&[1.0, 2.0, 3.0, 4.0], &[1.0, 3.0, 2.0, 4.0]).unwrap();
+
+        assert!((slope - 0.8).abs() < 1e-12);
+        assert!((intercept - 0.5).abs() < 1e-12);
+        assert!((r_squared - 0.64).abs() < 1e-12);
+        assert!((p_value - 0.2).abs() < 1e-9, "p = {}", p_value);
+    }
+
+    #[test]
+    fn linear_regression_of_a_perfect_line_is_significant() {
+        let (slope, intercept, r_squared, p_value) = linear_regression(&[0.0, 1.0, 2.0], &[1.0, 3.0, 5.0]).unwrap();
+
+        assert_eq!((slope, intercept, r_squared, p_value), (2.0, 1.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn linear_regression_needs_three_distinct_time_points() {
+        assert!(linear_regression(&[1.0, 2.0], &[1.0, 2.0]).is_none());
+        assert!(linear_regression(&[1.0, 1.0, 1.0], &[1.0, 2.0, 3.0]).is_none());
+    }
+
+    #[test]
+    fn timeline_regression_skips_terms_tested_at_too_few_time_points() {
+        let time_point_results: Vec<TimePointResults> = (0..3)
+            .map(|time_point| {
+                let mut go_terms: FxHashMap<GOTermID, GOTermResults> = FxHashMap::default();
+                go_terms.insert(1, term_results(2.0 * time_point as f64));
+                if time_point == 0 {
+                    go_terms.insert(2, term_results(1.0));
+                }
+                let mut results = FxHashMap::default();
+                results.insert(9606, go_terms);
+                (time_point, results)
+            })
+            .collect();
+
+        let regressions = timeline_regression(&time_point_results);
+
+        assert_eq!(regressions[&9606].len(), 1);
+        let regression = &regressions[&9606][&1];
+        assert_eq!(regression.time_points, vec![0.0, 1.0, 2.0]);
+        assert_eq!(regression.slope, 2.0);
+    }
+
+    #[test]
+    fn time_metadata_is_sorted_and_skips_the_header() {
+        let metadata_file = std::env::temp_dir().join(format!("taxago_timeline_metadata_{}.csv", std::process::id()));
+        std::fs::write(&metadata_file, "filename,time_point\nlate.txt,10\nearly.txt, 2\n").unwrap();
+
+        let time_points = read_time_metadata(&metadata_file).unwrap();
+        std::fs::remove_file(&metadata_file).unwrap();
+
+        assert_eq!(time_points, vec![("early.txt".to_string(), 2), ("late.txt".to_string(), 10)]);
+    }
+}
@@ -312,12 +312,7 @@ impl EnrichmentAnalysis {
             total_background_proteins_count,
         );
 
-        GOTermResults {
-            log_odds_ratio: calculate_log_odds_ratio(&contingency_table),
-            p_value: calculate_p_value(&contingency_table, self.test_type),
-            contingency_table,
-            variance: calculate_variance(&contingency_table),
-        }
+        self.term_results(contingency_table)
     }
 
     fn get_ancestors_including_term(
@@ -1,14 +1,19 @@
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 use std::fs::{self, File, create_dir_all};
 use std::io::{self, BufWriter, Write};
 use std::error::Error;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::fmt::Write as FmtWrite;
 use lazy_static::lazy_static;
+use serde::Serialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use clap::ValueEnum;
 
 use crate::parsers::{
     background_parser::*,
-    obo_parser::*
+    obo_parser::*,
+    study_parser::StudyPop
 };
 use crate::analysis::{
     enrichment_analysis::*,
@@ -21,10 +26,24 @@ pub fn clean_directory(dir_path: &PathBuf) -> io::Result<()> {
             let entry = entry?;
             let path = entry.path();
 
+            if path.file_name().and_then(|name| name.to_str()) == Some(PROGRESS_FILE) {
+                fs::remove_file(&path)?;
+                continue;
+            }
+
+            if path.is_file() {
+                if let Some(file_name) = path.file_name().and_then(|name| name.to_str()) {
+                    if file_name.starts_with("clusters_") && file_name.ends_with(".tsv") {
+                        fs::remove_file(&path)?;
+                    }
+                }
+                continue;
+            }
+
             if path.is_dir() {
                 if let Some(dir_name) = path.file_name() {
                     if let Some(name_str) = dir_name.to_str() {
-                        if name_str == "combined_taxonomy_results" || name_str == "single_taxon_results" {
+                        if name_str.starts_with("combined_taxonomy_results") || name_str == "single_taxon_results" {
                             fs::remove_dir_all(&path)?;
                         }
                     }
@@ -37,6 +56,236 @@ pub fn clean_directory(dir_path: &PathBuf) -> io::Result<()> {
 
 const BUFFER_SIZE: usize = 8192 * 32;
 
+const PROGRESS_FILE: &str = "progress.json";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProgressStep {
+    OboParsing,
+    BackgroundLoading,
+    StudyLoading,
+    EnrichmentAnalysis,
+    MultipleTestingCorrection,
+    WritingResults,
+    Finished,
+}
+
+impl ProgressStep {
+    const TOTAL_STEPS: usize = 7;
+
+    fn name(&self) -> &'static str {
+        match self {
+            ProgressStep::OboParsing => "obo_parsing",
+            ProgressStep::BackgroundLoading => "background_loading",
+            ProgressStep::StudyLoading => "study_loading",
+            ProgressStep::EnrichmentAnalysis => "enrichment_analysis",
+            ProgressStep::MultipleTestingCorrection => "multiple_testing_correction",
+            ProgressStep::WritingResults => "writing_results",
+            ProgressStep::Finished => "finished",
+        }
+    }
+}
+
+pub fn write_analysis_params(output_dir: &Path, params: &Value) -> io::Result<()> {
+    let file = File::create(output_dir.join("analysis_params.json"))?;
+    let mut writer = BufWriter::new(file);
+    serde_json::to_writer_pretty(&mut writer, params)?;
+    writer.write_all(b"\n")?;
+    writer.flush()
+}
+
+// Written to a temporary file and renamed so readers never see a partially written file.
+pub fn write_progress(
+    output_dir: &Path,
+    step: ProgressStep,
+    taxa_processed: usize,
+    total_taxa: usize,
+) -> io::Result<()> {
+    let progress = json!({
+        "step": step.name(),
+        "step_index": step as usize + 1,
+        "total_steps": ProgressStep::TOTAL_STEPS,
+        "taxa_processed": taxa_processed,
+        "total_taxa": total_taxa,
+    });
+
+    let temporary_path = output_dir.join(format!("{}.tmp", PROGRESS_FILE));
+    fs::write(&temporary_path, serde_json::to_vec(&progress)?)?;
+    fs::rename(&temporary_path, output_dir.join(PROGRESS_FILE))
+}
+
+const REPORT_FILE: &str = "analysis_report.json";
+
+fn sha256_file(path: &PathBuf) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+// Study populations can be a single file, a comma-separated list of files or a directory of files.
+fn study_files(study_pop: &str) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    for path in study_pop.split(',').map(str::trim).filter(|path| !path.is_empty()).map(PathBuf::from) {
+        if path.is_dir() {
+            let mut dir_files: Vec<PathBuf> = fs::read_dir(&path)
+                .map(|entries| entries.flatten().map(|entry| entry.path()).filter(|path| path.is_file()).collect())
+                .unwrap_or_default();
+            dir_files.sort();
+            files.extend(dir_files);
+        } else {
+            files.push(path);
+        }
+    }
+    files
+}
+
+// Hashing every background file would dominate the run time, so the background is summarised by its size.
+fn background_summary(background_pop: &PathBuf) -> (usize, u64) {
+    if background_pop.is_file() {
+        return (1, fs::metadata(background_pop).map_or(0, |metadata| metadata.len()));
+    }
+    fs::read_dir(background_pop)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| entry.metadata().ok())
+                .filter(|metadata| metadata.is_file())
+                .fold((0, 0), |(count, size), metadata| (count + 1, size + metadata.len()))
+        })
+        .unwrap_or((0, 0))
+}
+
+// Records everything needed to reproduce a run. Inputs that cannot be read (e.g. because the run failed
+// on them) are reported as null rather than preventing the report from being written.
+pub fn write_reproducibility_report<P: Serialize>(
+    params: &P,
+    obo_file: &PathBuf,
+    study_pop: &str,
+    background_pop: &PathBuf,
+    output_dir: &PathBuf,
+    success: bool,
+) -> io::Result<()> {
+    let study_hashes: serde_json::Map<String, Value> = study_files(study_pop)
+        .iter()
+        .map(|path| (path.to_string_lossy().into_owned(), json!(sha256_file(path).ok())))
+        .collect();
+    let (background_file_count, background_total_size) = background_summary(background_pop);
+
+    let report = json!({
+        "taxago_version": env!("CARGO_PKG_VERSION"),
+        "timestamp_utc": chrono::Utc::now().to_rfc3339(),
+        "success": success,
+        "cli_args": params,
+        "obo_file": {
+            "path": obo_file.to_string_lossy(),
+            "sha256": sha256_file(obo_file).ok()
+        },
+        "study_population": study_hashes,
+        "background_population": {
+            "path": background_pop.to_string_lossy(),
+            "file_count": background_file_count,
+            "total_size_bytes": background_total_size
+        },
+        "rayon_threads": rayon::current_num_threads(),
+        "os": {
+            "os": std::env::consts::OS,
+            "family": std::env::consts::FAMILY,
+            "arch": std::env::consts::ARCH
+        }
+    });
+
+    create_dir_all(output_dir)?;
+    let file = File::create(output_dir.join(REPORT_FILE))?;
+    let mut writer = BufWriter::new(file);
+    serde_json::to_writer_pretty(&mut writer, &report)?;
+    writer.write_all(b"\n")?;
+    writer.flush()
+}
+
+pub(crate) const RESULT_COLUMNS: [&str; 5] = [
+    "GO Term ID",
+    "Name",
+    "Namespace",
+    "log(Odds Ratio)",
+    "Statistical significance",
+];
+
+// The effect size column is named after the test, since the Beta-Binomial test reports a log Bayes factor.
+fn result_columns(effect_label: &str) -> [&str; 5] {
+    let mut columns = RESULT_COLUMNS;
+    columns[3] = effect_label;
+    columns
+}
+
+const COMBINED_COLUMNS: [&str; 6] = [
+    "Combined Variance",
+    "CI_Lower",
+    "CI_Upper",
+    "Q_statistic",
+    "Q_p_value",
+    "I_squared",
+];
+
+const CI_Z_SCORE: f64 = 1.96;
+
+const RESULTS_FILE_SUFFIX: &str = "_GOEA_results";
+const ALL_RESULTS_FILE_SUFFIX: &str = "_all_results";
+const SIGNIFICANCE_COLUMN: &str = "is_significant";
+
+// Name, format and columns of one kind of results file.
+struct ResultsFile<'a> {
+    suffix: &'a str,
+    output_format: OutputFormat,
+    effect_label: &'a str,
+    // Written after RESULT_COLUMNS, matching the ExtraValues of each row.
+    extra_columns: &'a [&'a str],
+}
+
+// Values of the extra columns of one results file row.
+enum ExtraValues<'a> {
+    None,
+    Combined(&'a TaxonomyGOResult),
+    Significance(bool),
+}
+
+fn taxon_metadata(species_name: &str, taxon_id: TaxonID, statistical_test: StatisticalTest) -> Value {
+    json!({
+        "taxon": species_name,
+        "taxon_id": taxon_id,
+        "test": format!("{:?}", statistical_test),
+    })
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq)]
+pub enum OutputFormat {
+    Tsv,
+    Csv,
+    Json,
+}
+
+impl OutputFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Tsv => "txt",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Json => "json",
+        }
+    }
+}
+
+pub struct WriteOptions<'a> {
+    pub output_format: OutputFormat,
+    pub statistical_test: StatisticalTest,
+    pub raw_counts: Option<&'a FxHashMap<TaxonID, FxHashMap<GOTermID, GOTermResults>>>,
+    pub max_terms_per_namespace: usize,
+    // Results of every tested term before multiple testing correction, for the unadjusted p-values.
+    pub tested_results: &'a FxHashMap<TaxonID, FxHashMap<GOTermID, GOTermResults>>,
+    // Every tested term with its adjusted p-value, written to `{taxon}_all_results` in the output format when set.
+    pub all_results: Option<&'a FxHashMap<TaxonID, FxHashMap<GOTermID, GOTermResults>>>,
+    // Terms that passed the significance and log(Odds Ratio) thresholds, including those collapsed by clustering.
+    pub significant_terms: &'a FxHashMap<TaxonID, FxHashSet<GOTermID>>,
+}
+
 lazy_static! {
     static ref NAMESPACE_MAPPING: FxHashMap<&'static str, &'static str> = {
         let mut go_term_class_map = FxHashMap::default();
@@ -80,120 +329,641 @@ pub fn write_single_taxon_results(
     ontology: &FxHashMap<u32, OboTerm>,
     taxid_species_map: &FxHashMap<TaxonID, String>,
     output_dir: &PathBuf,
+    options: &WriteOptions,
+    mut jsonl_writer: Option<Box<dyn Write>>,
 ) -> Result<(), Box<dyn Error>> {
     let results_dir = PathBuf::from(output_dir).join("single_taxon_results");
     create_dir_all(&results_dir)?;
 
-    println!("Writing single taxon results to: {}\n", results_dir.to_str().unwrap());
+    crate::progress!("Writing single taxon results to: {}\n", results_dir.to_str().unwrap());
     
     let mut term_cache = TermCache::new();
     
-    let mut line_buffer = String::with_capacity(256);
-    
     for (taxon_id, go_terms) in data {
         let species_name = taxid_species_map.get(taxon_id)
             .unwrap_or(&taxon_id.to_string())
             .replace(" ", "_");
 
-        let filename = results_dir.join(format!("{}_GOEA_results.txt", sanitize_filename(&species_name)));
-        let file = File::create(&filename)?;
-        let mut writer = BufWriter::with_capacity(BUFFER_SIZE, file);
-        
-        writer.write_all(b"GO Term ID\tName\tNamespace\tlog(Odds Ratio)\tStatistical significance\n")?;
-        for (go_term, results) in go_terms {
-        
-            if let Some(term) = ontology.get(go_term) {
-                if !term.is_obsolete {
-                    let formatted_go_term = term_cache.get_go_term(*go_term);
-                    let namespace_str = match term.namespace {
-                        NameSpace::BiologicalProcess => "biological_process",
-                        NameSpace::MolecularFunction => "molecular_function",
-                        NameSpace::CellularComponent => "cellular_component",
-                    };
-                    let formatted_namespace = format_namespace(namespace_str);
-                    
-                    line_buffer.clear();
-                    
-                    write!(
-                        &mut line_buffer,
-                        "{}\t{}\t{}\t{:.3}\t{:.5e}\n",
-                        formatted_go_term,
-                        term.name,
-                        formatted_namespace,
-                        results.log_odds_ratio,
-                        results.p_value,
-                    )?;
-                    
-                    writer.write_all(line_buffer.as_bytes())?;
+        let metadata = taxon_metadata(&species_name, *taxon_id, options.statistical_test);
+
+        let capped_terms = cap_terms_per_namespace(go_terms, ontology, options.max_terms_per_namespace);
+
+        if let Some(jsonl_writer) = jsonl_writer.as_mut() {
+            let taxon_name = taxid_species_map.get(taxon_id).cloned().unwrap_or_else(|| taxon_id.to_string());
+            write_jsonl_results(
+                &mut **jsonl_writer,
+                *taxon_id,
+                &taxon_name,
+                &capped_terms,
+                options.tested_results.get(taxon_id),
+                ontology
+            )?;
+        }
+
+        let results_file = ResultsFile {
+            suffix: RESULTS_FILE_SUFFIX,
+            output_format: options.output_format,
+            effect_label: options.statistical_test.effect_size_label(),
+            extra_columns: &[],
+        };
+        write_results_file(
+            &results_dir,
+            &species_name,
+            capped_terms
+                .into_iter()
+                .map(|(go_term, results)| (go_term, results.log_odds_ratio, results.p_value, ExtraValues::None)),
+            ontology,
+            &results_file,
+            metadata,
+            &mut term_cache,
+        )?;
+    }
+
+    if let Some(raw_counts) = options.raw_counts {
+        for (taxon_id, go_terms) in raw_counts {
+            let species_name = taxid_species_map.get(taxon_id)
+                .unwrap_or(&taxon_id.to_string())
+                .replace(" ", "_");
+
+            write_contingency_tables(&results_dir, &species_name, go_terms, &mut term_cache)?;
+        }
+    }
+
+    if let Some(all_results) = options.all_results {
+        for (taxon_id, go_terms) in all_results {
+            let species_name = taxid_species_map.get(taxon_id)
+                .unwrap_or(&taxon_id.to_string())
+                .replace(" ", "_");
+
+            let results_file = ResultsFile {
+                suffix: ALL_RESULTS_FILE_SUFFIX,
+                output_format: options.output_format,
+                effect_label: options.statistical_test.effect_size_label(),
+                extra_columns: &[SIGNIFICANCE_COLUMN],
+            };
+            let significant_terms = options.significant_terms.get(taxon_id);
+            let mut sorted_terms: Vec<(&GOTermID, &GOTermResults)> = go_terms.iter().collect();
+            sorted_terms.sort_by(|a, b| a.1.p_value.partial_cmp(&b.1.p_value).unwrap_or(std::cmp::Ordering::Equal));
+
+            write_results_file(
+                &results_dir,
+                &species_name,
+                sorted_terms.into_iter().map(|(go_term, results)| {
+                    let is_significant = significant_terms.is_some_and(|significant| significant.contains(go_term));
+                    (*go_term, results.log_odds_ratio, results.p_value, ExtraValues::Significance(is_significant))
+                }),
+                ontology,
+                &results_file,
+                taxon_metadata(&species_name, *taxon_id, options.statistical_test),
+                &mut term_cache,
+            )?;
+        }
+    }
+
+    if let Some(jsonl_writer) = jsonl_writer.as_mut() {
+        jsonl_writer.flush()?;
+    }
+    Ok(())
+}
+
+// One JSON object per line, so results can be consumed by line-oriented tools as they are written.
+fn write_jsonl_results(
+    writer: &mut dyn Write,
+    taxon_id: TaxonID,
+    taxon_name: &str,
+    go_terms: &[(GOTermID, &GOTermResults)],
+    tested_results: Option<&FxHashMap<GOTermID, GOTermResults>>,
+    ontology: &FxHashMap<u32, OboTerm>,
+) -> Result<(), Box<dyn Error>> {
+    for (go_term, results) in go_terms {
+        let Some(term) = ontology.get(go_term) else { continue };
+        if term.is_obsolete {
+            continue;
+        }
+
+        let namespace = term.namespace.abbreviation();
+        let p_value = tested_results
+            .and_then(|tested| tested.get(go_term))
+            .map(|tested| tested.p_value);
+
+        let line = json!({
+            "taxon_id": taxon_id,
+            "taxon_name": taxon_name,
+            "go_id": format!("GO:{:07}", go_term),
+            "name": term.name,
+            "namespace": namespace,
+            "log_odds_ratio": results.log_odds_ratio,
+            "p_value": p_value,
+            "adjusted_p_value": results.p_value,
+        });
+        serde_json::to_writer(&mut *writer, &line)?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+const LOW_COVERAGE_PERCENTAGE: f64 = 50.0;
+
+#[derive(Debug, Clone, Copy)]
+pub struct AnnotationCoverage {
+    pub total_proteins: usize,
+    pub annotated_proteins: usize,
+}
+
+impl AnnotationCoverage {
+    pub fn percentage(&self) -> f64 {
+        if self.total_proteins == 0 {
+            0.0
+        } else {
+            100.0 * self.annotated_proteins as f64 / self.total_proteins as f64
+        }
+    }
+}
+
+// Must be computed before any GO term filtering, which drops proteins whose only terms were removed.
+pub fn annotation_coverage(study_pop: &StudyPop) -> FxHashMap<TaxonID, AnnotationCoverage> {
+    study_pop.taxon_map
+        .iter()
+        .map(|(&taxon_id, proteins)| {
+            let annotated: FxHashSet<&Protein> = study_pop.go_term_to_protein_set
+                .get(&taxon_id)
+                .map(|term_proteins| term_proteins.values().flatten().collect())
+                .unwrap_or_default();
+            let annotated_proteins = proteins.iter().filter(|protein| annotated.contains(protein)).count();
+
+            (taxon_id, AnnotationCoverage {
+                total_proteins: proteins.len(),
+                annotated_proteins,
+            })
+        })
+        .collect()
+}
+
+pub fn generate_coverage_report(
+    coverage: &FxHashMap<TaxonID, AnnotationCoverage>,
+    taxid_species_map: &FxHashMap<TaxonID, String>,
+    output_dir: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let output_file = output_dir.join("annotation_coverage.tsv");
+    crate::progress!("Writing annotation coverage to: {}\n", output_file.display());
+
+    let mut writer = BufWriter::new(File::create(&output_file)?);
+    writer.write_all(b"Taxon_ID\tSpecies\tTotal_proteins\tAnnotated_proteins\tUnannotated_proteins\tCoverage_percentage\n")?;
+
+    let mut sorted_coverage: Vec<(&TaxonID, &AnnotationCoverage)> = coverage.iter().collect();
+    sorted_coverage.sort_by_key(|(taxon_id, _)| **taxon_id);
+
+    for (taxon_id, taxon_coverage) in sorted_coverage {
+        let species_name = taxid_species_map.get(taxon_id)
+            .unwrap_or(&taxon_id.to_string())
+            .replace(" ", "_");
+        let percentage = taxon_coverage.percentage();
+
+        writeln!(
+            writer,
+            "{}\t{}\t{}\t{}\t{}\t{:.2}",
+            taxon_id,
+            species_name,
+            taxon_coverage.total_proteins,
+            taxon_coverage.annotated_proteins,
+            taxon_coverage.total_proteins - taxon_coverage.annotated_proteins,
+            percentage
+        )?;
+
+        if percentage < LOW_COVERAGE_PERCENTAGE {
+            eprintln!(
+                "Warning: only {:.2}% of the study proteins of {} have a GO annotation in the background population",
+                percentage,
+                species_name
+            );
+        }
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+// Keeps the `max_terms` most significant terms of each namespace, sorted by p-value. 0 keeps every term.
+fn cap_terms_per_namespace<'a>(
+    go_terms: &'a FxHashMap<GOTermID, GOTermResults>,
+    ontology: &FxHashMap<u32, OboTerm>,
+    max_terms: usize,
+) -> Vec<(GOTermID, &'a GOTermResults)> {
+    let mut sorted_terms: Vec<(GOTermID, &GOTermResults)> = go_terms
+        .iter()
+        .map(|(go_term, results)| (*go_term, results))
+        .collect();
+    sorted_terms.sort_by(|a, b| a.1.p_value.partial_cmp(&b.1.p_value).unwrap_or(std::cmp::Ordering::Equal));
+
+    if max_terms == 0 {
+        return sorted_terms;
+    }
+
+    let mut namespace_counts: FxHashMap<NameSpace, usize> = FxHashMap::default();
+    sorted_terms.retain(|(go_term, _)| {
+        let Some(term) = ontology.get(go_term) else { return false };
+        let count = namespace_counts.entry(term.namespace).or_insert(0);
+        *count += 1;
+        *count <= max_terms
+    });
+
+    sorted_terms
+}
+
+fn write_contingency_tables(
+    results_dir: &Path,
+    name: &str,
+    go_terms: &FxHashMap<GOTermID, GOTermResults>,
+    term_cache: &mut TermCache,
+) -> Result<(), Box<dyn Error>> {
+    let filename = results_dir.join(format!("{}_contingency_tables.tsv", sanitize_taxon_name(name)));
+    let file = File::create(&filename)?;
+    let mut writer = BufWriter::with_capacity(BUFFER_SIZE, file);
+    let mut line_buffer = String::with_capacity(128);
+
+    writer.write_all(b"GO_ID\tstudy_in\tstudy_out\tbg_in\tbg_out\tlog_odds_ratio\traw_p_value\tresidual_study_in\tresidual_study_out\tresidual_bg_in\tresidual_bg_out\n")?;
+
+    let mut sorted_terms: Vec<_> = go_terms.iter().collect();
+    sorted_terms.sort_unstable_by_key(|(go_term, _)| **go_term);
+
+    for (go_term, results) in sorted_terms {
+        line_buffer.clear();
+        write!(&mut line_buffer, "{}", term_cache.get_go_term(*go_term))?;
+        // The Kolmogorov-Smirnov test has no counts, so its count and residual columns are NA.
+        match &results.contingency_table {
+            Some(contingency_table) => {
+                let [study_in, study_out, bg_in, bg_out] = *contingency_table;
+                write!(&mut line_buffer, "\t{}\t{}\t{}\t{}", study_in, study_out, bg_in, bg_out)?;
+            }
+            None => line_buffer.push_str("\tNA\tNA\tNA\tNA"),
+        }
+        write!(&mut line_buffer, "\t{:.3}\t{:.5e}", results.log_odds_ratio, results.p_value)?;
+        match &results.contingency_table {
+            Some(contingency_table) => {
+                for residual in calculate_pearson_residuals(contingency_table) {
+                    write!(&mut line_buffer, "\t{:.3}", residual)?;
                 }
             }
+            None => line_buffer.push_str("\tNA\tNA\tNA\tNA"),
         }
-        
-        writer.flush()?;
+        line_buffer.push('\n');
+        writer.write_all(line_buffer.as_bytes())?;
     }
+
+    writer.flush()?;
     Ok(())
 }
 
+fn csv_field(field: &str) -> String {
+    if field.contains(',') || field.contains('"') || field.contains('\n') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+// Values of COMBINED_COLUMNS for a combined taxonomy result.
+fn combined_values(result: &TaxonomyGOResult) -> [f64; 6] {
+    let margin = CI_Z_SCORE * result.combined_variance.sqrt();
+    [
+        result.combined_variance,
+        result.log_odds_ratio - margin,
+        result.log_odds_ratio + margin,
+        result.q_statistic,
+        result.q_p_value,
+        result.i_squared,
+    ]
+}
+
+fn format_combined_values(values: &[f64; 6], separator: char) -> String {
+    format!(
+        "{sep}{:.5e}{sep}{:.3}{sep}{:.3}{sep}{:.3}{sep}{:.5e}{sep}{:.1}",
+        values[0], values[1], values[2], values[3], values[4], values[5],
+        sep = separator
+    )
+}
+
+fn write_results_file<'a, I>(
+    results_dir: &Path,
+    name: &str,
+    go_terms: I,
+    ontology: &FxHashMap<u32, OboTerm>,
+    results_file: &ResultsFile,
+    metadata: Value,
+    term_cache: &mut TermCache,
+) -> Result<(), Box<dyn Error>>
+where
+    I: Iterator<Item = (GOTermID, f64, f64, ExtraValues<'a>)>,
+{
+    let output_format = results_file.output_format;
+    let filename = results_dir.join(format!(
+        "{}{}.{}",
+        sanitize_taxon_name(name),
+        results_file.suffix,
+        output_format.extension()
+    ));
+    let file = File::create(&filename)?;
+    let mut writer = BufWriter::with_capacity(BUFFER_SIZE, file);
+    let mut line_buffer = String::with_capacity(256);
+    let mut json_results: Vec<Value> = Vec::new();
+
+    let result_columns = result_columns(results_file.effect_label);
+    let mut columns: Vec<&str> = result_columns.to_vec();
+    columns.extend(results_file.extra_columns);
+
+    match output_format {
+        OutputFormat::Tsv => writer.write_all(columns.join("\t").as_bytes())?,
+        OutputFormat::Csv => writer.write_all(columns.iter().map(|column| csv_field(column)).collect::<Vec<_>>().join(",").as_bytes())?,
+        OutputFormat::Json => {}
+    }
+    if output_format != OutputFormat::Json {
+        writer.write_all(b"\n")?;
+    }
+
+    for (go_term, log_odds_ratio, p_value, extra_values) in go_terms {
+        let Some(term) = ontology.get(&go_term) else { continue };
+        if term.is_obsolete {
+            continue;
+        }
+
+        let formatted_go_term = term_cache.get_go_term(go_term);
+        let namespace_str = term.namespace.obo_name();
+        let formatted_namespace = format_namespace(namespace_str);
+
+        let combined = match extra_values {
+            ExtraValues::Combined(result) => Some(combined_values(result)),
+            _ => None,
+        };
+        let is_significant = match extra_values {
+            ExtraValues::Significance(is_significant) => Some(is_significant),
+            _ => None,
+        };
+
+        line_buffer.clear();
+
+        match output_format {
+            OutputFormat::Tsv => {
+                write!(
+                    &mut line_buffer,
+                    "{}\t{}\t{}\t{:.3}\t{:.5e}",
+                    formatted_go_term,
+                    term.name,
+                    formatted_namespace,
+                    log_odds_ratio,
+                    p_value,
+                )?;
+                if let Some(values) = &combined {
+                    line_buffer.push_str(&format_combined_values(values, '\t'));
+                }
+                if let Some(is_significant) = is_significant {
+                    write!(&mut line_buffer, "\t{}", is_significant)?;
+                }
+                line_buffer.push('\n');
+            }
+            OutputFormat::Csv => {
+                write!(
+                    &mut line_buffer,
+                    "{},{},{},{:.3},{:.5e}",
+                    formatted_go_term,
+                    csv_field(&term.name),
+                    csv_field(formatted_namespace),
+                    log_odds_ratio,
+                    p_value,
+                )?;
+                if let Some(values) = &combined {
+                    line_buffer.push_str(&format_combined_values(values, ','));
+                }
+                if let Some(is_significant) = is_significant {
+                    write!(&mut line_buffer, ",{}", is_significant)?;
+                }
+                line_buffer.push('\n');
+            }
+            OutputFormat::Json => {
+                let mut json_result = json!({
+                    result_columns[0]: formatted_go_term,
+                    result_columns[1]: term.name,
+                    result_columns[2]: formatted_namespace,
+                    result_columns[3]: log_odds_ratio,
+                    result_columns[4]: p_value,
+                });
+                if let Some(values) = &combined {
+                    for (column, value) in COMBINED_COLUMNS.iter().zip(values.iter()) {
+                        json_result[*column] = json!(value);
+                    }
+                }
+                if let Some(is_significant) = is_significant {
+                    json_result[SIGNIFICANCE_COLUMN] = json!(is_significant);
+                }
+                json_results.push(json_result);
+                continue;
+            }
+        }
+
+        writer.write_all(line_buffer.as_bytes())?;
+    }
+
+    if output_format == OutputFormat::Json {
+        serde_json::to_writer_pretty(&mut writer, &json!({
+            "results": json_results,
+            "metadata": metadata,
+        }))?;
+        writer.write_all(b"\n")?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn print_single_taxon_summary(
+    enrichment_results: &FxHashMap<TaxonID, FxHashMap<GOTermID, GOTermResults>>,
+    significant_results: &FxHashMap<TaxonID, FxHashMap<GOTermID, GOTermResults>>,
+    taxon_protein_count: &FxHashMap<TaxonID, usize>,
+    taxid_species_map: &FxHashMap<TaxonID, String>,
+    significance_threshold: f64,
+) {
+    let mut taxon_ids: Vec<&TaxonID> = enrichment_results.keys().collect();
+    taxon_ids.sort_unstable();
+
+    let names: Vec<String> = taxon_ids
+        .iter()
+        .map(|taxon_id| taxid_species_map
+            .get(taxon_id)
+            .cloned()
+            .unwrap_or_else(|| taxon_id.to_string()))
+        .collect();
+    let name_width = names.iter().map(|name| name.len()).max().unwrap_or(0).max(5);
+
+    eprintln!(
+        "{:<name_width$}  {:>8}  {:>12}  {:>14}  {:>11}  {:>8}  {:>8}",
+        "Taxon", "Proteins", "Terms tested", "Raw p <= alpha", "Significant", "Min LOR", "Max LOR"
+    );
+
+    for (taxon_id, name) in taxon_ids.iter().zip(names.iter()) {
+        let tested = &enrichment_results[*taxon_id];
+        let proteins = taxon_protein_count.get(*taxon_id).copied().unwrap_or(0);
+        let raw_significant = tested
+            .values()
+            .filter(|result| result.p_value <= significance_threshold)
+            .count();
+
+        let significant = significant_results.get(*taxon_id);
+        let significant_count = significant.map_or(0, |terms| terms.len());
+
+        let (min_lor, max_lor) = significant
+            .map(|terms| terms
+                .values()
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), result| {
+                    (min.min(result.log_odds_ratio), max.max(result.log_odds_ratio))
+                }))
+            .filter(|(min, max)| min <= max)
+            .map_or(("-".to_string(), "-".to_string()), |(min, max)| {
+                (format!("{:.3}", min), format!("{:.3}", max))
+            });
+
+        eprintln!(
+            "{:<name_width$}  {:>8}  {:>12}  {:>14}  {:>11}  {:>8}  {:>8}",
+            name, proteins, tested.len(), raw_significant, significant_count, min_lor, max_lor
+        );
+    }
+    eprintln!();
+}
+
 pub fn write_taxonomy_results(
     data: &FxHashMap<String, FxHashMap<u32, TaxonomyGOResult>>,
     ontology: &FxHashMap<u32, OboTerm>,
-    output_dir: &PathBuf,
+    results_dir: &PathBuf,
     level: &String,
+    output_format: OutputFormat,
 ) -> Result<(), Box<dyn Error>> {
-    let results_dir = PathBuf::from(output_dir).join("combined_taxonomy_results");
-    create_dir_all(&results_dir)?;
+    create_dir_all(results_dir)?;
 
-    println!("Writing {} results to: {}\n", level, results_dir.to_str().unwrap());
+    crate::progress!("Writing {} results to: {}\n", level, results_dir.to_str().unwrap());
     
     let mut term_cache = TermCache::new();
-    let mut line_buffer = String::with_capacity(256);
+    let results_file = ResultsFile {
+        suffix: RESULTS_FILE_SUFFIX,
+        output_format,
+        effect_label: RESULT_COLUMNS[3],
+        extra_columns: &COMBINED_COLUMNS,
+    };
     
     for (taxonomy, go_terms) in data {
-        let filename = results_dir.join(format!("{}_GOEA_results.txt", sanitize_filename(taxonomy)));
-        let file = File::create(&filename)?;
-        let mut writer = BufWriter::with_capacity(BUFFER_SIZE, file);
-        
-        writer.write_all(b"GO Term ID\tName\tNamespace\tlog(Odds Ratio)\tStatistical significance\n")?;
-        for (go_term, result) in go_terms {
-            if let Some(term) = ontology.get(go_term) {
-                if !term.is_obsolete {
-                    let formatted_go_term = term_cache.get_go_term(*go_term);
-                    let namespace_str = match term.namespace {
-                        NameSpace::BiologicalProcess => "biological_process",
-                        NameSpace::MolecularFunction => "molecular_function",
-                        NameSpace::CellularComponent => "cellular_component",
-                    };
-                    let formatted_namespace = format_namespace(namespace_str);
-                    
-                    line_buffer.clear();
-                    write!(
-                        &mut line_buffer,
-                        "{}\t{}\t{}\t{:.3}\t{:.5e}\n",
-                        formatted_go_term,
-                        term.name,
-                        formatted_namespace,
-                        result.log_odds_ratio,
-                        result.p_value
-                    )?;
-                    
-                    writer.write_all(line_buffer.as_bytes())?;
-                }
-            }
-        }
-        
-        writer.flush()?;
+        let metadata = json!({
+            "taxon": taxonomy,
+            "level": level,
+        });
+
+        write_results_file(
+            results_dir,
+            taxonomy,
+            go_terms.iter().map(|(go_term, result)| (*go_term, result.log_odds_ratio, result.p_value, ExtraValues::Combined(result))),
+            ontology,
+            &results_file,
+            metadata,
+            &mut term_cache,
+        )?;
     }
     Ok(())
 }
 
-fn sanitize_filename(name: &str) -> String {
-    name.replace("/", "_")
-        .replace(":", "_")
-        .replace("\\", "_")
-        .replace("*", "_")
-        .replace("?", "_")
-        .replace("\"", "_")
-        .replace("<", "_")
-        .replace(">", "_")
-        .replace("|", "_")
-}
\ No newline at end of file
+// Taxon names such as "Escherichia coli (strain K12)" become "Escherichia_coli_strain_K12", so result
+// file names only contain characters that are safe in paths and URLs on every platform.
+pub fn sanitize_taxon_name(name: &str) -> String {
+    name.trim()
+        .replace(' ', "_")
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term_results(p_value: f64) -> GOTermResults {
+        GOTermResults {
+            log_odds_ratio: 1.0,
+            p_value,
+            contingency_table: Some([5, 5, 5, 5]),
+            study_count: 5,
+            variance: 0.8,
+        }
+    }
+
+    #[test]
+    fn cap_terms_per_namespace_keeps_the_most_significant_terms() {
+        let mut ontology: OboMap = FxHashMap::default();
+        let mut go_terms: FxHashMap<GOTermID, GOTermResults> = FxHashMap::default();
+
+        // 50 significant Biological Process terms, GO:1 being the most significant.
+        for go_term in 1..=50 {
+            ontology.insert(go_term, OboTerm::new());
+            go_terms.insert(go_term, term_results(go_term as f64 / 1000.0));
+        }
+        let mut molecular_function = OboTerm::new();
+        molecular_function.namespace = NameSpace::MolecularFunction;
+        ontology.insert(100, molecular_function);
+        go_terms.insert(100, term_results(0.04));
+
+        let capped = cap_terms_per_namespace(&go_terms, &ontology, 10);
+        let biological_process: Vec<GOTermID> = capped
+            .iter()
+            .map(|(go_term, _)| *go_term)
+            .filter(|go_term| *go_term != 100)
+            .collect();
+
+        assert_eq!(biological_process, (1..=10).collect::<Vec<GOTermID>>());
+        assert!(capped.iter().any(|(go_term, _)| *go_term == 100));
+        assert_eq!(capped.len(), 11);
+    }
+
+    #[test]
+    fn cap_terms_per_namespace_without_a_limit_keeps_every_term() {
+        let ontology: OboMap = (1..=50).map(|go_term| (go_term, OboTerm::new())).collect();
+        let go_terms: FxHashMap<GOTermID, GOTermResults> = (1..=50)
+            .map(|go_term| (go_term, term_results(0.01)))
+            .collect();
+
+        assert_eq!(cap_terms_per_namespace(&go_terms, &ontology, 0).len(), 50);
+    }
+
+    #[test]
+    fn all_results_follow_the_output_format() {
+        let output_dir = std::env::temp_dir().join(format!("taxago_all_results_{}", std::process::id()));
+        let mut ontology: OboMap = FxHashMap::default();
+        for (go_term, name) in [(1, "binding"), (2, "transport, active")] {
+            let mut term = OboTerm::new();
+            term.name = name.to_string();
+            ontology.insert(go_term, term);
+        }
+
+        let tested: FxHashMap<GOTermID, GOTermResults> = [(1, term_results(0.001)), (2, term_results(0.2))].into_iter().collect();
+        let significant: FxHashMap<GOTermID, GOTermResults> = [(1, term_results(0.001))].into_iter().collect();
+        let all_results: FxHashMap<TaxonID, FxHashMap<GOTermID, GOTermResults>> = [(9606, tested.clone())].into_iter().collect();
+        let significant_results: FxHashMap<TaxonID, FxHashMap<GOTermID, GOTermResults>> = [(9606, significant)].into_iter().collect();
+        let significant_terms: FxHashMap<TaxonID, FxHashSet<GOTermID>> = [(9606, [1].into_iter().collect())].into_iter().collect();
+        let taxid_species_map: FxHashMap<TaxonID, String> = [(9606, "Homo sapiens".to_string())].into_iter().collect();
+
+        let options = WriteOptions {
+            output_format: OutputFormat::Csv,
+            statistical_test: StatisticalTest::Fishers,
+            raw_counts: None,
+            max_terms_per_namespace: 0,
+            tested_results: &all_results,
+            all_results: Some(&all_results),
+            significant_terms: &significant_terms,
+        };
+        write_single_taxon_results(&significant_results, &ontology, &taxid_species_map, &output_dir, &options, None).unwrap();
+
+        let all_results_file = output_dir.join("single_taxon_results").join("Homo_sapiens_all_results.csv");
+        let content = fs::read_to_string(&all_results_file).unwrap();
+        fs::remove_dir_all(&output_dir).unwrap();
+
+        assert_eq!(
+            content,
+            "GO Term ID,Name,Namespace,log(Odds Ratio),Statistical significance,is_significant\n\
+             GO:0000001,binding,Biological Process,1.000,1.00000e-3,true\n\
+             GO:0000002,\"transport, active\",Biological Process,1.000,2.00000e-1,false\n"
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use clap::Parser;
+use std::error::Error;
+use std::fs::File;
+use std::io::{stdin, stdout, BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+
+use TaxaGO::parsers::obo_parser::*;
+use TaxaGO::parsers::background_parser::GOTermID;
+use TaxaGO::utils::assets::default_asset_path;
+
+#[derive(Parser, Debug)]
+#[command(name = "taxago-annotate")]
+struct CliArgs {
+    #[arg(
+        short = 'o',
+        long = "obo",
+        value_name = "OBO_FILE",
+        help = "Path to the Gene Ontology file in OBO format.",
+    )]
+    obo_file: Option<String>,
+
+    #[arg(
+        short = 't',
+        long = "terms",
+        value_name = "GO_TERMS",
+        help = "Comma-separated list of GO terms [e.g., GO:0008150,GO:0003674].",
+        conflicts_with = "terms_file",
+    )]
+    go_terms: Option<String>,
+
+    #[arg(
+        long = "terms-file",
+        value_name = "FILE",
+        help = "File with one GO term per line. If neither --terms nor --terms-file is given, GO terms are read from stdin.",
+    )]
+    terms_file: Option<PathBuf>,
+}
+
+fn parse_go_id(term: &str) -> Option<GOTermID> {
+    let term = term.trim();
+    if !term.to_lowercase().starts_with("go:") {
+        return None;
+    }
+    term[3..].parse::<GOTermID>().ok()
+}
+
+fn read_terms<R: BufRead>(reader: R) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut terms = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let term = line.trim();
+        if !term.is_empty() {
+            terms.push(term.to_string());
+        }
+    }
+    Ok(terms)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let cli_args: CliArgs = CliArgs::parse();
+
+    let obo_file = cli_args.obo_file.unwrap_or(default_asset_path("go.obo"));
+
+    let terms: Vec<String> = match (&cli_args.go_terms, &cli_args.terms_file) {
+        (Some(go_terms), _) => go_terms
+            .split(',')
+            .map(|term| term.trim().to_string())
+            .filter(|term| !term.is_empty())
+            .collect(),
+        (None, Some(terms_file)) => read_terms(BufReader::new(File::open(terms_file)?))?,
+        (None, None) => read_terms(stdin().lock())?,
+    };
+
+    if terms.is_empty() {
+        return Err(Box::new(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "No GO terms provided"
+        )));
+    }
+
+    eprintln!("\nReading ontology information from: {}\n", &obo_file);
+    let ontology = parse_obo_file_with_obsolete(&PathBuf::from(&obo_file))?;
+
+    let mut writer = BufWriter::new(stdout().lock());
+    writeln!(writer, "GO_ID\tName\tNamespace\tIsObsolete\tReplaced_by")?;
+
+    for term in &terms {
+        let Some((go_id, obo_term)) = parse_go_id(term)
+            .and_then(|go_id| ontology.get(&go_id).map(|obo_term| (go_id, obo_term))) else {
+            writeln!(writer, "{}\tNOT_FOUND\t\t\t", term)?;
+            continue;
+        };
+
+        let namespace = obo_term.namespace.label();
+        let replaced_by = obo_term.replaced_by
+            .iter()
+            .map(|replacement_id| format!("GO:{:07}", replacement_id))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        writeln!(
+            writer,
+            "GO:{:07}\t{}\t{}\t{}\t{}",
+            go_id,
+            obo_term.name,
+            namespace,
+            obo_term.is_obsolete,
+            replaced_by
+        )?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
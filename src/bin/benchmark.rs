@@ -0,0 +1,250 @@
+use clap::Parser;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::env::temp_dir;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::process;
+use std::time::{Duration, Instant};
+use std::error::Error;
+use cpu_time::ProcessTime;
+
+use TaxaGO::parsers::{
+    background_parser::*, obo_parser::*, study_parser::*
+};
+use TaxaGO::analysis::{
+    enrichment_analysis::*,
+    multiple_testing_correction::*,
+    enrichment_plots::*
+};
+use TaxaGO::utils::assets::default_asset_path;
+
+const STAGES: [&str; 6] = [
+    "OBO parsing",
+    "Background parsing",
+    "Study parsing",
+    "Enrichment analysis",
+    "Multiple testing correction",
+    "Plot generation",
+];
+
+#[derive(Parser, Debug)]
+#[command(name = "taxago-benchmark")]
+struct CliArgs {
+    #[arg(
+        short = 'o',
+        long = "obo",
+        value_name = "OBO_FILE",
+        help = "Path to the Gene Ontology file in OBO format.",
+    )]
+    obo_file: Option<String>,
+
+    #[arg(
+        short = 's',
+        long = "study",
+        value_name = "STUDY_POP",
+        help = "Path to the study population FASTA file, directory or CSV file.",
+        required = true,
+    )]
+    study_pop: String,
+
+    #[arg(
+        short = 'b',
+        long = "background",
+        value_name = "BACKGROUND_DIR",
+        help = "Directory containing background populations.",
+    )]
+    background_dir: Option<String>,
+
+    #[arg(
+        short = 'e',
+        long = "evidence",
+        value_name = "CATEGORIES",
+        help = "Evidence code categories to parse in background associations, either comma-separated or as a file with one category per line (lines starting with # are ignored). [possible values: all, experimental, phylogenetic, computational, author, curator, electronic]",
+        default_value = "all"
+    )]
+    evidence_categories: String,
+
+    #[arg(
+        long = "iterations",
+        value_name = "NUMBER",
+        help = "Number of times the pipeline is run. Every run parses all inputs again.",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        default_value_t = 3
+    )]
+    iterations: usize,
+
+    #[arg(
+        long = "save-plots",
+        help = "Plot type generated in the plot stage. Plots are written to a temporary directory and removed after each run.",
+        value_enum,
+        default_value_t = PlotType::Interactive
+    )]
+    save_plots: PlotType,
+
+    #[arg(
+        long = "cores",
+        value_name = "NUMBER",
+        help = "Number of cores to use for the analysis. Uses all available by default.",
+        default_value_t = num_cpus::get()
+    )]
+    num_cores: usize,
+
+    #[arg(
+        long = "output",
+        value_name = "FILE",
+        help = "Markdown file to write the benchmark table to.",
+        default_value = "benchmark_results.md"
+    )]
+    output_file: PathBuf,
+}
+
+struct StageTiming {
+    wall: Duration,
+    cpu: Duration,
+}
+
+fn time_stage<T, F>(timings: &mut Vec<StageTiming>, stage: F) -> T
+where
+    F: FnOnce() -> T
+{
+    let wall_start = Instant::now();
+    let cpu_start = ProcessTime::now();
+    let result = stage();
+    timings.push(StageTiming {
+        wall: wall_start.elapsed(),
+        cpu: cpu_start.elapsed(),
+    });
+    result
+}
+
+fn run_pipeline(
+    cli_args: &CliArgs,
+    obo_file: &PathBuf,
+    background_dir: &str,
+    categories: &Vec<EvidenceCategory>,
+    plots_dir: &PathBuf,
+) -> Result<Vec<StageTiming>, Box<dyn Error>> {
+    let mut timings = Vec::with_capacity(STAGES.len());
+
+    let ontology = time_stage(&mut timings, || parse_obo_file(obo_file))?;
+
+    let taxon_ids = collect_taxon_ids(&PathBuf::from(&cli_args.study_pop)).map_err(|e| e.to_string())?;
+    let mut background_population = time_stage(&mut timings, || {
+        BackgroundPop::read_background_pop(&taxon_ids, background_dir, None, categories, &FxHashSet::default())
+    })?.ok_or("No background population data could be loaded")?;
+
+    let mut study_population = time_stage(&mut timings, || {
+        StudyPop::read_study_pop(&PathBuf::from(&cli_args.study_pop), &background_population.protein_to_go)
+    }).map_err(|e| e.to_string())?.ok_or("No study population data could be loaded")?;
+
+    let enrichment_results = time_stage(&mut timings, || {
+        study_population.filter_by_background_population(&taxon_ids, &background_population);
+        background_population.filter_by_study_population(&taxon_ids, &study_population);
+
+        EnrichmentAnalysis::new(StatisticalTest::Fishers).classic(
+            &taxon_ids,
+            &background_population.go_term_count,
+            &study_population.go_term_count,
+            &background_population.taxon_protein_count,
+            &study_population.taxon_protein_count,
+        )
+    });
+
+    let significant_results = time_stage(&mut timings, || {
+        adjust_species_p_values(
+            &enrichment_results,
+            AdjustmentMethod::BenjaminiHochberg,
+            Some(0.05),
+            0.0
+        )
+    });
+
+    time_stage(&mut timings, || -> Result<(), Box<dyn Error>> {
+        if cli_args.save_plots == PlotType::None {
+            return Ok(());
+        }
+
+        let named_results: FxHashMap<String, _> = significant_results
+            .into_iter()
+            .map(|(taxon_id, go_terms)| (taxon_id.to_string(), go_terms))
+            .collect();
+        let effect_label = StatisticalTest::Fishers.effect_size_label();
+        let plot_data = prepare_plot_data(&named_results, &ontology, 0, effect_label);
+
+        fs::create_dir_all(plots_dir)?;
+        bar_plot(&plot_data, plots_dir, cli_args.save_plots, effect_label).map_err(|e| e.to_string())?;
+        bubble_plot(&plot_data, plots_dir, cli_args.save_plots, effect_label).map_err(|e| e.to_string())?;
+        volcano_plot(&plot_data, plots_dir, cli_args.save_plots, 0.05, 0.0, effect_label).map_err(|e| e.to_string())?;
+        fs::remove_dir_all(plots_dir)?;
+        Ok(())
+    })?;
+
+    Ok(timings)
+}
+
+fn write_benchmark_table(
+    all_timings: &[Vec<StageTiming>],
+    num_cores: usize,
+    output_file: &PathBuf,
+) -> Result<(), Box<dyn Error>> {
+    let mut writer = BufWriter::new(File::create(output_file)?);
+
+    writeln!(writer, "# TaxaGO benchmark\n")?;
+    writeln!(writer, "{} iteration(s) with {} core(s). CPU per core is the process CPU time divided by the number of cores.\n", all_timings.len(), num_cores)?;
+    writeln!(writer, "| Iteration | Stage | Wall time (s) | CPU time (s) | CPU per core (s) |")?;
+    writeln!(writer, "|---|---|---|---|---|")?;
+
+    for (iteration, timings) in all_timings.iter().enumerate() {
+        for (stage, timing) in STAGES.iter().zip(timings.iter()) {
+            writeln!(
+                writer,
+                "| {} | {} | {:.3} | {:.3} | {:.3} |",
+                iteration + 1,
+                stage,
+                timing.wall.as_secs_f64(),
+                timing.cpu.as_secs_f64(),
+                timing.cpu.as_secs_f64() / num_cores as f64
+            )?;
+        }
+    }
+
+    writeln!(writer, "\n| Stage | Mean wall time (s) | Mean CPU time (s) |")?;
+    writeln!(writer, "|---|---|---|")?;
+
+    for (stage_index, stage) in STAGES.iter().enumerate() {
+        let iterations = all_timings.len() as f64;
+        let mean_wall = all_timings.iter().map(|timings| timings[stage_index].wall.as_secs_f64()).sum::<f64>() / iterations;
+        let mean_cpu = all_timings.iter().map(|timings| timings[stage_index].cpu.as_secs_f64()).sum::<f64>() / iterations;
+        writeln!(writer, "| {} | {:.3} | {:.3} |", stage, mean_wall, mean_cpu)?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let cli_args: CliArgs = CliArgs::parse();
+
+    let obo_file = PathBuf::from(cli_args.obo_file.clone().unwrap_or(default_asset_path("go.obo")));
+    let background_dir = cli_args.background_dir.clone().unwrap_or(default_asset_path("background_pop"));
+    let categories = map_input_to_category(cli_args.evidence_categories.clone())?;
+    let plots_dir = temp_dir().join(format!("taxago_benchmark_plots_{}", process::id()));
+
+    if let Err(e) = rayon::ThreadPoolBuilder::new()
+        .num_threads(cli_args.num_cores)
+        .build_global() {
+        eprintln!("Failed to initialize Rayon global thread pool: {:?}", e);
+    };
+
+    let mut all_timings = Vec::with_capacity(cli_args.iterations);
+    for iteration in 1..=cli_args.iterations {
+        println!("\nBenchmark iteration {}/{}", iteration, cli_args.iterations);
+        all_timings.push(run_pipeline(&cli_args, &obo_file, &background_dir, &categories, &plots_dir)?);
+    }
+
+    write_benchmark_table(&all_timings, cli_args.num_cores, &cli_args.output_file)?;
+    println!("\nBenchmark results have been written to: {}\n", cli_args.output_file.display());
+
+    Ok(())
+}
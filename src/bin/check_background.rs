@@ -0,0 +1,116 @@
+use clap::Parser;
+use std::fs::read_dir;
+use std::path::PathBuf;
+use std::error::Error;
+
+use TaxaGO::parsers::{
+    background_parser::*, obo_parser::*
+};
+use TaxaGO::analysis::handle_lineage::*;
+use TaxaGO::utils::assets::default_asset_path;
+
+#[derive(Parser, Debug)]
+#[command(name = "taxago-check-background")]
+struct CliArgs {
+    #[arg(
+        short = 'o',
+        long = "obo",
+        value_name = "OBO_FILE",
+        help = "Path to the Gene Ontology file in OBO format.",
+    )]
+    obo_file: Option<String>,
+
+    #[arg(
+        short = 'b',
+        long = "background",
+        value_name = "BACKGROUND_DIR",
+        help = "Directory containing the {taxon_id}_background.txt files to check.",
+    )]
+    background_dir: Option<String>,
+
+    #[arg(
+        short = 'e',
+        long = "evidence",
+        value_name = "CATEGORIES",
+        help = "Evidence code categories to parse in background associations, either comma-separated or as a file with one category per line (lines starting with # are ignored). [possible values: all, experimental, phylogenetic, computational, author, curator, electronic]",
+        default_value = "all"
+    )]
+    evidence_categories: String,
+
+    #[arg(
+        short = 'l',
+        long = "lineage",
+        value_name = "LINEAGE_FILE",
+        help = "Lineage file used to check that each taxon ID is known.",
+    )]
+    lineage_file: Option<String>,
+
+    #[arg(
+        long = "sample-lines",
+        value_name = "LINES",
+        help = "Number of lines parsed from the start of each file.",
+        default_value_t = 100
+    )]
+    sample_lines: usize,
+}
+
+// JSON strings are valid YAML scalars, so file names and messages are quoted with serde_json.
+fn yaml_string(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| String::from("\"\""))
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let cli_args: CliArgs = CliArgs::parse();
+
+    let obo_file = cli_args.obo_file.unwrap_or(default_asset_path("go.obo"));
+    let background_dir = cli_args.background_dir.unwrap_or(default_asset_path("background_pop"));
+    let lineage_file = cli_args.lineage_file.unwrap_or(default_asset_path("lineage.txt"));
+
+    let categories = map_input_to_category(cli_args.evidence_categories)?;
+    let ontology = parse_obo_file(&PathBuf::from(&obo_file))?;
+    let lineage = read_lineage(&lineage_file)?;
+
+    let mut background_files: Vec<PathBuf> = read_dir(&background_dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .collect();
+    background_files.sort();
+
+    let mut valid_files = 0;
+    let mut error_files = 0;
+
+    println!("files:{}", if background_files.is_empty() { " []" } else { "" });
+    for background_file in &background_files {
+        let file_name = background_file
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        println!("  - file: {}", yaml_string(&file_name));
+
+        match check_background_file(background_file, &categories, cli_args.sample_lines) {
+            Ok((taxon_id, proteins_read, go_terms)) => {
+                let unknown_go_terms = go_terms.iter().filter(|go_term| !ontology.contains_key(*go_term)).count();
+                println!("    taxon_id: {}", taxon_id);
+                println!("    proteins_read: {}", proteins_read);
+                println!("    in_lineage: {}", lineage.contains_key(&taxon_id));
+                println!("    unknown_go_terms: {}", unknown_go_terms);
+                println!("    error: null");
+                valid_files += 1;
+            },
+            Err(e) => {
+                println!("    taxon_id: null");
+                println!("    proteins_read: 0");
+                println!("    in_lineage: false");
+                println!("    unknown_go_terms: 0");
+                println!("    error: {}", yaml_string(&e.to_string()));
+                error_files += 1;
+            }
+        }
+    }
+
+    println!("summary:");
+    println!("  valid_files: {}", valid_files);
+    println!("  error_files: {}", error_files);
+
+    Ok(())
+}
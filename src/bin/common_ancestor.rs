@@ -1,30 +1,11 @@
 use clap::Parser;
-use rustc_hash::FxHashMap;
 use std::error::Error;
 use std::process::Command;
 use std::path::PathBuf;
-use dirs::home_dir;
-use std::env::var;
-use daggy::NodeIndex;
 
 use TaxaGO::parsers::obo_parser::*;
 use TaxaGO::utils::common_ancestor::*;
-
-fn get_default_obo_path() -> String {
-    let cargo_home = var("CARGO_HOME")
-        .unwrap_or_else(|_| {
-            home_dir()
-                .expect("Could not determine home directory")
-                .join(".cargo")
-                .to_string_lossy()
-                .into_owned()
-        });
-    PathBuf::from(cargo_home)
-        .join("taxago_assets")
-        .join("go.obo")
-        .to_string_lossy()
-        .into_owned()
-}
+use TaxaGO::utils::assets::default_asset_path;
 
 #[derive(Parser, Debug)]
 #[command(name = "common-ancestors")]
@@ -81,7 +62,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     let cli_args: CliArgs = CliArgs::parse();
     
     // Calculate default path at runtime
-    let default_obo_path = get_default_obo_path();
+    let default_obo_path = default_asset_path("go.obo");
     
     // Use provided value or default
     let obo_file = cli_args.obo_file.unwrap_or(default_obo_path);
@@ -97,39 +78,19 @@ fn main() -> Result<(), Box<dyn Error>> {
     let obo_file_path = PathBuf::from(&obo_file);
     let ontology = parse_obo_file(&obo_file_path)?;
     let (ontology_graph, go_id_to_node_index) = build_ontology_graph(&ontology)?;
-    
-    let node_index_to_go_id: FxHashMap<NodeIndex, u32> = go_id_to_node_index
-        .iter()
-        .map(|(&go_id, &node_idx)| (node_idx, go_id))
-        .collect();
 
-    for &go_id in &target_go_ids {
-        if !go_id_to_node_index.contains_key(&go_id) {
-            eprintln!("Error: GO:{:07} not found in the ontology", go_id);
+    let ancestor_result = match run_common_ancestor_analysis(
+        &target_go_ids,
+        &ontology,
+        &ontology_graph,
+        &go_id_to_node_index
+    ) {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("Error: {}", e);
             std::process::exit(1);
         }
-    }
-    
-    let mut ancestry_paths = Vec::new();
-    
-    for &go_id in &target_go_ids {
-        let target_node_idx = go_id_to_node_index[&go_id];        
-        let path = collect_ancestry_path(&ontology_graph, target_node_idx);
-        ancestry_paths.push(path);
-    }
-    
-    let common_ancestors = find_common_ancestors(&ancestry_paths, &node_index_to_go_id);
-    
-    if common_ancestors.is_empty() {
-        eprintln!("No common ancestors found between the provided GO terms");
-        std::process::exit(1);
-    }
-
-    let first_common_ancestor = find_first_common_ancestor(
-        &ancestry_paths, 
-        &node_index_to_go_id,
-        &ontology_graph
-    );
+    };
     
     println!("\nAnalyzing GO terms: {}", target_go_ids.iter()
         .map(|id| format!("GO:{:07}", id))
@@ -137,24 +98,21 @@ fn main() -> Result<(), Box<dyn Error>> {
         .join(", "));
     
     println!("\nCommon ancestors:");
-    for &go_id in &common_ancestors {
-        let term = &ontology[&go_id];
+    for go_id in &ancestor_result.common_ancestors {
+        let term = &ontology[go_id];
+        println!("GO:{:07} - {}", go_id, term.name.replace("_", " "));
+    }
+
+    println!("\nMost specific common ancestors:");
+    for go_id in &ancestor_result.most_specific_ancestors {
+        let term = &ontology[go_id];
         println!("GO:{:07} - {}", go_id, term.name.replace("_", " "));
     }
-    
-    let mermaid_chart = generate_mermaid_chart(
-        &ontology_graph,
-        &target_go_ids,
-        &go_id_to_node_index,
-        &node_index_to_go_id,
-        &ontology,
-        first_common_ancestor
-    );
 
     let graph_file = cli_args.graph_path.to_string() + "ontology_graph.mmd";
     let graph_pdf = cli_args.graph_path.to_string() + "ontology_graph.pdf";
 
-    std::fs::write(&graph_file, &mermaid_chart)?;
+    std::fs::write(&graph_file, &ancestor_result.mermaid_chart)?;
     println!("\nMermaid graph Markdown file has been written to: {}\n", &graph_file);
 
     let mermaid_cli = Command::new("mmdc")
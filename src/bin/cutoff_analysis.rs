@@ -0,0 +1,86 @@
+use clap::Parser;
+use std::fs::create_dir_all;
+use std::path::PathBuf;
+use std::error::Error;
+
+use TaxaGO::parsers::obo_parser::*;
+use TaxaGO::analysis::{
+    cutoff_analysis::*,
+    results_summary::read_results_dir
+};
+use TaxaGO::utils::assets::default_asset_path;
+
+#[derive(Parser, Debug)]
+#[command(name = "taxago-cutoff-analysis")]
+struct CliArgs {
+    #[arg(
+        short = 'o',
+        long = "obo",
+        value_name = "OBO_FILE",
+        help = "Path to the Gene Ontology file in OBO format.",
+    )]
+    obo_file: Option<String>,
+
+    #[arg(
+        long = "results-dir",
+        value_name = "RESULTS_DIR",
+        help = "Directory containing the *_GOEA_results.{txt,csv,json} single taxon result files.",
+        default_value = "./results/single_taxon_results",
+    )]
+    results_dir: PathBuf,
+
+    #[arg(
+        long = "thresholds",
+        value_name = "THRESHOLDS",
+        help = "Comma-separated minimum log(Odds Ratio) values to evaluate.",
+        value_delimiter = ',',
+        default_value = "0.0,0.1,0.2,0.5,1.0,2.0"
+    )]
+    thresholds: Vec<f64>,
+
+    #[arg(
+        short = 'd',
+        long = "dir",
+        value_name = "OUTPUT_DIR",
+        help = "Directory to write cutoff_analysis.tsv and cutoff_analysis.html.",
+        default_value = "./results",
+    )]
+    output_dir: PathBuf,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let cli_args: CliArgs = CliArgs::parse();
+
+    let obo_file = cli_args.obo_file.clone().unwrap_or(default_asset_path("go.obo"));
+
+    let mut thresholds = cli_args.thresholds.clone();
+    if thresholds.iter().any(|threshold| !threshold.is_finite()) {
+        return Err(Box::new(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "Thresholds must be finite numbers"
+        )));
+    }
+    thresholds.sort_by(|a, b| a.total_cmp(b));
+    thresholds.dedup();
+
+    println!("\nReading ontology information from: {}\n", &obo_file);
+    let ontology = parse_obo_file(&PathBuf::from(&obo_file))?;
+
+    println!("Reading single taxon results from: {}\n", cli_args.results_dir.display());
+    let species_results = read_results_dir(&cli_args.results_dir)?;
+    if species_results.is_empty() {
+        return Err(Box::new(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("No *_GOEA_results.{{txt,csv,json}} files found in '{}'", cli_args.results_dir.display())
+        )));
+    }
+
+    let cutoff_counts = cutoff_analysis(&species_results, &ontology, &thresholds);
+
+    create_dir_all(&cli_args.output_dir)?;
+    write_cutoff_analysis(&cutoff_counts, &cli_args.output_dir.join("cutoff_analysis.tsv"))?;
+    cutoff_plot(&cutoff_counts, &thresholds, &cli_args.output_dir.join("cutoff_analysis.html"))?;
+
+    println!("Finished cutoff analysis\n");
+    Ok(())
+}
@@ -0,0 +1,238 @@
+use clap::Parser;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::fs::create_dir_all;
+use std::path::PathBuf;
+use std::error::Error;
+
+use TaxaGO::parsers::{
+    background_parser::*, obo_parser::*, study_parser::*
+};
+use TaxaGO::analysis::{
+    enrichment_analysis::*,
+    multiple_testing_correction::*,
+    count_propagation::*,
+    differential_enrichment::*,
+    taxon_enrichment::*
+};
+use TaxaGO::utils::assets::default_asset_path;
+
+#[derive(Parser, Debug)]
+#[command(name = "taxago-diff")]
+struct CliArgs {
+    #[arg(
+        short = 'o',
+        long = "obo",
+        value_name = "OBO_FILE",
+        help = "Path to the Gene Ontology file in OBO format.",
+    )]
+    obo_file: Option<String>,
+
+    #[arg(
+        long = "study-a",
+        value_name = "FILE",
+        help = "Study population A in FASTA, CSV or UniProt TSV format.",
+    )]
+    study_a: PathBuf,
+
+    #[arg(
+        long = "study-b",
+        value_name = "FILE",
+        help = "Study population B in FASTA, CSV or UniProt TSV format. Must belong to the same taxon as study population A.",
+    )]
+    study_b: PathBuf,
+
+    #[arg(
+        short = 'b',
+        long = "background",
+        value_name = "BACKGROUND_DIR",
+        help = "Directory containing background populations.",
+    )]
+    background_dir: Option<String>,
+
+    #[arg(
+        short = 'e',
+        long = "evidence",
+        value_name = "CATEGORIES",
+        help = "Evidence code categories to parse in background associations, either comma-separated or as a file with one category per line (lines starting with # are ignored). [possible values: all, experimental, phylogenetic, computational, author, curator, electronic]",
+        default_value = "all"
+    )]
+    evidence_categories: String,
+
+    #[arg(
+        short = 'p',
+        long = "propagate-counts",
+        help = "Propagates GO term counts upwards the Ontology graph (from child to parent). [Must be specified to propagate the counts]",
+        default_value_t = false
+    )]
+    propagate_counts: bool,
+
+    #[arg(
+        short = 't',
+        long = "test",
+        value_enum,
+        help = "Statistical test to use.",
+        default_value_t = StatisticalTest::Fishers
+    )]
+    statistical_test: StatisticalTest,
+
+    #[arg(
+        short = 'm',
+        long = "min-prot",
+        value_name = "COUNT",
+        help = "Minimum protein count a GO Term must have to be processed.",
+        default_value_t = 5
+    )]
+    min_protein_count: usize,
+
+    #[arg(
+        short = 'c',
+        long = "correction-method",
+        value_enum,
+        help = "Method to adjust p-values for multiple test correction.",
+        default_value_t = AdjustmentMethod::BenjaminiHochberg
+    )]
+    correction_method: AdjustmentMethod,
+
+    #[arg(
+        short = 'd',
+        long = "dir",
+        value_name = "RESULTS_DIR",
+        help = "Directory to write results.",
+        default_value = "./",
+    )]
+    output_dir: String,
+}
+
+fn read_single_taxon_study(
+    study_path: &PathBuf,
+    protein_to_go: &FxHashMap<TaxonID, ProteinToGO>
+) -> Result<StudyPop, Box<dyn Error>> {
+    match StudyPop::read_study_pop(study_path, protein_to_go).map_err(|e| e.to_string())? {
+        Some(study_pop) => Ok(study_pop),
+        None => Err(Box::new(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("No study population data could be loaded from '{}'", study_path.display())
+        )))
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+
+    let cli_args: CliArgs = CliArgs::parse();
+
+    let default_obo_path = default_asset_path("go.obo");
+    let default_background_path = default_asset_path("background_pop");
+
+    let obo_file = cli_args.obo_file.unwrap_or(default_obo_path);
+    let background_dir = cli_args.background_dir.unwrap_or(default_background_path);
+
+    if matches!(cli_args.statistical_test, StatisticalTest::KolmogorovSmirnov) {
+        return Err(Box::new(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "The kolmogorov-smirnov test requires a ranked study population and is not supported by taxago-diff"
+        )));
+    }
+    if matches!(cli_args.statistical_test, StatisticalTest::BetaBinomial) {
+        return Err(Box::new(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "The beta-binomial test reports Bayes factors instead of log(Odds Ratios) and is not supported by taxago-diff"
+        )));
+    }
+
+    create_dir_all(&cli_args.output_dir)?;
+
+    let taxon_ids_a = collect_taxon_ids(&cli_args.study_a).map_err(|e| e.to_string())?;
+    let taxon_ids_b = collect_taxon_ids(&cli_args.study_b).map_err(|e| e.to_string())?;
+
+    if taxon_ids_a.len() != 1 || taxon_ids_a != taxon_ids_b {
+        return Err(Box::new(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "Both study populations must contain exactly the same single taxon"
+        )));
+    }
+    let taxon_ids: FxHashSet<TaxonID> = taxon_ids_a;
+    let taxon_id = *taxon_ids.iter().next().unwrap();
+
+    println!("\nReading ontology information from: {}\n", &obo_file);
+
+    let obo_file_path = PathBuf::from(&obo_file);
+    let ontology = parse_obo_file(&obo_file_path)?;
+
+    println!("Reading background population from: {}\n", &background_dir);
+
+    let categories = map_input_to_category(cli_args.evidence_categories.clone())?;
+
+    let mut background_population = match BackgroundPop::read_background_pop(
+        &taxon_ids,
+        &background_dir,
+        None,
+        &categories,
+        &FxHashSet::default()
+    )? {
+        Some(background_pop) => background_pop,
+        None => {
+            return Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "No background population data could be loaded\n"
+            )));
+        }
+    };
+
+    println!("Reading study populations A and B for taxon {}\n", taxon_id);
+
+    let mut study_population_a = read_single_taxon_study(&cli_args.study_a, &background_population.protein_to_go)?;
+    let mut study_population_b = read_single_taxon_study(&cli_args.study_b, &background_population.protein_to_go)?;
+
+    if cli_args.propagate_counts {
+        println!("Propagating counts up the Ontology graph\n");
+
+        let ancestor_cache = GOAncestorCache::from_ontology(&ontology)?;
+
+        study_population_a.propagate_counts(&taxon_ids, &ancestor_cache);
+        study_population_b.propagate_counts(&taxon_ids, &ancestor_cache);
+        background_population.propagate_counts(&taxon_ids, &ancestor_cache);
+    }
+
+    study_population_a.filter_by_threshold(&taxon_ids, cli_args.min_protein_count);
+    study_population_b.filter_by_threshold(&taxon_ids, cli_args.min_protein_count);
+
+    println!("Performing enrichment analysis for both study populations\n");
+
+    let analysis = EnrichmentAnalysis::new(cli_args.statistical_test);
+
+    let enrichment_results_a = run_taxon_enrichment(
+        &analysis,
+        &EnrichmentAlgorithm::Classic,
+        &taxon_ids,
+        &study_population_a,
+        &background_population
+    );
+    let enrichment_results_b = run_taxon_enrichment(
+        &analysis,
+        &EnrichmentAlgorithm::Classic,
+        &taxon_ids,
+        &study_population_b,
+        &background_population
+    );
+
+    let empty_results = FxHashMap::default();
+    let differential_results = differential_enrichment(
+        enrichment_results_a.get(&taxon_id).unwrap_or(&empty_results),
+        enrichment_results_b.get(&taxon_id).unwrap_or(&empty_results),
+        cli_args.correction_method
+    );
+
+    println!("Compared {} GO terms present in both study populations\n", differential_results.len());
+
+    let output_file = PathBuf::from(&cli_args.output_dir)
+        .join(format!("differential_enrichment_taxon_{}.tsv", taxon_id));
+
+    write_differential_results(
+        &differential_results,
+        &ontology,
+        &output_file
+    )?;
+
+    println!("Finished analysis\n");
+    Ok(())
+}
@@ -0,0 +1,135 @@
+use clap::{Parser, ValueEnum};
+use std::fs::{self, read_dir};
+use std::path::PathBuf;
+use std::error::Error;
+use serde_json::{json, Value};
+
+use TaxaGO::analysis::enrichment_plots::CYTOSCAPE_FILE_SUFFIX;
+
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq)]
+enum NamespaceFilter {
+    #[value(name = "BP")]
+    Bp,
+    #[value(name = "MF")]
+    Mf,
+    #[value(name = "CC")]
+    Cc,
+    All,
+}
+
+impl NamespaceFilter {
+    fn subdirs(&self) -> Vec<&'static str> {
+        match self {
+            NamespaceFilter::Bp => vec!["Biological_Process"],
+            NamespaceFilter::Mf => vec!["Molecular_Function"],
+            NamespaceFilter::Cc => vec!["Cellular_Component"],
+            NamespaceFilter::All => vec!["Biological_Process", "Molecular_Function", "Cellular_Component"],
+        }
+    }
+}
+
+#[derive(Parser, Debug)]
+#[command(name = "taxago-export-network")]
+struct CliArgs {
+    #[arg(
+        long = "results-dir",
+        value_name = "RESULTS_DIR",
+        help = "Results directory whose plots/ subdirectory contains the *_network.cyjs files written by taxago.",
+        default_value = "./results/single_taxon_results",
+    )]
+    results_dir: PathBuf,
+
+    #[arg(
+        long = "output",
+        value_name = "FILE",
+        help = "Cytoscape.js JSON file to write the networks to.",
+        default_value = "network.cyjs",
+    )]
+    output_file: PathBuf,
+
+    #[arg(
+        long = "namespace",
+        value_enum,
+        help = "GO namespace of the networks to export.",
+        default_value_t = NamespaceFilter::All
+    )]
+    namespace: NamespaceFilter,
+
+    #[arg(
+        long = "taxon",
+        value_name = "TAXON_NAME",
+        help = "Only export the networks of this taxon, as named in the network file names. All taxa are exported if not given.",
+    )]
+    taxon: Option<String>,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let cli_args: CliArgs = CliArgs::parse();
+
+    let plots_dir = cli_args.results_dir.join("plots");
+    let mut network_files: Vec<(String, PathBuf)> = Vec::new();
+
+    for namespace_subdir in cli_args.namespace.subdirs() {
+        let namespace_dir = plots_dir.join(namespace_subdir);
+        if !namespace_dir.is_dir() {
+            continue;
+        }
+        for entry in read_dir(&namespace_dir)? {
+            let path = entry?.path();
+            let Some(taxon_name) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(CYTOSCAPE_FILE_SUFFIX))
+                .map(|name| name.to_string()) else { continue };
+
+            if cli_args.taxon.as_ref().is_none_or(|taxon| *taxon == taxon_name) {
+                network_files.push((taxon_name, path));
+            }
+        }
+    }
+    network_files.sort();
+
+    if network_files.is_empty() {
+        return Err(format!("No *{} files found in '{}'", CYTOSCAPE_FILE_SUFFIX, plots_dir.display()).into());
+    }
+
+    // Node IDs are only unique within a taxon, so they are prefixed with the taxon name when several taxa are exported.
+    let first_taxon = &network_files[0].0;
+    let multiple_taxa = network_files.iter().any(|(taxon_name, _)| taxon_name != first_taxon);
+    let node_id = |taxon_name: &str, go_id: &Value| match (multiple_taxa, go_id.as_str()) {
+        (true, Some(go_id)) => json!(format!("{}/{}", taxon_name, go_id)),
+        _ => go_id.clone(),
+    };
+
+    let mut nodes: Vec<Value> = Vec::new();
+    let mut edges: Vec<Value> = Vec::new();
+
+    for (taxon_name, path) in &network_files {
+        let bundle: Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+        let elements = &bundle["elements"];
+
+        for mut node in elements["nodes"].as_array().cloned().unwrap_or_default() {
+            node["data"]["id"] = node_id(taxon_name, &node["data"]["id"]);
+            node["data"]["taxon"] = json!(taxon_name);
+            nodes.push(node);
+        }
+        for mut edge in elements["edges"].as_array().cloned().unwrap_or_default() {
+            edge["data"]["source"] = node_id(taxon_name, &edge["data"]["source"]);
+            edge["data"]["target"] = node_id(taxon_name, &edge["data"]["target"]);
+            edge["data"]["taxon"] = json!(taxon_name);
+            edges.push(edge);
+        }
+    }
+
+    if let Some(output_dir) = cli_args.output_file.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(output_dir)?;
+    }
+    let bundle = json!({"elements": {"nodes": nodes, "edges": edges}});
+    fs::write(&cli_args.output_file, serde_json::to_string_pretty(&bundle)?)?;
+
+    println!(
+        "Wrote {} nodes and {} edges from {} networks to: {}",
+        nodes.len(), edges.len(), network_files.len(), cli_args.output_file.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use clap::Parser;
+use rustc_hash::FxHashSet;
+use std::fs::create_dir_all;
+use std::path::PathBuf;
+use std::error::Error;
+
+use TaxaGO::parsers::{
+    background_parser::*, obo_parser::*
+};
+use TaxaGO::analysis::count_propagation::*;
+use TaxaGO::utils::{
+    assets::default_asset_path,
+    extract_proteins::*,
+    semantic_similarity::process_go_terms_input
+};
+
+#[derive(Parser, Debug)]
+#[command(name = "taxago-extract-proteins")]
+struct CliArgs {
+    #[arg(
+        short = 'o',
+        long = "obo",
+        value_name = "OBO_FILE",
+        help = "Path to the Gene Ontology file in OBO format.",
+    )]
+    obo_file: Option<String>,
+
+    #[arg(
+        short = 't',
+        long = "terms",
+        value_name = "GO_TERMS_OR_FILE",
+        help = "Comma-separated GO terms [e.g., GO:0005515,GO:0003677] or a file with one GO term per line.",
+    )]
+    terms: String,
+
+    #[arg(
+        long = "taxon",
+        value_name = "TAXON_ID",
+        help = "NCBI taxon ID to extract proteins for. All taxa in the background are used if not given.",
+    )]
+    taxon_id: Option<TaxonID>,
+
+    #[arg(
+        short = 'b',
+        long = "background",
+        value_name = "BACKGROUND_DIR",
+        help = "Directory (or .tar.gz archive) containing background populations.",
+    )]
+    background_dir: Option<String>,
+
+    #[arg(
+        short = 'e',
+        long = "evidence",
+        value_name = "CATEGORIES",
+        help = "Evidence code categories to parse in background associations, either comma-separated or as a file with one category per line (lines starting with # are ignored). [possible values: all, experimental, phylogenetic, computational, author, curator, electronic]",
+        default_value = "all"
+    )]
+    evidence_categories: String,
+
+    #[arg(
+        short = 'p',
+        long = "propagate-counts",
+        help = "Propagates background annotations upwards the Ontology graph, so proteins annotated to descendant terms are included.",
+        default_value_t = false
+    )]
+    propagate_counts: bool,
+
+    #[arg(
+        long = "output",
+        value_name = "FILE",
+        help = "TSV file to write the proteins to.",
+        default_value = "proteins.tsv",
+    )]
+    output_file: PathBuf,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+
+    let cli_args: CliArgs = CliArgs::parse();
+
+    let obo_file = cli_args.obo_file.unwrap_or(default_asset_path("go.obo"));
+    let background_dir = cli_args.background_dir.unwrap_or(default_asset_path("background_pop"));
+
+    if let Some(output_dir) = cli_args.output_file.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        create_dir_all(output_dir)?;
+    }
+
+    let go_terms = process_go_terms_input(&cli_args.terms, 0)?;
+
+    println!("\nReading ontology information from: {}\n", &obo_file);
+    let ontology = parse_obo_file(&PathBuf::from(&obo_file))?;
+
+    let taxon_ids: FxHashSet<TaxonID> = match cli_args.taxon_id {
+        Some(taxon_id) => FxHashSet::from_iter([taxon_id]),
+        None => list_background_taxa(&background_dir)?,
+    };
+    if taxon_ids.is_empty() {
+        return Err(format!("No *_background.txt files found in '{}'", background_dir).into());
+    }
+
+    println!("Reading background population of {} taxa from: {}\n", taxon_ids.len(), &background_dir);
+    let categories = map_input_to_category(cli_args.evidence_categories.clone())?;
+    let mut background_population = BackgroundPop::read_background_pop(
+        &taxon_ids,
+        &background_dir,
+        None,
+        &categories,
+        &FxHashSet::default()
+    )?.ok_or("No background population data could be loaded")?;
+
+    if cli_args.propagate_counts {
+        println!("Propagating background annotations up the Ontology graph\n");
+
+        let ancestor_cache = GOAncestorCache::from_ontology(&ontology)?;
+
+        background_population.propagate_counts(&taxon_ids, &ancestor_cache);
+    }
+
+    let rows = extract_proteins(&go_terms, &background_population);
+
+    write_extracted_proteins(&rows, &ontology, &cli_args.output_file)?;
+
+    println!("Finished extraction\n");
+    Ok(())
+}
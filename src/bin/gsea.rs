@@ -0,0 +1,205 @@
+use clap::Parser;
+use rustc_hash::FxHashSet;
+use std::fs::create_dir_all;
+use std::path::PathBuf;
+use std::error::Error;
+
+use TaxaGO::parsers::{
+    background_parser::*, obo_parser::*, study_parser::*
+};
+use TaxaGO::analysis::{
+    count_propagation::*,
+    gsea::*
+};
+use TaxaGO::utils::assets::default_asset_path;
+
+#[derive(Parser, Debug)]
+#[command(name = "taxago-gsea")]
+struct CliArgs {
+    #[arg(
+        short = 'o',
+        long = "obo",
+        value_name = "OBO_FILE",
+        help = "Path to the Gene Ontology file in OBO format.",
+    )]
+    obo_file: Option<String>,
+
+    #[arg(
+        short = 'r',
+        long = "ranked-proteins",
+        value_name = "FILE",
+        help = "CSV file with 'taxon_id', 'protein_id' and 'score' columns, one row per protein.",
+    )]
+    ranked_proteins: PathBuf,
+
+    #[arg(
+        short = 'b',
+        long = "background",
+        value_name = "BACKGROUND_DIR",
+        help = "Directory containing background populations.",
+    )]
+    background_dir: Option<String>,
+
+    #[arg(
+        short = 'e',
+        long = "evidence",
+        value_name = "CATEGORIES",
+        help = "Evidence code categories to parse in background associations, either comma-separated or as a file with one category per line (lines starting with # are ignored). [possible values: all, experimental, phylogenetic, computational, author, curator, electronic]",
+        default_value = "all"
+    )]
+    evidence_categories: String,
+
+    #[arg(
+        short = 'p',
+        long = "propagate-counts",
+        help = "Propagates GO term annotations upwards the Ontology graph (from child to parent). [Must be specified to propagate the annotations]",
+        default_value_t = false
+    )]
+    propagate_counts: bool,
+
+    #[arg(
+        long = "min-size",
+        value_name = "COUNT",
+        help = "Minimum number of ranked proteins a GO Term must have to be tested.",
+        default_value_t = 15
+    )]
+    min_set_size: usize,
+
+    #[arg(
+        long = "max-size",
+        value_name = "COUNT",
+        help = "Maximum number of ranked proteins a GO Term may have to be tested.",
+        default_value_t = 500
+    )]
+    max_set_size: usize,
+
+    #[arg(
+        long = "permutations",
+        value_name = "NUMBER",
+        help = "Number of permutations used to build the null distribution of enrichment scores.",
+        default_value_t = 1000
+    )]
+    permutations: u32,
+
+    #[arg(
+        long = "seed",
+        value_name = "NUMBER",
+        help = "Seed for the GSEA permutations. A random seed is used and printed if not specified.",
+    )]
+    seed: Option<u64>,
+
+    #[arg(
+        short = 'd',
+        long = "dir",
+        value_name = "RESULTS_DIR",
+        help = "Directory to write results.",
+        default_value = "./",
+    )]
+    output_dir: String,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+
+    let cli_args: CliArgs = CliArgs::parse();
+
+    let default_obo_path = default_asset_path("go.obo");
+    let default_background_path = default_asset_path("background_pop");
+
+    let obo_file = cli_args.obo_file.unwrap_or(default_obo_path);
+    let background_dir = cli_args.background_dir.unwrap_or(default_background_path);
+
+    create_dir_all(&cli_args.output_dir)?;
+
+    let taxon_ids: FxHashSet<TaxonID> = collect_ranked_taxon_ids(&cli_args.ranked_proteins).map_err(|e| e.to_string())?;
+
+    println!("\nReading ontology information from: {}\n", &obo_file);
+
+    let obo_file_path = PathBuf::from(&obo_file);
+    let ontology = parse_obo_file(&obo_file_path)?;
+
+    println!("Reading background populations from: {}\n", &background_dir);
+
+    let categories = map_input_to_category(cli_args.evidence_categories.clone())?;
+
+    let background_population = match BackgroundPop::read_background_pop(
+        &taxon_ids,
+        &background_dir,
+        None,
+        &categories,
+        &FxHashSet::default()
+    )? {
+        Some(background_pop) => background_pop,
+        None => {
+            return Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "No background population data could be loaded\n"
+            )));
+        }
+    };
+
+    println!("Reading ranked proteins from: {}\n", cli_args.ranked_proteins.display());
+
+    let mut ranked_population = match StudyPop::from_ranked_csv_file(
+        &cli_args.ranked_proteins,
+        &background_population.protein_to_go
+    ).map_err(|e| e.to_string())? {
+        Some(ranked_pop) => ranked_pop,
+        None => {
+            return Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("No ranked proteins could be loaded from '{}'", cli_args.ranked_proteins.display())
+            )));
+        }
+    };
+
+    if cli_args.propagate_counts {
+        println!("Propagating annotations up the Ontology graph\n");
+
+        let ancestor_cache: GOAncestorCache = GOAncestorCache::from_ontology(&ontology)?;
+
+        ranked_population.propagate_counts(&taxon_ids, &ancestor_cache);
+    }
+
+    let seed = match cli_args.seed {
+        Some(seed) => seed,
+        None => {
+            let seed = rand::random::<u64>();
+            eprintln!("Using permutation seed {} (pass --seed {} to reproduce)", seed, seed);
+            seed
+        }
+    };
+
+    let empty_terms = GOTermToProteinSet::default();
+
+    for &taxon_id in &taxon_ids {
+        let Some(protein_scores) = ranked_population.protein_scores.get(&taxon_id) else { continue };
+        let go_term_to_proteins = ranked_population.go_term_to_protein_set
+            .get(&taxon_id)
+            .unwrap_or(&empty_terms);
+
+        println!("Running GSEA for taxon {} with {} permutations\n", taxon_id, cli_args.permutations);
+
+        let gsea_results = gsea(
+            protein_scores,
+            go_term_to_proteins,
+            cli_args.min_set_size,
+            cli_args.max_set_size,
+            cli_args.permutations,
+            seed
+        );
+
+        println!("Tested {} GO terms for taxon {}\n", gsea_results.len(), taxon_id);
+
+        let output_file = PathBuf::from(&cli_args.output_dir)
+            .join(format!("gsea_results_taxon_{}.tsv", taxon_id));
+
+        write_gsea_results(
+            &gsea_results,
+            &ontology,
+            &output_file
+        )?;
+    }
+
+    println!("Finished analysis\n");
+    Ok(())
+}
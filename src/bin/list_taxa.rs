@@ -0,0 +1,81 @@
+use clap::{Parser, ValueEnum};
+use std::error::Error;
+use serde_json::json;
+
+use TaxaGO::parsers::background_parser::*;
+use TaxaGO::analysis::handle_lineage::*;
+use TaxaGO::utils::assets::default_asset_path;
+
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq)]
+enum ListFormat {
+    Tsv,
+    Json,
+}
+
+#[derive(Parser, Debug)]
+#[command(name = "taxago-list-taxa")]
+struct CliArgs {
+    #[arg(
+        short = 'b',
+        long = "background",
+        value_name = "BACKGROUND_DIR",
+        help = "Directory (or .tar.gz archive) containing background populations.",
+    )]
+    background_dir: Option<String>,
+
+    #[arg(
+        short = 'l',
+        long = "lineage",
+        value_name = "LINEAGE_FILE",
+        help = "Lineage file used to look up the scientific name of each taxon.",
+    )]
+    lineage_file: Option<String>,
+
+    #[arg(
+        short = 'f',
+        long = "format",
+        value_enum,
+        help = "Output format.",
+        default_value_t = ListFormat::Tsv
+    )]
+    format: ListFormat,
+}
+
+const UNKNOWN_NAME: &str = "UNKNOWN";
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let cli_args: CliArgs = CliArgs::parse();
+
+    let background_dir = cli_args.background_dir.unwrap_or(default_asset_path("background_pop"));
+    let lineage_file = cli_args.lineage_file.unwrap_or(default_asset_path("lineage.txt"));
+
+    let species_names = taxid_to_species(&lineage_file)?;
+    let protein_counts = background_protein_counts(&background_dir)?;
+
+    let mut taxon_ids: Vec<TaxonID> = protein_counts.keys().copied().collect();
+    taxon_ids.sort_unstable();
+
+    let name = |taxon_id: &TaxonID| species_names.get(taxon_id).map_or(UNKNOWN_NAME, String::as_str);
+
+    match cli_args.format {
+        ListFormat::Tsv => {
+            println!("taxon_id\tscientific_name\tn_proteins");
+            for taxon_id in &taxon_ids {
+                println!("{}\t{}\t{}", taxon_id, name(taxon_id), protein_counts[taxon_id]);
+            }
+        }
+        ListFormat::Json => {
+            let taxa: Vec<_> = taxon_ids
+                .iter()
+                .map(|taxon_id| json!({
+                    "taxon_id": taxon_id,
+                    "scientific_name": name(taxon_id),
+                    "n_proteins": protein_counts[taxon_id],
+                }))
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&taxa)?);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,143 @@
+use clap::Parser;
+use rustc_hash::FxHashSet;
+use std::fs::create_dir_all;
+use std::path::PathBuf;
+use std::error::Error;
+
+use TaxaGO::parsers::{
+    background_parser::*, gmt_parser::*, obo_parser::*
+};
+use TaxaGO::analysis::{
+    count_propagation::*,
+    pathway_overlap::*,
+    results_summary::read_single_taxon_results
+};
+use TaxaGO::utils::assets::default_asset_path;
+
+#[derive(Parser, Debug)]
+#[command(name = "taxago-pathway-overlap")]
+struct CliArgs {
+    #[arg(
+        short = 'o',
+        long = "obo",
+        value_name = "OBO_FILE",
+        help = "Path to the Gene Ontology file in OBO format.",
+    )]
+    obo_file: Option<String>,
+
+    #[arg(
+        long = "results",
+        value_name = "FILE",
+        help = "Single taxon *_GOEA_results.{txt,csv,json} file with the significant GO terms.",
+    )]
+    results_file: PathBuf,
+
+    #[arg(
+        long = "pathway-file",
+        value_name = "GMT_FILE",
+        help = "Pathways in GMT format (name, description and members, tab-separated). Members must use the same protein IDs as the background population.",
+    )]
+    pathway_file: PathBuf,
+
+    #[arg(
+        long = "taxon",
+        value_name = "TAXON_ID",
+        help = "NCBI taxon ID of the species the results belong to.",
+    )]
+    taxon_id: TaxonID,
+
+    #[arg(
+        short = 'b',
+        long = "background",
+        value_name = "BACKGROUND_DIR",
+        help = "Directory containing background populations.",
+    )]
+    background_dir: Option<String>,
+
+    #[arg(
+        short = 'e',
+        long = "evidence",
+        value_name = "CATEGORIES",
+        help = "Evidence code categories to parse in background associations, either comma-separated or as a file with one category per line (lines starting with # are ignored). [possible values: all, experimental, phylogenetic, computational, author, curator, electronic]",
+        default_value = "all"
+    )]
+    evidence_categories: String,
+
+    #[arg(
+        short = 'p',
+        long = "propagate-counts",
+        help = "Propagates background annotations upwards the Ontology graph. Use it when the results were obtained with count propagation.",
+        default_value_t = false
+    )]
+    propagate_counts: bool,
+
+    #[arg(
+        long = "output",
+        value_name = "FILE",
+        help = "TSV file to write the pathway overlap to.",
+        default_value = "./results/pathway_overlap.tsv",
+    )]
+    output_file: PathBuf,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+
+    let cli_args: CliArgs = CliArgs::parse();
+
+    let obo_file = cli_args.obo_file.unwrap_or(default_asset_path("go.obo"));
+    let background_dir = cli_args.background_dir.unwrap_or(default_asset_path("background_pop"));
+
+    if let Some(output_dir) = cli_args.output_file.parent() {
+        create_dir_all(output_dir)?;
+    }
+
+    println!("\nReading ontology information from: {}\n", &obo_file);
+    let ontology = parse_obo_file(&PathBuf::from(&obo_file))?;
+
+    println!("Reading pathways from: {}\n", cli_args.pathway_file.display());
+    let gene_sets = parse_gmt_file(&cli_args.pathway_file)?;
+    let pathway_source = cli_args.pathway_file
+        .file_stem()
+        .map_or(String::new(), |stem| stem.to_string_lossy().into_owned());
+
+    let go_terms: Vec<GOTermID> = read_single_taxon_results(&cli_args.results_file)?
+        .into_iter()
+        .map(|(go_term, _, _)| go_term)
+        .collect();
+
+    println!("Reading background population from: {}\n", &background_dir);
+
+    let taxon_ids: FxHashSet<TaxonID> = FxHashSet::from_iter([cli_args.taxon_id]);
+    let categories = map_input_to_category(cli_args.evidence_categories.clone())?;
+    let mut background_population = BackgroundPop::read_background_pop(
+        &taxon_ids,
+        &background_dir,
+        None,
+        &categories,
+        &FxHashSet::default()
+    )?.ok_or("No background population data could be loaded")?;
+
+    if cli_args.propagate_counts {
+        println!("Propagating background annotations up the Ontology graph\n");
+
+        let ancestor_cache = GOAncestorCache::from_ontology(&ontology)?;
+
+        background_population.propagate_counts(&taxon_ids, &ancestor_cache);
+    }
+
+    let (Some(term_proteins), Some(universe)) = (
+        background_population.go_term_to_protein_set.get(&cli_args.taxon_id),
+        background_population.protein_to_go.get(&cli_args.taxon_id)
+    ) else {
+        return Err(format!("No background annotations found for taxon {}", cli_args.taxon_id).into());
+    };
+
+    println!("Comparing {} GO terms with {} pathways\n", go_terms.len(), gene_sets.len());
+
+    let overlaps = pathway_overlap(&go_terms, term_proteins, &gene_sets, universe);
+
+    write_pathway_overlap(&overlaps, &ontology, &pathway_source, &cli_args.output_file)?;
+
+    println!("Finished analysis\n");
+    Ok(())
+}
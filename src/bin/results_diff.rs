@@ -0,0 +1,74 @@
+use clap::Parser;
+use std::error::Error;
+use std::path::PathBuf;
+
+use TaxaGO::parsers::obo_parser::*;
+use TaxaGO::utils::diff_results::*;
+use TaxaGO::utils::assets::default_asset_path;
+
+#[derive(Parser, Debug)]
+#[command(name = "taxago-results-diff")]
+struct CliArgs {
+    #[arg(
+        short = 'o',
+        long = "obo",
+        value_name = "OBO_FILE",
+        help = "Path to the Gene Ontology file in OBO format.",
+    )]
+    obo_file: Option<String>,
+
+    #[arg(
+        long = "before",
+        value_name = "RESULTS_DIR",
+        help = "Results directory of the first run, containing single_taxon_results/.",
+    )]
+    before_dir: PathBuf,
+
+    #[arg(
+        long = "after",
+        value_name = "RESULTS_DIR",
+        help = "Results directory of the second run, containing single_taxon_results/.",
+    )]
+    after_dir: PathBuf,
+
+    #[arg(
+        long = "min-lor-change",
+        value_name = "NUMBER",
+        help = "Minimum absolute change in log(Odds Ratio) for a term significant in both runs to count as increased or decreased instead of stable.",
+        default_value_t = 0.0
+    )]
+    min_lor_change: f64,
+
+    #[arg(
+        long = "output",
+        value_name = "FILE",
+        help = "TSV file to write the comparison to.",
+        default_value = "results_diff.tsv",
+    )]
+    output_file: PathBuf,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+
+    let cli_args: CliArgs = CliArgs::parse();
+
+    let obo_file = cli_args.obo_file.unwrap_or(default_asset_path("go.obo"));
+
+    println!("\nReading ontology information from: {}\n", &obo_file);
+
+    let ontology = parse_obo_file(&PathBuf::from(&obo_file))?;
+
+    println!(
+        "Comparing results in '{}' and '{}'\n",
+        cli_args.before_dir.display(),
+        cli_args.after_dir.display()
+    );
+
+    let diffs = diff_result_dirs(&cli_args.before_dir, &cli_args.after_dir, cli_args.min_lor_change)?;
+
+    write_results_diff(&diffs, &ontology, &cli_args.output_file)?;
+    print_diff_summary(&diffs);
+
+    println!("Finished comparison\n");
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use clap::Parser;
+use std::error::Error;
+use std::path::PathBuf;
+
+use TaxaGO::parsers::obo_parser::*;
+use TaxaGO::analysis::results_summary::*;
+use TaxaGO::utils::assets::default_asset_path;
+
+#[derive(Parser, Debug)]
+#[command(name = "taxago-summary")]
+struct CliArgs {
+    #[arg(
+        short = 'o',
+        long = "obo",
+        value_name = "OBO_FILE",
+        help = "Path to the Gene Ontology file in OBO format.",
+    )]
+    obo_file: Option<String>,
+
+    #[arg(
+        short = 'd',
+        long = "dir",
+        value_name = "RESULTS_DIR",
+        help = "Directory containing the *_GOEA_results.{txt,csv,json} single taxon result files.",
+        default_value = "./results/single_taxon_results",
+    )]
+    results_dir: PathBuf,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+
+    let cli_args: CliArgs = CliArgs::parse();
+
+    let obo_file = cli_args.obo_file.unwrap_or(default_asset_path("go.obo"));
+
+    println!("\nReading ontology information from: {}\n", &obo_file);
+
+    let ontology = parse_obo_file(&PathBuf::from(&obo_file))?;
+
+    println!("Reading single taxon results from: {}\n", cli_args.results_dir.display());
+
+    let species_results = read_results_dir(&cli_args.results_dir)?;
+    if species_results.is_empty() {
+        return Err(Box::new(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("No *_GOEA_results.{{txt,csv,json}} files found in '{}'", cli_args.results_dir.display())
+        )));
+    }
+
+    println!("Summarizing results of {} species\n", species_results.len());
+
+    let summary = summarize_results(&species_results);
+
+    write_results_summary(
+        &summary,
+        &ontology,
+        &cli_args.results_dir.join("results_summary.tsv")
+    )?;
+
+    println!("Finished summary\n");
+    Ok(())
+}
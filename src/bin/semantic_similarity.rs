@@ -1,34 +1,15 @@
 use clap::Parser;
 use rustc_hash::{FxHashMap, FxHashSet};
-use std::env::var;
 use std::fs::create_dir_all;
 use std::path::PathBuf;
-use dirs::home_dir;
 use daggy::NodeIndex;
 use std::error::Error;
-use petgraph::algo::toposort;
 
 use TaxaGO::parsers::obo_parser::*;
 use TaxaGO::parsers::background_parser::*;
 use TaxaGO::utils::semantic_similarity::*;
-use TaxaGO::utils::common_ancestor::*;
 use TaxaGO::analysis::count_propagation::*;
-
-fn get_default_asset_path(filename: &str) -> String {
-    let cargo_home = var("CARGO_HOME")
-        .unwrap_or_else(|_| {
-            home_dir()
-                .expect("Could not determine home directory")
-                .join(".cargo")
-                .to_string_lossy()
-                .into_owned()
-        });
-    PathBuf::from(cargo_home)
-        .join("taxago_assets")
-        .join(filename)
-        .to_string_lossy()
-        .into_owned()
-}
+use TaxaGO::utils::assets::default_asset_path;
 
 #[derive(Parser, Debug)]
 #[command(name = "semantic-similarity")]
@@ -84,6 +65,14 @@ struct CliArgs {
     )]
     method: Method,
 
+    #[arg(
+        long = "zero-ic",
+        value_enum,
+        help = "Lin similarity returned when both terms have an Information Content of zero. With return-nan these pairs are written as NaN and left out of the heatmap clustering.",
+        default_value_t = ZeroIcBehavior::ReturnZero,
+    )]
+    zero_ic_behavior: ZeroIcBehavior,
+
     #[arg(
         short = 'p',
         long = "propagate-counts",
@@ -91,14 +80,22 @@ struct CliArgs {
         default_value_t = false
     )]
     propagate_counts: bool,
+
+    #[arg(
+        long = "max-terms",
+        value_name = "COUNT",
+        help = "When --terms is a TaxaGO results file, only keep its most significant GO terms. 0 keeps every term.",
+        default_value_t = 50
+    )]
+    max_terms: usize,
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
 
     let cli_args: CliArgs = CliArgs::parse();
     
-    let default_obo_path = get_default_asset_path("go.obo");
-    let default_background_path = get_default_asset_path("background_pop");
+    let default_obo_path = default_asset_path("go.obo");
+    let default_background_path = default_asset_path("background_pop");
     
     let obo_file = cli_args.obo_file.unwrap_or(default_obo_path);
     let background_dir = cli_args.background_dir.unwrap_or(default_background_path);
@@ -111,20 +108,6 @@ fn main() -> Result<(), Box<dyn Error>> {
     let ontology = parse_obo_file(&obo_file_path)?;
     let (ontology_graph, go_id_to_node_index) = build_ontology_graph(&ontology)?;
     
-    let node_index_to_go_id: FxHashMap<NodeIndex, u32> = go_id_to_node_index
-        .iter()
-        .map(|(&go_id, &node_idx)| (node_idx, go_id))
-        .collect();
-
-    let topo_result = toposort(&ontology_graph, None).unwrap();
-
-    let mut propagation_order: Vec<GOTermID> = topo_result
-        .iter()
-        .filter_map(|&node_idx| node_index_to_go_id.get(&node_idx).copied())
-        .collect();
-    
-    propagation_order.reverse();
-    
     println!("Reading background populations from: {}\n", &background_dir);
     
     let taxon_ids: FxHashSet<TaxonID> = cli_args.taxon_ids.split(',')
@@ -142,7 +125,9 @@ fn main() -> Result<(), Box<dyn Error>> {
     let mut background_population = match BackgroundPop::read_background_pop(
         &taxon_ids, 
         &background_dir,
-        &categories
+        None,
+        &categories,
+        &FxHashSet::default()
     )? {
         Some(background_pop) => {
             println!("Successfully loaded background population for {} taxa\n", &taxon_ids.len());
@@ -158,6 +143,11 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     if cli_args.propagate_counts {
         println!("Propagating counts up the Ontology graph\n");
+
+        let node_index_to_go_id: FxHashMap<NodeIndex, u32> = go_id_to_node_index
+            .iter()
+            .map(|(&go_id, &node_idx)| (node_idx, go_id))
+            .collect();
         
         let ancestor_cache: GOAncestorCache = GOAncestorCache::new(
             &ontology_graph, 
@@ -171,55 +161,49 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let go_term_count: FxHashMap<u32, FxHashMap<u32, usize>> = background_population.go_term_count;
 
-    let go_terms = process_go_terms_input(&cli_args.go_terms_input)?;
+    let go_terms = process_go_terms_input(&cli_args.go_terms_input, cli_args.max_terms)?;
 
     println!("Calculating Information Content (IC) for {} GO terms\n", go_terms.len());
+    println!("Finding Most Informative Common Ancestor (MICA)\n");
 
-    let mut expanded_terms = go_terms.clone();
-    for &term in go_terms.iter() {
-        if let Some(&node_idx) = go_id_to_node_index.get(&term) {
-            let ancestry_path = collect_ancestry_path(&ontology_graph, node_idx);
-            for (idx, _) in ancestry_path {
-                let ancestor_id = node_index_to_go_id[&idx];
-                expanded_terms.insert(ancestor_id);
-            }
-        }
-    }
-
-    let ic_results = calculate_information_content(
+    let similarity_results = run_semantic_similarity(
+        &go_terms,
+        &taxon_ids,
         &go_term_count,
-        &expanded_terms,
-        &go_id_to_node_index
-    );
+        &ontology_graph,
+        &go_id_to_node_index,
+        cli_args.method,
+        cli_args.zero_ic_behavior
+    )?;
 
-    println!("Finding Most Informative Common Ancestor (MICA)\n");
-    
-    for &taxon_id in &taxon_ids {        
+    let mut sorted_taxon_ids: Vec<TaxonID> = similarity_results.keys().copied().collect();
+    sorted_taxon_ids.sort_unstable();
+
+    for taxon_id in sorted_taxon_ids {
         println!("Processing for Taxon ID: {}\n", taxon_id);
-    
-        let term_pairs = generate_term_pairs(
-            &go_terms,
-            taxon_id,
-            &ic_results,
-            &ontology_graph,
-            &go_id_to_node_index,
-            &node_index_to_go_id,
-            &propagation_order,
-            cli_args.method
-        );
-        
+        let term_pairs = &similarity_results[&taxon_id];
+
         if term_pairs.is_empty() && !go_terms.is_empty() {
             println!("No term pairs generated for taxon {}. This might happen if terms are not found or IC values are missing for IC-based methods.", taxon_id);
         }
 
         write_similarity_to_tsv(
-            &term_pairs, 
+            term_pairs, 
             &go_terms, 
             taxon_id, 
             cli_args.method, 
             &cli_args.output_dir
         )
         .map_err(|e| format!("Failed to write similarity TSV for taxon {}: {}", taxon_id, e))?;
+
+        write_similarity_heatmap_html(
+            term_pairs,
+            &go_terms,
+            taxon_id,
+            cli_args.method,
+            &cli_args.output_dir
+        )
+        .map_err(|e| format!("Failed to write similarity heatmap for taxon {}: {}", taxon_id, e))?;
     }
     
     println!("All semantic similarity calculations completed successfully!\n");
@@ -0,0 +1,63 @@
+use clap::Parser;
+use std::error::Error;
+use std::fs::{create_dir_all, write};
+use std::path::PathBuf;
+
+use TaxaGO::analysis::{
+    results_summary::*,
+    species_comparison::*
+};
+
+#[derive(Parser, Debug)]
+#[command(name = "taxago-compare")]
+struct CliArgs {
+    #[arg(
+        short = 'd',
+        long = "dir",
+        value_name = "RESULTS_DIR",
+        help = "Directory containing the *_GOEA_results.{txt,csv,json} single taxon result files.",
+        default_value = "./results/single_taxon_results",
+    )]
+    results_dir: PathBuf,
+
+    #[arg(
+        long = "output-dir",
+        value_name = "OUTPUT_DIR",
+        help = "Directory to write the correlation matrix and Newick dendrogram.",
+        default_value = "./results",
+    )]
+    output_dir: PathBuf,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+
+    let cli_args: CliArgs = CliArgs::parse();
+
+    create_dir_all(&cli_args.output_dir)?;
+
+    println!("\nReading single taxon results from: {}\n", cli_args.results_dir.display());
+
+    let species_results = read_results_dir(&cli_args.results_dir)?;
+    if species_results.len() < 2 {
+        return Err(Box::new(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("At least two *_GOEA_results.{{txt,csv,json}} files are required in '{}'", cli_args.results_dir.display())
+        )));
+    }
+
+    println!("Correlating log(Odds Ratio) profiles of {} species\n", species_results.len());
+
+    let correlation = species_correlation(&species_results);
+
+    write_species_correlation(
+        &correlation,
+        &cli_args.output_dir.join("species_correlation.tsv")
+    )?;
+
+    let newick_file = cli_args.output_dir.join("species_correlation.nwk");
+    println!("Writing species dendrogram to: {}\n", newick_file.display());
+    write(&newick_file, format!("{}\n", correlation_newick(&correlation)))?;
+
+    println!("Finished comparison\n");
+    Ok(())
+}
@@ -5,12 +5,15 @@ static GLOBAL: MiMalloc = MiMalloc;
 
 use clap::{Parser, ValueEnum, ArgGroup};
 use std::fs;
-use std::env::var;
+use std::io::{self, BufWriter, Write};
 use std::process::ExitCode;
+use std::panic::{self, AssertUnwindSafe};
 use rustc_hash::{FxHashMap, FxHashSet};
 use daggy::NodeIndex;
 use std::path::PathBuf;
-use dirs::home_dir;
+use rand::{SeedableRng, rngs::SmallRng};
+use polars::prelude::DataFrame;
+use serde::{Serialize, Serializer};
 
 use TaxaGO::parsers::{
     background_parser::*, obo_parser::*, study_parser::*
@@ -23,23 +26,20 @@ use TaxaGO::analysis::{
     result_combination::*,
     count_propagation::*,
     phylogenetic_meta_analysis::*,
-    enrichment_plots::*
+    enrichment_plots::*,
+    term_clustering::*,
+    network_centrality::*,
+    plot_backend::*,
+    taxon_enrichment::*
 };
+use TaxaGO::progress;
+use TaxaGO::utils::progress::set_progress_to_stderr;
+use TaxaGO::utils::assets::default_asset_path;
 
-fn get_default_asset_path(filename: &str) -> String {
-    let cargo_home = var("CARGO_HOME")
-        .unwrap_or_else(|_| {
-            home_dir()
-                .expect("Could not determine home directory")
-                .join(".cargo")
-                .to_string_lossy()
-                .into_owned()
-        });
-    PathBuf::from(cargo_home)
-        .join("taxago_assets")
-        .join(filename)
-        .to_string_lossy()
-        .into_owned()
+// Enum arguments are recorded in the reproducibility report under the same names used on the command line.
+fn serialize_value_enum<T: ValueEnum, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    let possible_value = value.to_possible_value();
+    serializer.serialize_str(possible_value.as_ref().map_or("", |possible_value| possible_value.get_name()))
 }
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
@@ -50,7 +50,7 @@ enum PropagationMethod {
     Weight
 }
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone, Serialize)]
 #[command(name = "taxago", about, version, author)]
 #[command(group(
     ArgGroup::new("meta_analysis")
@@ -66,31 +66,63 @@ struct CliArgs {
     )]
     obo_file: Option<String>,
     
+    #[arg(
+        long = "max-missing-references",
+        value_name = "COUNT",
+        help = "Maximum number of relationships in the OBO file that may point to GO terms missing from it before the file is rejected as malformed.",
+        default_value_t = 100
+    )]
+    max_missing_references: usize,
+    
     #[arg(
         short = 's',
         long = "study",
         value_name = "FILE_OR_DIR",
-        help = "Directory containing study population for each taxon in FASTA format or CSV file with the study population for each species.",
-        required = true
+        help = "Directory containing study population for each taxon in FASTA format, CSV file with the study population for each species, UniProt TSV file, or a comma-separated list of such files.",
+        required_unless_present = "ranked_study",
+        conflicts_with = "ranked_study"
     )]
-    study_pop: String,
+    study_pop: Option<String>,
+
+    #[arg(
+        long = "ranked-study",
+        value_name = "FILE",
+        help = "CSV file with 'taxon_id', 'protein_id' and 'score' columns ranking the study population. [Required by the kolmogorov-smirnov test]",
+    )]
+    ranked_study: Option<String>,
     
     #[arg(
         short = 'b',
         long = "background",
+        alias = "background-tarball",
         value_name = "DIRECTORY",
-        help = "Directory containing background populations.",
+        help = "Directory or .tar.gz archive containing background populations.",
     )]
     background_pop: Option<String>,
 
+    #[arg(
+        long = "extra-background",
+        value_name = "DIRECTORY",
+        help = "Additional directory containing background populations. Can be given multiple times; taxa found in several directories use the last one.",
+    )]
+    extra_background: Vec<PathBuf>,
+
     #[arg(
         short = 'e',
         long = "evidence",
         value_name = "CATEGORY",
-        help = "Evidence code categories to parse in background associations. [possible values: all, experimental, phylogenetic, computational, author, curator, electronic]",
+        help = "Evidence code categories to parse in background associations, either comma-separated or as a file with one category per line (lines starting with # are ignored). [possible values: all, experimental, phylogenetic, computational, author, curator, electronic]",
         default_value = "all"
     )]
     evidence_categories: String,   
+
+    #[arg(
+        long = "evidence-exclude",
+        value_name = "CODES",
+        help = "Comma-separated GO evidence codes to drop from background associations, applied on top of --evidence [e.g., IEA,ND].",
+        default_value = ""
+    )]
+    evidence_exclude: String,
     
     #[arg(
         short = 'd',
@@ -108,17 +140,42 @@ struct CliArgs {
         help = "Method to propagate GO term counts up the Ontology graph.",
         default_value_t = PropagationMethod::None
     )]
+    #[serde(serialize_with = "serialize_value_enum")]
     propagate_counts: PropagationMethod,
 
     #[arg(
         short = 't',
         long = "test",
         value_enum,
-        help = "Statistical test to use.",
+        help = "Statistical test to use. kolmogorov-smirnov is one-sided and only detects GO terms whose proteins have higher scores than the rest of the ranked study population.",
         default_value_t = StatisticalTest::Fishers
     )]
+    #[serde(serialize_with = "serialize_value_enum")]
     statistical_test: StatisticalTest,
 
+    #[arg(
+        long = "two-tailed",
+        help = "Use the two-sided Fisher's exact test, detecting both enriched and depleted GO terms. Same as -t fishers-two-tailed.",
+        default_value_t = false
+    )]
+    two_tailed: bool,
+
+    #[arg(
+        long = "bayes-alpha",
+        value_name = "ALPHA",
+        help = "Alpha hyperparameter of the Beta prior used by the beta-binomial test.",
+        default_value_t = 1.0
+    )]
+    bayes_alpha: f64,
+
+    #[arg(
+        long = "bayes-beta",
+        value_name = "BETA",
+        help = "Beta hyperparameter of the Beta prior used by the beta-binomial test.",
+        default_value_t = 1.0
+    )]
+    bayes_beta: f64,
+
     #[arg(
         short = 'm',
         long = "min-prot",
@@ -127,16 +184,82 @@ struct CliArgs {
         default_value_t = 5
     )]
     min_protein_count: usize,
+
+    #[arg(
+        long = "min-background-count",
+        value_name = "COUNT",
+        help = "Minimum background protein count a GO Term must have to be processed.",
+        default_value_t = 2
+    )]
+    min_background_count: usize,
     
     #[arg(
         short = 'r',
         long = "min-score",
         value_name = "SCORE",
-        help = "Minimum log(odds ratio) a GO Term must have to be included in results.",
+        help = "Minimum log(odds ratio) a GO Term must have to be included in results. With the beta-binomial test, this is the minimum log Bayes factor.",
         default_value_t = 0.2
     )]
     min_odds_ratio: f64,
 
+    #[arg(
+        long = "cluster-threshold",
+        value_name = "THRESHOLD",
+        num_args = 0..=1,
+        default_missing_value = "0.75",
+        help = "Collapse enriched GO terms whose proteins overlap with a Jaccard index >= THRESHOLD (complete linkage) to their most significant term. Must be greater than 0 and at most 1. Uses 0.75 if no value is given."
+    )]
+    cluster_threshold: Option<f64>,
+
+    #[arg(
+        long = "network-jaccard-threshold",
+        value_name = "THRESHOLD",
+        help = "Minimum Jaccard index between the proteins of two GO terms for an edge in the network plots. Must be greater than 0 and at most 1.",
+        default_value_t = 0.25
+    )]
+    network_jaccard_threshold: f64,
+
+    #[arg(
+        long = "network-color",
+        value_enum,
+        help = "Node property used to colour the network plots.",
+        default_value_t = NetworkColor::LogOddsRatio
+    )]
+    #[serde(serialize_with = "serialize_value_enum")]
+    network_color: NetworkColor,
+
+    #[arg(
+        long = "network-fr-iterations",
+        value_name = "N",
+        help = "Number of Fruchterman-Reingold iterations used to lay out the network plots.",
+        default_value_t = 5000
+    )]
+    network_fr_iterations: usize,
+
+    #[arg(
+        long = "network-fr-scale",
+        value_name = "SCALE",
+        help = "Scale of the Fruchterman-Reingold layout. Larger values spread out the nodes of large networks.",
+        default_value_t = 50.0
+    )]
+    network_fr_scale: f32,
+
+    #[arg(
+        long = "network-fr-dt",
+        value_name = "DT",
+        help = "Time step of the Fruchterman-Reingold layout. Must be between 0 and 1.",
+        default_value_t = 0.02
+    )]
+    network_fr_dt: f32,
+
+    #[arg(
+        long = "network-fr-cooloff",
+        value_name = "FACTOR",
+        help = "Factor by which the Fruchterman-Reingold step size shrinks after each iteration. Must be between 0 and 1.",
+        default_value_t = 0.975
+    )]
+    network_fr_cooloff: f32,
+
     #[arg(
         short = 'a',
         long = "alpha",
@@ -146,6 +269,13 @@ struct CliArgs {
     )]
     significance_threshold: f64,
 
+    #[arg(
+        long = "elim-threshold",
+        value_name = "THRESHOLD",
+        help = "Raw p-value threshold at which a GO term removes its proteins from its ancestors in the elim algorithm. Defaults to --alpha.",
+    )]
+    elim_threshold: Option<f64>,
+
     #[arg(
         short = 'c',
         long = "correction-method",
@@ -153,6 +283,7 @@ struct CliArgs {
         help = "Method to adjust p-values for multiple test correction.",
         default_value_t = AdjustmentMethod::BenjaminiHochberg
     )]
+    #[serde(serialize_with = "serialize_value_enum")]
     correction_method: AdjustmentMethod,
 
     #[arg(
@@ -162,12 +293,20 @@ struct CliArgs {
         help = "Combine results based on the specified taxonomic level.",
     )]
     combine_results: Option<String>,
+
+    #[arg(
+        long = "taxonomy-levels",
+        value_name = "TAXONOMIC_LEVELS",
+        help = "Comma-separated taxonomic levels to combine results at in a single run [e.g., family,order,class]. Each level is written to combined_taxonomy_results_{level}/.",
+        conflicts_with = "combine_results",
+    )]
+    taxonomy_levels: Option<String>,
     
     #[arg(
         short = 'l',
         long = "lineage-file",
         value_name = "FILE",
-        help = "Path to the taxonomic lineage information file.",
+        help = "Path to a custom taxonomic lineage file. Tab-separated with a header row and the columns: Taxon ID, Species, Genus, Family, Order, Class, Phylum, Kingdom, Superkingdom.",
     )]
     lineage_file: Option<String>,
 
@@ -179,6 +318,22 @@ struct CliArgs {
     )]
     lineage_percentage: f64,
 
+    #[arg(
+        long = "min-species-percentage",
+        value_name = "PERCENTAGE",
+        help = "Only report a combined taxonomy GO term if it is significant (after multiple testing correction) in at least this percentage (0-100) of the species in its taxonomic group. Applied after the combined p-values are corrected, unlike --lineage-percentage, which decides which species results enter the combination.",
+        default_value_t = 0.0
+    )]
+    min_species_percentage: f64,
+
+    #[arg(
+        long = "min-species-count",
+        value_name = "COUNT",
+        help = "Minimum number of species in a taxonomic level that must have results for a GO term before it is combined. Applied together with --lineage-percentage.",
+        default_value_t = 1
+    )]
+    min_species_count: usize,
+
     #[arg(
         short = 'v',
         long = "vcv-matrix",
@@ -187,6 +342,15 @@ struct CliArgs {
     )]
     vcv_matrix: Option<PathBuf>,
 
+    #[arg(
+        long = "combination-method",
+        value_enum,
+        help = "Method to combine single taxon results within a taxonomic level.",
+        default_value_t = CombinationMethod::Phylogenetic
+    )]
+    #[serde(serialize_with = "serialize_value_enum")]
+    combination_method: CombinationMethod,
+
     #[arg(
         long = "permutations",
         value_name = "COUNT",
@@ -195,62 +359,453 @@ struct CliArgs {
     )]
     permutations: u32,
 
+    #[arg(
+        long = "permutation-seed",
+        value_name = "NUMBER",
+        help = "Seed for the permutations of the phylogenetic meta-analysis. A random seed is used and printed if not specified.",
+    )]
+    permutation_seed: Option<u64>,
+
     #[arg(
         long = "cores",
         value_name = "NUMBER",
-        help = "Number of cores to use for the analysis. Uses all available by default.",
+        help = "Number of cores to use for the analysis. 0 uses all available cores. Values above twice the available cores are capped unless --force-cores is given.",
         default_value_t = num_cpus::get()
     )]
     num_cores: usize,
 
+    #[arg(
+        long = "force-cores",
+        help = "Use the number of cores given by --cores even if it exceeds twice the available cores."
+    )]
+    force_cores: bool,
+
+    #[arg(
+        long = "verbose",
+        help = "Print a per-taxon summary of the enrichment statistics to stderr."
+    )]
+    verbose: bool,
+
+    #[arg(
+        long = "write-insignificant-results",
+        help = "Also write every tested GO term for each taxon, with its adjusted p-value and whether it is significant."
+    )]
+    write_insignificant_results: bool,
+
+    #[arg(
+        long = "jsonl-stream",
+        help = "Also print each significant GO term result as a JSON object per line (JSONL) to stdout. Progress messages are printed to stderr instead."
+    )]
+    jsonl_stream: bool,
+
+    #[arg(
+        long = "save-raw-counts",
+        help = "Write the contingency tables of every tested GO term for each taxon, before multiple testing correction."
+    )]
+    save_raw_counts: bool,
+
+    #[arg(
+        long = "no-cleanup",
+        help = "Keep the results of a previous run. By default, single_taxon_results/, combined_taxonomy_results/ and progress.json are removed from the output directory before the analysis starts."
+    )]
+    no_cleanup: bool,
+
+    #[arg(
+        long = "max-terms-per-namespace",
+        value_name = "COUNT",
+        help = "Maximum number of the most significant GO terms written and plotted per namespace for each taxon. 0 writes every significant term.",
+        default_value_t = 0
+    )]
+    max_terms_per_namespace: usize,
+
+    #[arg(
+        long = "background-subsample",
+        value_name = "COUNT",
+        help = "Randomly sample this many background proteins per taxon before the enrichment analysis, always keeping the study proteins. Must be at least the largest study population size. 0 uses the full background.",
+        default_value_t = 0
+    )]
+    background_subsample: usize,
+
+    #[arg(
+        long = "subsample-replicates",
+        value_name = "NUMBER",
+        help = "Number of background subsamples to analyze. The median p-value across replicates is reported.",
+        default_value_t = 1
+    )]
+    subsample_replicates: usize,
+
+    #[arg(
+        long = "seed",
+        value_name = "NUMBER",
+        help = "Seed for the random background subsampling.",
+        default_value_t = 42
+    )]
+    seed: u64,
+
+    #[arg(
+        short = 'f',
+        long = "output-format",
+        value_enum,
+        help = "Format of the enrichment result files.",
+        default_value_t = OutputFormat::Tsv
+    )]
+    #[serde(serialize_with = "serialize_value_enum")]
+    output_format: OutputFormat,
+
     #[arg(
         long = "save-plots",
         help = "If specified, TaxaGO will save the enrichment plots.",
         value_enum,
         default_value_t = PlotType::Interactive
     )]
+    #[serde(serialize_with = "serialize_value_enum")]
     save_plots: PlotType,
+
+    #[arg(
+        long = "plot-backend",
+        value_enum,
+        help = "Library used to draw the bar, bubble and network plots. vega-lite writes Vega-Lite specifications (.vl.json) instead of HTML/SVG files.",
+        default_value_t = PlotBackendType::Plotly
+    )]
+    #[serde(serialize_with = "serialize_value_enum")]
+    plot_backend: PlotBackendType,
 }
 
-fn main() -> ExitCode{
-    let cli_args: CliArgs = CliArgs::parse();
+fn fr_layout_config(cli_args: &CliArgs) -> FrLayoutConfig {
+    FrLayoutConfig {
+        iterations: cli_args.network_fr_iterations,
+        scale: cli_args.network_fr_scale,
+        dt: cli_args.network_fr_dt,
+        cooloff_factor: cli_args.network_fr_cooloff,
+    }
+}
+
+// Runs the combination of single taxon results at one taxonomic level and writes its results, clusters and plots to `results_dir`.
+#[allow(clippy::too_many_arguments)]
+fn combine_taxonomy_level(
+    cli_args: &CliArgs,
+    level_to_combine: &String,
+    results_dir: &PathBuf,
+    lineage: &FxHashMap<TaxonID, Vec<String>>,
+    taxon_ids: &FxHashSet<TaxonID>,
+    enrichment_results: &FxHashMap<TaxonID, FxHashMap<GOTermID, GOTermResults>>,
+    significant_species_terms: &FxHashMap<TaxonID, FxHashSet<GOTermID>>,
+    ontology: &OboMap,
+    study_population: &StudyPop,
+    vcv_matrix: &DataFrame,
+    permutation_seed: u64,
+) -> Result<(), String> {
+    progress!("Grouping species based on {}\n", level_to_combine);
     
-    let default_obo_path = get_default_asset_path("go.obo");
-    let default_background_path = get_default_asset_path("background_pop");
-    let default_lineage_path = get_default_asset_path("lineage.txt");
-    let lineage_file = cli_args.lineage_file.unwrap_or(default_lineage_path);
+    let grouped_species = taxid_to_level(
+        enrichment_results,
+        lineage,
+        level_to_combine
+    );
+
+    let lineage_organized_results= group_results_by_taxonomy(
+        &grouped_species, 
+        enrichment_results, 
+        cli_args.lineage_percentage,
+        cli_args.min_species_count
+    );
+
+    let phylogenetic_results = match cli_args.combination_method {
+        CombinationMethod::Phylogenetic => {
+            progress!("Performing phylogenetic meta-analysis with {} permutations", &cli_args.permutations);
+            phylogenetic_meta_analysis(
+                taxon_ids,
+                lineage_organized_results, 
+                vcv_matrix.clone(),
+                cli_args.permutations,
+                permutation_seed
+            )
+        },
+        CombinationMethod::Stouffer => {
+            progress!("Combining p-values with the weighted Stouffer Z-score method\n");
+            stouffer_combination(
+                taxon_ids,
+                lineage_organized_results,
+                vcv_matrix.clone()
+            )
+        },
+        CombinationMethod::Fishers => {
+            if cli_args.vcv_matrix.is_some() {
+                eprintln!("Warning: Fisher's method assumes independent p-values and ignores the phylogenetic relationships in the provided VCV matrix\n");
+            }
+            progress!("Combining p-values with Fisher's method\n");
+            fishers_combination(lineage_organized_results)
+        }
+    };
+
+    let mut significant_taxonomy_results = adjust_taxonomy_p_values(
+        &phylogenetic_results, 
+        cli_args.correction_method, 
+        Some(cli_args.significance_threshold),
+        cli_args.min_odds_ratio,
+        level_to_combine);
+
+    if cli_args.min_species_percentage > 0.0 {
+        filter_by_species_percentage(
+            &mut significant_taxonomy_results,
+            &grouped_species,
+            significant_species_terms,
+            cli_args.min_species_percentage
+        );
+    }
+
+    let taxonomy_protein_provider = ProteinDataProvider::Taxonomy {
+        species_data_by_id: &study_population.go_term_to_protein_set,
+        taxonomy_to_species_ids: &grouped_species,
+    };
+
+    if let Some(cluster_threshold) = cli_args.cluster_threshold {
+        let taxonomy_proteins: FxHashMap<String, GOTermToProteinSet> = significant_taxonomy_results
+            .iter()
+            .map(|(taxonomy, go_terms)| {
+                let go_term_ids: FxHashSet<GOTermID> = go_terms.keys().copied().collect();
+                (taxonomy.clone(), taxonomy_protein_provider.get_proteins_for_taxon(taxonomy, &go_term_ids))
+            })
+            .collect();
+
+        let (clustered_results, taxonomy_clusters) = cluster_results(
+            &significant_taxonomy_results,
+            &taxonomy_proteins,
+            ontology,
+            cluster_threshold
+        );
+        significant_taxonomy_results = clustered_results;
+
+        if let Err(e) = write_term_clusters(&taxonomy_clusters, ontology, results_dir) {
+            eprintln!("Error writing taxonomy GO term clusters: {}", e);
+        }
+    }
+    
+    write_taxonomy_results(
+        &significant_taxonomy_results,
+        ontology,
+        results_dir,
+        level_to_combine,
+        cli_args.output_format
+    ).map_err(|e| format!(
+        "Failed to write taxonomy results to directory '{}': {}",
+        results_dir.display(),
+        e
+    ))?;
+
+    if cli_args.save_plots != PlotType::None {
+        let taxonomy_plot_data = prepare_plot_data(
+            &significant_taxonomy_results, 
+            ontology,
+            0,
+            cli_args.statistical_test.effect_size_label());
+
+        let taxon_network_data = prepare_network_data(
+            &significant_taxonomy_results,
+            &taxonomy_protein_provider,
+            ontology,
+        );
+
+        let taxonomy_plots_subdir = results_dir.join("plots");
+        fs::create_dir_all(&taxonomy_plots_subdir).unwrap_or_else(|e| {
+            eprintln!("Error creating taxonomy plot directory: {}", e);
+        });
+
+        let backend = plot_backend(cli_args.plot_backend, cli_args.save_plots, cli_args.network_color, fr_layout_config(cli_args), cli_args.statistical_test.effect_size_label());
+
+        let _taxonomy_bar_plots = backend.render_bar(
+            &taxonomy_plot_data, 
+            &taxonomy_plots_subdir);
+
+        let _taxonomy_bubble_plots = backend.render_bubble(
+            &taxonomy_plot_data, 
+            &taxonomy_plots_subdir);
+
+        let _taxonomy_volcano_plots = volcano_plot(
+            &taxonomy_plot_data,
+            &taxonomy_plots_subdir,
+            cli_args.save_plots,
+            cli_args.significance_threshold,
+            cli_args.min_odds_ratio,
+            cli_args.statistical_test.effect_size_label());
+
+        let taxon_networks = build_networks(
+            &taxon_network_data,
+            &significant_taxonomy_results,
+            ontology,
+            cli_args.network_jaccard_threshold,
+            cli_args.statistical_test.effect_size_label()
+        );
+
+        let _taxon_network_plots = backend.render_network(
+            &taxon_networks, 
+            &taxonomy_plots_subdir);
+
+        if let Err(e) = write_network_centrality(
+            &taxon_networks,
+            results_dir) {
+            eprintln!("Error writing taxonomy network centrality: {}", e);
+        }
+
+        if let Err(e) = write_network_communities(
+            &taxon_networks,
+            results_dir) {
+            eprintln!("Error writing taxonomy network communities: {}", e);
+        }
+
+        if let Err(e) = export_network_graphml(
+            &taxon_networks,
+            &taxonomy_plots_subdir) {
+            eprintln!("Error writing taxonomy network GraphML files: {}", e);
+        }
+
+        if let Err(e) = export_network_cytoscape(
+            &taxon_networks,
+            &taxonomy_plots_subdir) {
+            eprintln!("Error writing taxonomy network Cytoscape.js files: {}", e);
+        }
+    }
+
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let cli_args: CliArgs = CliArgs::parse();
+
+    let report_args = cli_args.clone();
+    // A panic inside the analysis still gets a report, recorded as a failed run.
+    let exit_code = panic::catch_unwind(AssertUnwindSafe(|| run_analysis(cli_args)))
+        .unwrap_or(ExitCode::FAILURE);
+
+    let obo_file = report_args.obo_file.clone().unwrap_or(default_asset_path("go.obo"));
+    let background_pop = report_args.background_pop.clone().unwrap_or(default_asset_path("background_pop"));
+    let study_pop = report_args.study_pop.clone()
+        .or_else(|| report_args.ranked_study.clone())
+        .unwrap_or_default();
+
+    if let Err(e) = write_reproducibility_report(
+        &report_args,
+        &PathBuf::from(&obo_file),
+        &study_pop,
+        &PathBuf::from(&background_pop),
+        &report_args.output_dir,
+        exit_code == ExitCode::SUCCESS
+    ) {
+        eprintln!("Warning: Could not write reproducibility report: {}", e);
+    }
+
+    exit_code
+}
+
+fn run_analysis(mut cli_args: CliArgs) -> ExitCode {
+    set_progress_to_stderr(cli_args.jsonl_stream);
+
+    let default_obo_path = default_asset_path("go.obo");
+    let default_background_path = default_asset_path("background_pop");
+    let default_lineage_path = default_asset_path("lineage.txt");
+    if let Some(custom_lineage_file) = &cli_args.lineage_file {
+        if let Err(e) = validate_lineage_file(custom_lineage_file) {
+            eprintln!("\nError validating lineage file '{}':", custom_lineage_file);
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    }
+    let lineage_file = cli_args.lineage_file.clone().unwrap_or(default_lineage_path);
     
-    let obo_file = cli_args.obo_file.unwrap_or(default_obo_path);
-    let background_pop = cli_args.background_pop.unwrap_or(default_background_path);
+    let obo_file = cli_args.obo_file.clone().unwrap_or(default_obo_path);
+    let background_pop = cli_args.background_pop.clone().unwrap_or(default_background_path);
+
+    if cli_args.two_tailed {
+        match cli_args.statistical_test {
+            StatisticalTest::Fishers | StatisticalTest::FishersTwoTailed => {
+                cli_args.statistical_test = StatisticalTest::FishersTwoTailed;
+            },
+            _ => {
+                eprintln!("\nError: --two-tailed can only be used with Fisher's exact test.");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let is_ranked_test = matches!(cli_args.statistical_test, StatisticalTest::KolmogorovSmirnov);
+    if is_ranked_test != cli_args.ranked_study.is_some() {
+        eprintln!("\nError: The kolmogorov-smirnov test and --ranked-study must be used together.");
+        return ExitCode::FAILURE;
+    }
+    if matches!(cli_args.statistical_test, StatisticalTest::BetaBinomial)
+        && (cli_args.combine_results.is_some() || cli_args.taxonomy_levels.is_some()) {
+        eprintln!("\nError: The beta-binomial test reports Bayes factors and posterior probabilities, which cannot be combined across species. Use another test with --group-results or --taxonomy-levels.");
+        return ExitCode::FAILURE;
+    }
+    if cli_args.cluster_threshold.is_some_and(|threshold| !(threshold > 0.0 && threshold <= 1.0)) {
+        eprintln!("\nError: --cluster-threshold must be greater than 0 and at most 1.");
+        return ExitCode::FAILURE;
+    }
+    if !(cli_args.network_jaccard_threshold > 0.0 && cli_args.network_jaccard_threshold <= 1.0) {
+        eprintln!("\nError: --network-jaccard-threshold must be greater than 0 and at most 1.");
+        return ExitCode::FAILURE;
+    }
+    if cli_args.network_fr_iterations == 0 || !(cli_args.network_fr_scale > 0.0) {
+        eprintln!("\nError: --network-fr-iterations and --network-fr-scale must be greater than 0.");
+        return ExitCode::FAILURE;
+    }
+    if !(cli_args.network_fr_dt > 0.0 && cli_args.network_fr_dt < 1.0)
+        || !(cli_args.network_fr_cooloff > 0.0 && cli_args.network_fr_cooloff < 1.0) {
+        eprintln!("\nError: --network-fr-dt and --network-fr-cooloff must be between 0 and 1 (exclusive).");
+        return ExitCode::FAILURE;
+    }
+    if !(cli_args.bayes_alpha > 0.0 && cli_args.bayes_beta > 0.0) {
+        eprintln!("\nError: --bayes-alpha and --bayes-beta must be greater than 0.");
+        return ExitCode::FAILURE;
+    }
+    if !(0.0..=100.0).contains(&cli_args.min_species_percentage) {
+        eprintln!("\nError: --min-species-percentage must be between 0 and 100.");
+        return ExitCode::FAILURE;
+    }
+    let study_pop = cli_args.study_pop.clone()
+        .or_else(|| cli_args.ranked_study.clone())
+        .unwrap_or_default();
     
-    let cargo_home = var("CARGO_HOME")
-            .unwrap_or_else(|_| {
-                home_dir()
-                    .expect("Could not determine home directory")
-                    .join(".cargo")
-                    .to_string_lossy()
-                    .into_owned()
-            });
+    let available_cores = num_cpus::get();
+    if cli_args.num_cores == 0 {
+        cli_args.num_cores = available_cores;
+    } else if cli_args.num_cores > available_cores * 2 {
+        eprintln!(
+            "\nWarning: requested {} cores but only {} are available; consider using at most {}.",
+            cli_args.num_cores, available_cores, available_cores
+        );
+        if !cli_args.force_cores {
+            cli_args.num_cores = available_cores * 2;
+        }
+    }
 
-    println!("\nAnalysis will be performed with {} core(s)", &cli_args.num_cores);
+    progress!("\nAnalysis will be performed with {} core(s)", &cli_args.num_cores);
 
     if let Err(e) = rayon::ThreadPoolBuilder::new()
         .num_threads(cli_args.num_cores)
         .build_global() {
         eprintln!("Failed to initialize Rayon global thread pool: {:?}", e);
     }; 
-    println!("\nCleaning previous results");
-    clean_directory(&cli_args.output_dir).unwrap_or_else(|e| {
-        eprintln!("Error cleaning output directory: {}", e);
-    });
+    if !cli_args.no_cleanup {
+        progress!("\nCleaning previous results");
+        clean_directory(&cli_args.output_dir).unwrap_or_else(|e| {
+            eprintln!("Error cleaning output directory: {}", e);
+        });
+    }
 
     fs::create_dir_all(&cli_args.output_dir).unwrap_or_else(|e| {
         eprintln!("Error creating output directory: {}", e);
     });
+
+    let report_progress = |step: ProgressStep, taxa_processed: usize, total_taxa: usize| {
+        if let Err(e) = write_progress(&cli_args.output_dir, step, taxa_processed, total_taxa) {
+            eprintln!("Warning: Failed to write progress file: {}", e);
+        }
+    };
     
     let obo_file_path = PathBuf::from(&obo_file);
 
-    println!("\nReading ontology information from: {}", &obo_file_path.to_string_lossy());
+    progress!("\nReading ontology information from: {}", &obo_file_path.to_string_lossy());
 
      let ontology = match parse_obo_file(&obo_file_path) {
         Ok(parsed_ontology) => parsed_ontology,
@@ -261,7 +816,14 @@ fn main() -> ExitCode{
         }
     };
     
-    println!("Successfully parsed OBO file with {} GO terms\n", ontology.len());
+    if let Err(e) = validate_references(&ontology, cli_args.max_missing_references) {
+        eprintln!("\nError validating OBO file '{}':", obo_file);
+        eprintln!("{}", e);
+        return ExitCode::FAILURE;
+    }
+    
+    progress!("Successfully parsed OBO file with {} GO terms\n", ontology.len());
+    report_progress(ProgressStep::OboParsing, 0, 0);
 
     let (ontology_graph, go_id_to_node_index) = match build_ontology_graph(&ontology) {
         Ok(graph_data) => graph_data,
@@ -285,11 +847,15 @@ fn main() -> ExitCode{
         &root_go_ids
     );
     
-    println!("Reading background populations from: {}\n", &background_pop);
-    let taxon_ids: FxHashSet<TaxonID> = match collect_taxon_ids(&PathBuf::from(&cli_args.study_pop)) {
+    progress!("Reading background populations from: {}\n", &background_pop);
+    let collected_taxon_ids = match &cli_args.ranked_study {
+        Some(ranked_study) => collect_ranked_taxon_ids(&PathBuf::from(ranked_study)),
+        None => collect_taxon_ids(&PathBuf::from(&study_pop)),
+    };
+    let taxon_ids: FxHashSet<TaxonID> = match collected_taxon_ids {
         Ok(taxon_ids) => taxon_ids,
         Err(e) => {
-            eprintln!("\nError collecting taxon IDs from study population '{}':", cli_args.study_pop);
+            eprintln!("\nError collecting taxon IDs from study population '{}':", study_pop);
             eprintln!("{}", e);
             return ExitCode::FAILURE;
         }
@@ -302,14 +868,25 @@ fn main() -> ExitCode{
             return ExitCode::FAILURE;
         }
     };
+    let excluded_codes: FxHashSet<String> = match map_input_to_excluded_codes(&cli_args.evidence_exclude) {
+        Ok(excluded_codes) => excluded_codes,
+        Err(e) => {
+            eprintln!("\nError parsing excluded evidence codes '{}':", cli_args.evidence_exclude);
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
 
     let mut background_population = match BackgroundPop::read_background_pop(
         &taxon_ids, 
         &background_pop,
-        &categories
+        Some(cli_args.extra_background.as_slice()),
+        &categories,
+        &excluded_codes
     ) {
         Ok(Some(background_pop)) => {
-            println!("Successfully loaded background population for {} taxa\n", &taxon_ids.len());
+            progress!("Successfully loaded background population for {} taxa\n", &taxon_ids.len());
+            report_progress(ProgressStep::BackgroundLoading, background_pop.taxon_protein_count.len(), taxon_ids.len());
             background_pop
         },
         Ok(None) => {
@@ -322,18 +899,27 @@ fn main() -> ExitCode{
         }
     };
 
-    println!("Reading study populations from: {}\n", &cli_args.study_pop.clone());
+    progress!("Reading study populations from: {}\n", &study_pop);
     
-    let mut study_population = match StudyPop::read_study_pop(
-        &PathBuf::from(&cli_args.study_pop), 
-        &background_population.protein_to_go
-    ){
+    let read_study_population = match &cli_args.ranked_study {
+        Some(ranked_study) => StudyPop::from_ranked_csv_file(
+            &PathBuf::from(ranked_study),
+            &background_population.protein_to_go
+        ),
+        None => StudyPop::read_study_pop(
+            &PathBuf::from(&study_pop),
+            &background_population.protein_to_go
+        ),
+    };
+
+    let mut study_population = match read_study_population {
         Ok(Some(study_pop)) => {
-            println!("Successfully loaded study population for {} taxa\n", &taxon_ids.len());
+            progress!("Successfully loaded study population for {} taxa\n", &taxon_ids.len());
+            report_progress(ProgressStep::StudyLoading, study_pop.taxon_protein_count.len(), taxon_ids.len());
             study_pop
         },
         Ok(None) => {
-            eprintln!("Error: No study population data could be loaded from '{}'", cli_args.study_pop);
+            eprintln!("Error: No study population data could be loaded from '{}'", study_pop);
             return ExitCode::FAILURE;
         },
         Err(e) => {
@@ -342,15 +928,24 @@ fn main() -> ExitCode{
         }
     };
 
+    if let Err(e) = validate_taxon_overlap(&study_population, &background_population) {
+        eprintln!("\nError: {}", e);
+        return ExitCode::FAILURE;
+    }
+
+    let study_annotation_coverage = annotation_coverage(&study_population);
+
     let should_propagate = match cli_args.propagate_counts {
         PropagationMethod::None => false,
         PropagationMethod::Classic | PropagationMethod::Elim | PropagationMethod::Weight => true,
     };
 
+    let mut ancestor_cache = GOAncestorCache::default();
+
     if should_propagate{
-        println!("Propagating counts up the Ontology graph\n");
+        progress!("Propagating counts up the Ontology graph\n");
         
-        let ancestor_cache: GOAncestorCache = match GOAncestorCache::new(
+        ancestor_cache = match GOAncestorCache::new(
             &ontology_graph, 
             &ontology, 
             &go_id_to_node_index,
@@ -376,66 +971,139 @@ fn main() -> ExitCode{
         &taxon_ids,
         cli_args.min_protein_count
     );
+
+    background_population.filter_by_threshold(
+        &taxon_ids,
+        cli_args.min_background_count
+    );
+
+    study_population.filter_by_background_population(
+        &taxon_ids,
+        &background_population
+    );
     
     background_population.filter_by_study_population(
         &taxon_ids, 
         &study_population
     );
     
-    println!("Starting Gene Ontology (GO) term enrichment analysis\n");
+    progress!("Starting Gene Ontology (GO) term enrichment analysis\n");
     
-    let analysis = EnrichmentAnalysis::new(cli_args.statistical_test);
+    let analysis = EnrichmentAnalysis::new(cli_args.statistical_test)
+        .with_beta_prior(BetaPrior { alpha: cli_args.bayes_alpha, beta: cli_args.bayes_beta });
 
-    let enrichment_results = match cli_args.propagate_counts {
+    let algorithm = match cli_args.propagate_counts {
+        _ if matches!(cli_args.statistical_test, StatisticalTest::KolmogorovSmirnov) => {
+            progress!("Performing Kolmogorov-Smirnov test on ranked study population\n");
+            EnrichmentAlgorithm::Classic
+        },
         PropagationMethod::Elim => {
-            println!("Performing elim algorithm on propagated counts\n");
-            analysis.elim_analysis(
-                &taxon_ids,
-                cli_args.significance_threshold,
-                &study_population,
-                &background_population,
-                &level_to_go_term
-            )
+            progress!("Performing elim algorithm on propagated counts\n");
+            EnrichmentAlgorithm::Elim {
+                elim_threshold: cli_args.elim_threshold.unwrap_or(cli_args.significance_threshold),
+                level_to_go_term: &level_to_go_term,
+                ancestor_cache: &ancestor_cache,
+            }
         },
         PropagationMethod::Classic => {
-            println!("Performing classic analysis with propagated counts\n");
-            analysis.classic(
-                &taxon_ids,          
-                &background_population.go_term_count,
-                &study_population.go_term_count,
-                &background_population.taxon_protein_count,
-                &study_population.taxon_protein_count,
-            )
+            progress!("Performing classic analysis with propagated counts\n");
+            EnrichmentAlgorithm::Classic
         },
         PropagationMethod::Weight => {
-            println!("Performing weight algorithm with propagated counts\n");
-            analysis.weight(
-                &taxon_ids, 
-                &study_population, 
-                &background_population, 
-                &level_to_go_term, 
-                &ontology_graph, 
-                &go_id_to_node_index, 
-                &node_index_to_go_id)
+            progress!("Performing weight algorithm with propagated counts\n");
+            EnrichmentAlgorithm::Weight {
+                level_to_go_term: &level_to_go_term,
+                ontology_graph: &ontology_graph,
+                go_id_to_node_index: &go_id_to_node_index,
+                node_index_to_go_id: &node_index_to_go_id,
+            }
         },
         PropagationMethod::None => {
-            println!("Performing classic analysis without count propagation\n");
-            analysis.classic(
-                &taxon_ids,          
-                &background_population.go_term_count,
-                &study_population.go_term_count,
-                &background_population.taxon_protein_count,
-                &study_population.taxon_protein_count,
-            )
+            progress!("Performing classic analysis without count propagation\n");
+            EnrichmentAlgorithm::Classic
+        }
+    };
+
+    let run_enrichment = |background_population: &BackgroundPop| run_taxon_enrichment(
+        &analysis,
+        &algorithm,
+        &taxon_ids,
+        &study_population,
+        background_population
+    );
+
+    let layout_config = fr_layout_config(&cli_args);
+    let mut analysis_params = serde_json::json!({
+        "network_layout": {
+            "fr_iterations": layout_config.iterations,
+            "fr_scale": layout_config.scale,
+            "fr_dt": layout_config.dt,
+            "fr_cooloff": layout_config.cooloff_factor,
+        },
+    });
+    if cli_args.background_subsample == 0 {
+        if let Err(e) = write_analysis_params(&cli_args.output_dir, &analysis_params) {
+            eprintln!("Warning: Failed to write analysis parameters: {}", e);
+        }
+    }
+
+    if cli_args.background_subsample > 0 {
+        let largest_study = study_population.taxon_protein_count.values().copied().max().unwrap_or(0);
+        if cli_args.background_subsample < largest_study {
+            eprintln!(
+                "\nError: --background-subsample ({}) must be at least the size of the largest study population ({} proteins), since the study proteins are always kept in the subsample.",
+                cli_args.background_subsample, largest_study
+            );
+            return ExitCode::FAILURE;
+        }
+    }
+
+    let enrichment_results = if cli_args.background_subsample > 0 {
+        let replicates = cli_args.subsample_replicates.max(1);
+        let mut rng = SmallRng::seed_from_u64(cli_args.seed);
+
+        analysis_params["background_subsample"] = serde_json::json!(cli_args.background_subsample);
+        analysis_params["subsample_replicates"] = serde_json::json!(replicates);
+        analysis_params["seed"] = serde_json::json!(cli_args.seed);
+        if let Err(e) = write_analysis_params(&cli_args.output_dir, &analysis_params) {
+            eprintln!("Warning: Failed to write analysis parameters: {}", e);
         }
+
+        let replicate_results: Vec<_> = (1..=replicates)
+            .map(|replicate| {
+                progress!(
+                    "Subsampling {} background proteins per taxon (replicate {}/{})\n",
+                    cli_args.background_subsample, replicate, replicates
+                );
+                let subsampled_background = background_population.subsample(
+                    &taxon_ids,
+                    &study_population,
+                    cli_args.background_subsample,
+                    &mut rng
+                );
+                run_enrichment(&subsampled_background)
+            })
+            .collect();
+
+        median_replicate_p_values(replicate_results)
+    } else {
+        run_enrichment(&background_population)
     };
     
+    report_progress(ProgressStep::EnrichmentAnalysis, enrichment_results.len(), taxon_ids.len());
+
     let significant_species_results = adjust_species_p_values(
         &enrichment_results, 
         cli_args.correction_method, 
         Some(cli_args.significance_threshold),
         cli_args.min_odds_ratio
     );
+    report_progress(ProgressStep::MultipleTestingCorrection, significant_species_results.len(), taxon_ids.len());
+
+    let significant_species_terms: FxHashMap<TaxonID, FxHashSet<GOTermID>> = significant_species_results
+        .iter()
+        .map(|(taxon_id, go_terms)| (*taxon_id, go_terms.keys().copied().collect()))
+        .collect();
         
     let taxid_species_map = match taxid_to_species(lineage_file.clone()) {
         Ok(map) => map,
@@ -446,13 +1114,63 @@ fn main() -> ExitCode{
         }
     };
 
+    if cli_args.verbose {
+        print_single_taxon_summary(
+            &enrichment_results,
+            &significant_species_results,
+            &study_population.taxon_protein_count,
+            &taxid_species_map,
+            cli_args.significance_threshold
+        );
+    }
+
+    let all_species_results = cli_args.write_insignificant_results.then(|| adjust_all_species_p_values(
+        &enrichment_results,
+        cli_args.correction_method
+    ));
+
+    let significant_species_results = match cli_args.cluster_threshold {
+        Some(cluster_threshold) => {
+            progress!("Clustering enriched GO terms with Jaccard index >= {}\n", cluster_threshold);
+            let (clustered_results, species_clusters) = cluster_results(
+                &significant_species_results,
+                &study_population.go_term_to_protein_set,
+                &ontology,
+                cluster_threshold
+            );
+
+            let species_clusters: TermClusters<String> = species_clusters
+                .into_iter()
+                .map(|(taxon_id, clusters)| (taxid_species_map[&taxon_id].clone(), clusters))
+                .collect();
+
+            if let Err(e) = write_term_clusters(&species_clusters, &ontology, &cli_args.output_dir) {
+                eprintln!("Error writing single taxon GO term clusters: {}", e);
+            }
+
+            clustered_results
+        }
+        None => significant_species_results,
+    };
+
     match write_single_taxon_results(
         &significant_species_results,
         &ontology,
         &taxid_species_map,
         &cli_args.output_dir,
+        &WriteOptions {
+            output_format: cli_args.output_format,
+            statistical_test: cli_args.statistical_test,
+            raw_counts: cli_args.save_raw_counts.then_some(&enrichment_results),
+            max_terms_per_namespace: cli_args.max_terms_per_namespace,
+            tested_results: &enrichment_results,
+            all_results: all_species_results.as_ref(),
+            significant_terms: &significant_species_terms,
+        },
+        cli_args.jsonl_stream.then(|| Box::new(BufWriter::new(io::stdout())) as Box<dyn Write>),
     ) {
         Ok(_) => {
+            report_progress(ProgressStep::WritingResults, significant_species_results.len(), taxon_ids.len());
         }
         Err(e) => {
             eprintln!(
@@ -463,13 +1181,16 @@ fn main() -> ExitCode{
             // return ExitCode::FAILURE; 
         }
     }
-    if cli_args.save_plots != PlotType::None {
-        println!("Generating enrichment plots\n");
-        let species_plots_subdir = cli_args.output_dir.join("single_taxon_results").join("plots");
-        fs::create_dir_all(&species_plots_subdir).unwrap_or_else(|e| {
-            eprintln!("Error creating species plot  directory: {}", e);
-        });
 
+    if let Err(e) = generate_coverage_report(
+        &study_annotation_coverage,
+        &taxid_species_map,
+        &cli_args.output_dir
+    ) {
+        eprintln!("Warning: Failed to write annotation coverage report: {}", e);
+    }
+    if cli_args.save_plots != PlotType::None {
+        progress!("Generating enrichment plots\n");
         let (processed_species_data, go_term_to_protein_set) = process_species_data(
             significant_species_results,
             &study_population,
@@ -478,19 +1199,10 @@ fn main() -> ExitCode{
 
         let species_plot_data = prepare_plot_data(
             &processed_species_data, 
-            &ontology);
-        
-        let _species_bar_plots = bar_plot(
-            &species_plot_data, 
-            &species_plots_subdir,
-            cli_args.save_plots
-        );
-        
-        let _species_bubble_plots = bubble_plot(
-            species_plot_data, 
-            &species_plots_subdir,
-            cli_args.save_plots);
-        
+            &ontology,
+            cli_args.max_terms_per_namespace,
+            cli_args.statistical_test.effect_size_label());
+
         let species_protein_provider = ProteinDataProvider::Species(&go_term_to_protein_set);
         let species_network_data = prepare_network_data(
             &processed_species_data,
@@ -498,21 +1210,91 @@ fn main() -> ExitCode{
             &ontology,
         );
 
+        let species_plots_subdir = cli_args.output_dir.join("single_taxon_results").join("plots");
+        fs::create_dir_all(&species_plots_subdir).unwrap_or_else(|e| {
+            eprintln!("Error creating species plot  directory: {}", e);
+        });
+
+        let backend = plot_backend(cli_args.plot_backend, cli_args.save_plots, cli_args.network_color, layout_config, cli_args.statistical_test.effect_size_label());
+
+        let _species_bar_plots = backend.render_bar(
+            &species_plot_data, 
+            &species_plots_subdir
+        );
+
+        let _species_bubble_plots = backend.render_bubble(
+            &species_plot_data, 
+            &species_plots_subdir);
+
+        let _species_volcano_plots = volcano_plot(
+            &species_plot_data,
+            &species_plots_subdir,
+            cli_args.save_plots,
+            cli_args.significance_threshold,
+            cli_args.min_odds_ratio,
+            cli_args.statistical_test.effect_size_label());
+
+        if processed_species_data.len() >= 2 {
+            if let Err(e) = multi_species_heatmap(
+                &processed_species_data,
+                &ontology,
+                &cli_args.output_dir.join("single_taxon_results"),
+                cli_args.save_plots,
+                cli_args.statistical_test.effect_size_label()) {
+                eprintln!("Error writing multi-species heatmap: {}", e);
+            }
+        }
+
         let species_networks = build_networks(
             &species_network_data,
             &processed_species_data,
-            &ontology
+            &ontology,
+            cli_args.network_jaccard_threshold,
+            cli_args.statistical_test.effect_size_label()
         );
-        
-        let _species_network_plots = network_plot(
+
+        let _species_network_plots = backend.render_network(
             &species_networks, 
-            &species_plots_subdir,
-            cli_args.save_plots);
-    }  
+            &species_plots_subdir);
+
+        if let Err(e) = write_network_centrality(
+            &species_networks,
+            &cli_args.output_dir.join("single_taxon_results")) {
+            eprintln!("Error writing species network centrality: {}", e);
+        }
+
+        if let Err(e) = write_network_communities(
+            &species_networks,
+            &cli_args.output_dir.join("single_taxon_results")) {
+            eprintln!("Error writing species network communities: {}", e);
+        }
+
+        if let Err(e) = export_network_graphml(
+            &species_networks,
+            &species_plots_subdir) {
+            eprintln!("Error writing species network GraphML files: {}", e);
+        }
+
+        if let Err(e) = export_network_cytoscape(
+            &species_networks,
+            &species_plots_subdir) {
+            eprintln!("Error writing species network Cytoscape.js files: {}", e);
+        }
+    }
     
-    if let Some(level_to_combine) = &cli_args.combine_results {
+    let taxonomy_levels: Vec<String> = match (&cli_args.combine_results, &cli_args.taxonomy_levels) {
+        (Some(level), _) => vec![level.clone()],
+        (None, Some(levels)) => levels
+            .split(',')
+            .map(|level| level.trim().to_string())
+            .filter(|level| !level.is_empty())
+            .collect(),
+        (None, None) => Vec::new(),
+    };
+
+    if !taxonomy_levels.is_empty() {
 
-        println!("Reading taxonomic lineage information from: {}\n", lineage_file);
+        progress!("Reading taxonomic lineage information from: {}\n", lineage_file);
         
         let lineage = match read_lineage(lineage_file.clone()) {
             Ok(lineage) => lineage,
@@ -523,6 +1305,20 @@ fn main() -> ExitCode{
             }
         };
 
+        let mut missing_lineage_taxa: Vec<TaxonID> = taxon_ids
+            .iter()
+            .filter(|taxon_id| !lineage.contains_key(taxon_id))
+            .copied()
+            .collect();
+        if !missing_lineage_taxa.is_empty() {
+            missing_lineage_taxa.sort_unstable();
+            eprintln!(
+                "Warning: {} taxon ID(s) are missing from the lineage file and will not be part of any taxonomic group: {}\n",
+                missing_lineage_taxa.len(),
+                missing_lineage_taxa.iter().map(|taxon_id| taxon_id.to_string()).collect::<Vec<_>>().join(", ")
+            );
+        }
+
         let superkingdom = match get_superkingdom(&taxon_ids, &lineage) {
             Ok(superkingdom) => superkingdom,
             Err(e) => {
@@ -531,114 +1327,60 @@ fn main() -> ExitCode{
             }
         };
         
-        println!("Grouping species based on {}\n", level_to_combine);
-        
-        let grouped_species = taxid_to_level(
-            &enrichment_results,
-            &lineage,
-            level_to_combine
-        );
-
-        let lineage_organized_results= group_results_by_taxonomy(
-            &grouped_species, 
-            &enrichment_results, 
-            cli_args.lineage_percentage
-        );
-
         let matrix_path = if let Some(custom_path) = &cli_args.vcv_matrix {
-            println!("Using custom VCV matrix from: {:?} \n", custom_path);
+            progress!("Using custom VCV matrix from: {:?} \n", custom_path);
             custom_path.clone()
         } else {
-            let matrix_filename = format!("{}.dmat", &superkingdom);
-            let default_path = PathBuf::from(&cargo_home)
-                .join("taxago_assets")
-                .join(matrix_filename);
-            println!("Reading {} VCV matrix from: {:?} \n", &superkingdom, default_path);
+            let default_path = PathBuf::from(default_asset_path(&format!("{}.dmat", &superkingdom)));
+            progress!("Reading {} VCV matrix from: {:?} \n", &superkingdom, default_path);
             default_path
         };
 
-        let vcv_matrix = read_vcv_matrix(
-            matrix_path
-        ).unwrap();
-
-        println!("Performing phylogenetic meta-analysis with {} permutations", &cli_args.permutations);
-
-        let phylogenetic_results = phylogenetic_meta_analysis(
-            &taxon_ids,
-            lineage_organized_results, 
-            vcv_matrix,
-            cli_args.permutations
-        );
-
-        let significant_taxonomy_results = adjust_taxonomy_p_values(
-            &phylogenetic_results, 
-            cli_args.correction_method, 
-            Some(cli_args.significance_threshold),
-            cli_args.min_odds_ratio,
-            level_to_combine);
-        
-        match write_taxonomy_results(
-        &significant_taxonomy_results,
-            &ontology,
-            &cli_args.output_dir,
-            level_to_combine
-        ) {
-            Ok(_) => {
-            }
+        let vcv_matrix = match read_vcv_matrix(matrix_path.clone()) {
+            Ok(vcv_matrix) => vcv_matrix,
             Err(e) => {
-                eprintln!(
-                    "Error: Failed to write taxonomy results to directory '{}': {}",
-                    cli_args.output_dir.display(), 
-                    e 
-                );
-                return ExitCode::FAILURE; 
+                eprintln!("\nError reading VCV matrix from {:?}: {}", matrix_path, e);
+                return ExitCode::FAILURE;
             }
-        }
-
-        if cli_args.save_plots != PlotType::None{
-            let taxonomy_plots_subdir = cli_args.output_dir.join("combined_taxonomy_results").join("plots");
-            fs::create_dir_all(&taxonomy_plots_subdir).unwrap_or_else(|e| {
-                eprintln!("Error creating taxonomy plot directory: {}", e);
-            });
-
-            let taxonomy_plot_data = prepare_plot_data(
-                &significant_taxonomy_results, 
-                &ontology);
-
-            let _taxonomy_bar_plots = bar_plot(
-                &taxonomy_plot_data, 
-                &taxonomy_plots_subdir,
-                cli_args.save_plots);
+        };
 
-            let _taxonomy_bubble_plots = bubble_plot(
-                taxonomy_plot_data, 
-                &taxonomy_plots_subdir,
-                cli_args.save_plots);
+        let permutation_seed = match cli_args.permutation_seed {
+            Some(seed) => seed,
+            None if cli_args.combination_method == CombinationMethod::Phylogenetic => {
+                let seed = rand::random::<u64>();
+                eprintln!("Using permutation seed {} (pass --permutation-seed {} to reproduce)", seed, seed);
+                seed
+            },
+            None => 0,
+        };
 
-            let taxonomy_protein_provider = ProteinDataProvider::Taxonomy {
-                species_data_by_id: &study_population.go_term_to_protein_set,
-                taxonomy_to_species_ids: &grouped_species,
+        for level_to_combine in &taxonomy_levels {
+            let results_dir = if cli_args.taxonomy_levels.is_some() {
+                cli_args.output_dir.join(format!("combined_taxonomy_results_{}", level_to_combine.to_lowercase()))
+            } else {
+                cli_args.output_dir.join("combined_taxonomy_results")
             };
-            let taxon_network_data = prepare_network_data(
-                &significant_taxonomy_results,
-                &taxonomy_protein_provider,
+
+            if let Err(e) = combine_taxonomy_level(
+                &cli_args,
+                level_to_combine,
+                &results_dir,
+                &lineage,
+                &taxon_ids,
+                &enrichment_results,
+                &significant_species_terms,
                 &ontology,
-            );
-            
-            let taxon_networks = build_networks(
-                &taxon_network_data,
-                &significant_taxonomy_results,
-                &ontology
-            );
-            
-            let _taxon_network_plots = network_plot(
-                &taxon_networks, 
-                &taxonomy_plots_subdir,
-                cli_args.save_plots);
+                &study_population,
+                &vcv_matrix,
+                permutation_seed
+            ) {
+                eprintln!("Error: {}", e);
+                return ExitCode::FAILURE;
             }
-
         }
-    
-    println!("Finished analysis\n");
+    }
+
+    report_progress(ProgressStep::Finished, taxon_ids.len(), taxon_ids.len());
+    progress!("Finished analysis\n");
     ExitCode::SUCCESS
 }
\ No newline at end of file
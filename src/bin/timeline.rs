@@ -0,0 +1,137 @@
+use clap::Parser;
+use rustc_hash::FxHashSet;
+use std::fs::create_dir_all;
+use std::path::PathBuf;
+use std::error::Error;
+
+use TaxaGO::parsers::{
+    background_parser::*, obo_parser::*, study_parser::*
+};
+use TaxaGO::analysis::{
+    enrichment_analysis::*,
+    taxon_enrichment::*,
+    timeline::*
+};
+use TaxaGO::utils::assets::default_asset_path;
+
+const TOP_PLOTTED_TERMS: usize = 20;
+
+#[derive(Parser, Debug)]
+#[command(name = "taxago-timeline")]
+struct CliArgs {
+    #[arg(
+        short = 'o',
+        long = "obo",
+        value_name = "OBO_FILE",
+        help = "Path to the Gene Ontology file in OBO format.",
+    )]
+    obo_file: Option<String>,
+
+    #[arg(
+        long = "study-dir",
+        value_name = "STUDY_DIR",
+        help = "Directory containing one study population file per time point.",
+    )]
+    study_dir: PathBuf,
+
+    #[arg(
+        long = "time-metadata",
+        value_name = "CSV_FILE",
+        help = "CSV file mapping each study population file name to an integer time point [e.g., day1.fa,1].",
+    )]
+    time_metadata: PathBuf,
+
+    #[arg(
+        short = 'b',
+        long = "background",
+        value_name = "BACKGROUND_DIR",
+        help = "Directory containing background populations.",
+    )]
+    background_dir: Option<String>,
+
+    #[arg(
+        short = 'e',
+        long = "evidence",
+        value_name = "CATEGORIES",
+        help = "Evidence code categories to parse in background associations, either comma-separated or as a file with one category per line (lines starting with # are ignored). [possible values: all, experimental, phylogenetic, computational, author, curator, electronic]",
+        default_value = "all"
+    )]
+    evidence_categories: String,
+
+    #[arg(
+        short = 'd',
+        long = "dir",
+        value_name = "RESULTS_DIR",
+        help = "Directory to write results.",
+        default_value = "./results",
+    )]
+    output_dir: String,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let cli_args: CliArgs = CliArgs::parse();
+
+    let obo_file = cli_args.obo_file.clone().unwrap_or(default_asset_path("go.obo"));
+    let background_dir = cli_args.background_dir.clone().unwrap_or(default_asset_path("background_pop"));
+
+    let time_points = read_time_metadata(&cli_args.time_metadata)?;
+    if time_points.len() < 3 {
+        return Err(Box::new(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "At least 3 time points are required to fit a regression"
+        )));
+    }
+
+    let mut taxon_ids: FxHashSet<TaxonID> = FxHashSet::default();
+    for (filename, _) in &time_points {
+        let study_path = cli_args.study_dir.join(filename);
+        taxon_ids.extend(collect_taxon_ids(&study_path).map_err(|e| e.to_string())?);
+    }
+
+    create_dir_all(&cli_args.output_dir)?;
+
+    println!("\nReading ontology information from: {}\n", &obo_file);
+    let ontology = parse_obo_file(&PathBuf::from(&obo_file))?;
+
+    println!("Reading background population from: {}\n", &background_dir);
+    let categories = map_input_to_category(cli_args.evidence_categories.clone())?;
+    let background_population = BackgroundPop::read_background_pop(
+        &taxon_ids,
+        &background_dir,
+        None,
+        &categories,
+        &FxHashSet::default()
+    )?.ok_or("No background population data could be loaded")?;
+
+    let analysis = EnrichmentAnalysis::new(StatisticalTest::Fishers);
+    let mut time_point_results: Vec<TimePointResults> = Vec::with_capacity(time_points.len());
+
+    for (filename, time_point) in &time_points {
+        println!("Performing enrichment analysis for time point {} ({})\n", time_point, filename);
+
+        let study_path = cli_args.study_dir.join(filename);
+        let mut study_population = StudyPop::read_study_pop(&study_path, &background_population.protein_to_go)
+            .map_err(|e| e.to_string())?
+            .ok_or(format!("No study population data could be loaded from '{}'", study_path.display()))?;
+
+        study_population.filter_by_background_population(&taxon_ids, &background_population);
+
+        let enrichment_results = run_taxon_enrichment(
+            &analysis,
+            &EnrichmentAlgorithm::Classic,
+            &taxon_ids,
+            &study_population,
+            &background_population
+        );
+        time_point_results.push((*time_point, enrichment_results));
+    }
+
+    let regressions = timeline_regression(&time_point_results);
+
+    let output_dir = PathBuf::from(&cli_args.output_dir);
+    write_timeline_regression(&regressions, &ontology, &output_dir.join("timeline_regression.tsv"))?;
+    timeline_plots(&regressions, &ontology, &output_dir.join("timeline_plots"), TOP_PLOTTED_TERMS)?;
+
+    println!("Finished analysis\n");
+    Ok(())
+}
@@ -1,11 +1,14 @@
 use rustc_hash::{FxHashMap, FxHashSet};
-use std::fs::File;
+use std::fs::{read_dir, read_to_string, File};
 use std::io::{BufRead, BufReader, Result as IoResult, Error as IoError, ErrorKind};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use rayon::prelude::*;
+use rand::{Rng, seq::index::sample};
 use crate::parsers::study_parser::*;
 use compact_str::CompactString;
+use flate2::read::GzDecoder;
+use tar::Archive;
 use thiserror::Error;
 
 pub type TaxonID = u32;
@@ -64,6 +67,11 @@ pub enum BackgroundParserError {
         kind: std::io::ErrorKind,
         message: String,
     },
+
+    #[error("Background file name '{file_path}' does not follow the {{taxon_id}}_background.txt pattern")]
+    InvalidFileName {
+        file_path: PathBuf,
+    },
 }
 
 pub fn map_code_to_category(
@@ -86,15 +94,25 @@ pub fn map_code_to_category(
     }
 }
 
+// The input is either a comma-separated list of categories or a file with one category per line.
+// Blank lines and lines starting with '#' are skipped in files.
 pub fn map_input_to_category(
     cli_input: String
 ) -> IoResult<Vec<EvidenceCategory>> {
-    let parts: Vec<String> = cli_input
-        .to_lowercase()
-        .split(',')
-        .map(|part| part.trim().to_string())
-        .filter(|part| !part.is_empty())
-        .collect();
+    let parts: Vec<String> = if Path::new(&cli_input).is_file() {
+        read_to_string(&cli_input)?
+            .lines()
+            .map(|line| line.trim().to_lowercase())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .collect()
+    } else {
+        cli_input
+            .to_lowercase()
+            .split(',')
+            .map(|part| part.trim().to_string())
+            .filter(|part| !part.is_empty())
+            .collect()
+    };
 
     let contains_all = parts.iter().any(|part| part == "all");
 
@@ -138,21 +156,166 @@ pub fn map_input_to_category(
     }
 }
 
+// Individual evidence codes to drop on top of the selected categories, e.g. "IEA,ND".
+// Codes are matched case-insensitively and must be known GO evidence codes.
+pub fn map_input_to_excluded_codes(
+    cli_input: &str
+) -> IoResult<FxHashSet<String>> {
+    let mut excluded_codes = FxHashSet::default();
+
+    for part in cli_input.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+        let code = CompactString::new(part.to_uppercase());
+        if map_code_to_category(&code, 0, &PathBuf::new()).is_err() {
+            return Err(IoError::new(
+                ErrorKind::InvalidInput,
+                format!("Unrecognized evidence code: {}. Expected a GO evidence code such as IEA, ISS or TAS.", part)
+            ));
+        }
+        excluded_codes.insert(code.to_string());
+    }
+
+    Ok(excluded_codes)
+}
+
+// Calls `visit` with the taxon ID and contents of every `{taxon_id}_background.txt` file in a background
+// directory or tarball. Tarball entries are decompressed one at a time as the archive is read.
+fn for_each_background_file<F>(dir: &str, mut visit: F) -> IoResult<()>
+where
+    F: FnMut(TaxonID, &mut dyn BufRead) -> IoResult<()>
+{
+    let background_path = PathBuf::from(dir);
+    let taxon_from_name = |name: &str| name
+        .strip_suffix("_background.txt")
+        .and_then(|taxon_id| taxon_id.parse::<TaxonID>().ok());
+
+    if background_path.is_file() && is_tarball(&background_path) {
+        let mut archive = Archive::new(GzDecoder::new(File::open(&background_path)?));
+        for entry in archive.entries()? {
+            let entry = entry?;
+            let taxon_id = entry.path()?.file_name().and_then(|name| name.to_str()).and_then(taxon_from_name);
+            if let Some(taxon_id) = taxon_id {
+                visit(taxon_id, &mut BufReader::new(entry))?;
+            }
+        }
+    } else {
+        for entry in read_dir(&background_path)? {
+            let entry = entry?;
+            if let Some(taxon_id) = entry.file_name().to_str().and_then(taxon_from_name) {
+                let file = File::open(entry.path())?;
+                visit(taxon_id, &mut BufReader::with_capacity(128 * 1024, file))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Taxa with a `{taxon_id}_background.txt` file in a background directory or tarball.
+pub fn list_background_taxa(dir: &str) -> IoResult<FxHashSet<TaxonID>> {
+    let mut taxon_ids = FxHashSet::default();
+    for_each_background_file(dir, |taxon_id, _| {
+        taxon_ids.insert(taxon_id);
+        Ok(())
+    })?;
+    Ok(taxon_ids)
+}
+
+// Number of distinct proteins in a background file, read from its first column without parsing the annotations.
+pub fn count_background_proteins<R: BufRead>(reader: R) -> IoResult<usize> {
+    let mut proteins: FxHashSet<String> = FxHashSet::default();
+    for line in reader.lines() {
+        let line = line?;
+        if let Some(protein) = line.split('\t').next().filter(|protein| !protein.is_empty()) {
+            if !proteins.contains(protein) {
+                proteins.insert(protein.to_string());
+            }
+        }
+    }
+    Ok(proteins.len())
+}
+
+// Protein count of every taxon with a `{taxon_id}_background.txt` file in a background directory or tarball.
+pub fn background_protein_counts(dir: &str) -> IoResult<FxHashMap<TaxonID, usize>> {
+    let mut protein_counts = FxHashMap::default();
+    for_each_background_file(dir, |taxon_id, reader| {
+        protein_counts.insert(taxon_id, count_background_proteins(reader)?);
+        Ok(())
+    })?;
+    Ok(protein_counts)
+}
+
 impl BackgroundPop {
     pub fn read_background_pop(
         taxon_ids: &FxHashSet<TaxonID>, 
         dir: &str,
-        categories: &Vec<EvidenceCategory>
+        extra_dirs: Option<&[PathBuf]>,
+        categories: &Vec<EvidenceCategory>,
+        excluded_codes: &FxHashSet<String>
+    ) -> IoResult<Option<Self>> {
+        let background_pop = Self::read_background_dir(taxon_ids, dir, categories, excluded_codes)?;
+
+        let Some(extra_dirs) = extra_dirs.filter(|dirs| !dirs.is_empty()) else {
+            return Ok(background_pop);
+        };
+
+        let mut populations: Vec<Self> = background_pop.into_iter().collect();
+        for extra_dir in extra_dirs {
+            if let Some(extra_background_pop) = Self::read_background_dir(
+                taxon_ids,
+                &extra_dir.to_string_lossy(),
+                categories,
+                excluded_codes
+            )? {
+                populations.push(extra_background_pop);
+            }
+        }
+
+        Ok(Some(Self::merge(populations)))
+    }
+
+    pub fn merge(populations: Vec<BackgroundPop>) -> BackgroundPop {
+        let mut merged = BackgroundPop::default();
+
+        for population in populations {
+            for (taxon_id, protein_count) in population.taxon_protein_count {
+                if merged.taxon_protein_count.insert(taxon_id, protein_count).is_some() {
+                    eprintln!("[WARNING] Taxon {} is present in multiple background populations. Using the last one read.", taxon_id);
+                    merged.protein_to_go.remove(&taxon_id);
+                    merged.go_term_count.remove(&taxon_id);
+                    merged.go_term_to_protein_set.remove(&taxon_id);
+                }
+            }
+            merged.protein_to_go.extend(population.protein_to_go);
+            merged.go_term_count.extend(population.go_term_count);
+            merged.go_term_to_protein_set.extend(population.go_term_to_protein_set);
+        }
+
+        merged
+    }
+
+    fn read_background_dir(
+        taxon_ids: &FxHashSet<TaxonID>, 
+        dir: &str,
+        categories: &Vec<EvidenceCategory>,
+        excluded_codes: &FxHashSet<String>
     ) -> IoResult<Option<Self>> {
+        let background_path = PathBuf::from(dir);
+        if background_path.is_file() && background_path.extension().and_then(|s| s.to_str()) == Some("dat") {
+            return Self::from_uniprot_dat_file(&background_path, taxon_ids, categories, excluded_codes);
+        }
+        if background_path.is_file() && is_tarball(&background_path) {
+            return Self::from_tarball(&background_path, taxon_ids, categories, excluded_codes);
+        }
         
-        let (taxon_protein_count, protein_to_go, go_term_count, go_term_to_protein_set) = taxon_ids
+        let (mut taxon_protein_count, mut protein_to_go, mut go_term_count, mut go_term_to_protein_set) = taxon_ids
             .par_iter()
             .map(|&taxon_id| {
                 let taxon_background_file_path = format!("{}/{}_background.txt", dir, taxon_id);
                 
                 match process_single_taxon(
                     &PathBuf::from(&taxon_background_file_path),
-                    categories
+                    categories,
+                    excluded_codes
                 ) {
                     Ok(Some(data)) => (taxon_id, Some(data)),
                     Ok(None) => {
@@ -187,6 +350,45 @@ impl BackgroundPop {
                     (pc1, pg1, gc1, gp1)
                 }
             );
+
+        let missing_taxon_ids: FxHashSet<TaxonID> = taxon_ids
+            .iter()
+            .filter(|taxon_id| !taxon_protein_count.contains_key(taxon_id))
+            .copied()
+            .collect();
+
+        if !missing_taxon_ids.is_empty() && background_path.is_dir() {
+            let dat_files: Vec<PathBuf> = read_dir(&background_path)?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("dat"))
+                .collect();
+
+            for dat_file in dat_files {
+                match Self::from_uniprot_dat_file(&dat_file, &missing_taxon_ids, categories, excluded_codes) {
+                    Ok(Some(dat_background)) => {
+                        for (taxon_id, count) in dat_background.taxon_protein_count {
+                            if taxon_protein_count.contains_key(&taxon_id) {
+                                continue;
+                            }
+                            taxon_protein_count.insert(taxon_id, count);
+                            if let Some(map) = dat_background.protein_to_go.get(&taxon_id) {
+                                protein_to_go.insert(taxon_id, map.clone());
+                            }
+                            if let Some(map) = dat_background.go_term_count.get(&taxon_id) {
+                                go_term_count.insert(taxon_id, map.clone());
+                            }
+                            if let Some(map) = dat_background.go_term_to_protein_set.get(&taxon_id) {
+                                go_term_to_protein_set.insert(taxon_id, map.clone());
+                            }
+                        }
+                    },
+                    Ok(None) => {},
+                    Err(err) => {
+                        eprintln!("[ERROR] Error processing UniProt flat file {}: {}", dat_file.display(), err);
+                    }
+                }
+            }
+        }
     
         Ok(Some(Self {
             taxon_protein_count,
@@ -196,6 +398,213 @@ impl BackgroundPop {
         }))
     }
 
+    pub fn from_uniprot_dat_file(
+        dat_file: &PathBuf,
+        taxon_ids: &FxHashSet<TaxonID>,
+        categories: &Vec<EvidenceCategory>,
+        excluded_codes: &FxHashSet<String>
+    ) -> IoResult<Option<Self>> {
+        let taxon_data = process_uniprot_dat_file(dat_file, taxon_ids, categories, excluded_codes)
+            .map_err(|e| IoError::new(ErrorKind::InvalidData, e.to_string()))?;
+
+        if taxon_data.is_empty() {
+            return Ok(None);
+        }
+
+        let mut taxon_protein_count = FxHashMap::default();
+        let mut protein_to_go = FxHashMap::default();
+        let mut go_term_count = FxHashMap::default();
+        let mut go_term_to_protein_set = FxHashMap::default();
+
+        for (taxon_id, (protein_count, protein_to_go_map, go_term_counts, go_term_to_protein)) in taxon_data {
+            taxon_protein_count.insert(taxon_id, protein_count);
+            protein_to_go.insert(taxon_id, protein_to_go_map);
+            go_term_count.insert(taxon_id, go_term_counts);
+            go_term_to_protein_set.insert(taxon_id, go_term_to_protein);
+        }
+
+        Ok(Some(Self {
+            taxon_protein_count,
+            protein_to_go,
+            go_term_count,
+            go_term_to_protein_set
+        }))
+    }
+
+    // Entries are read one at a time from the compressed stream, so only the requested taxa are kept in memory.
+    pub fn from_tarball(
+        tarball: &PathBuf,
+        taxon_ids: &FxHashSet<TaxonID>,
+        categories: &Vec<EvidenceCategory>,
+        excluded_codes: &FxHashSet<String>
+    ) -> IoResult<Option<Self>> {
+        let file = File::open(tarball)?;
+        let mut archive = Archive::new(GzDecoder::new(file));
+
+        let mut taxon_protein_count = FxHashMap::default();
+        let mut protein_to_go = FxHashMap::default();
+        let mut go_term_count = FxHashMap::default();
+        let mut go_term_to_protein_set = FxHashMap::default();
+
+        for entry in archive.entries()? {
+            let entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+
+            let entry_path = entry.path()?.into_owned();
+            let Some(taxon_id) = entry_path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix("_background.txt"))
+                .and_then(|taxon_id| taxon_id.parse::<TaxonID>().ok()) else { continue };
+
+            if !taxon_ids.contains(&taxon_id) {
+                continue;
+            }
+
+            let entry_file_path = tarball.join(&entry_path);
+            match parse_background_reader(
+                BufReader::with_capacity(128 * 1024, entry),
+                &entry_file_path,
+                categories,
+                excluded_codes
+            ) {
+                Ok(Some((protein_count, protein_to_go_map, go_term_counts, go_term_to_protein))) => {
+                    taxon_protein_count.insert(taxon_id, protein_count);
+                    protein_to_go.insert(taxon_id, protein_to_go_map);
+                    go_term_count.insert(taxon_id, go_term_counts);
+                    go_term_to_protein_set.insert(taxon_id, go_term_to_protein);
+                },
+                Ok(None) => {
+                    eprintln!("[INFO] No data processed or found for taxon {} from file: {}", taxon_id, entry_file_path.display());
+                },
+                Err(err) => {
+                    eprintln!("[ERROR] Error processing taxon {} from file {}: {}", taxon_id, entry_file_path.display(), err);
+                }
+            }
+        }
+
+        for taxon_id in taxon_ids {
+            if !taxon_protein_count.contains_key(taxon_id) {
+                eprintln!("[INFO] No background file found for taxon {} in archive: {}", taxon_id, tarball.display());
+            }
+        }
+
+        Ok(Some(Self {
+            taxon_protein_count,
+            protein_to_go,
+            go_term_count,
+            go_term_to_protein_set
+        }))
+    }
+
+    pub fn filter_by_threshold(
+        &mut self,
+        taxon_ids: &FxHashSet<TaxonID>,
+        threshold: usize
+    ) {
+        for taxon_id in taxon_ids {
+            let terms_to_remove: Vec<GOTermID> = match self.go_term_count.get(taxon_id) {
+                Some(term_count) => term_count
+                    .iter()
+                    .filter(|(_, &count)| count < threshold)
+                    .map(|(&term_id, _)| term_id)
+                    .collect(),
+                None => continue,
+            };
+
+            if terms_to_remove.is_empty() {
+                continue;
+            }
+
+            if let Some(count_map) = self.go_term_count.get_mut(taxon_id) {
+                for term_id in &terms_to_remove {
+                    count_map.remove(term_id);
+                }
+            }
+
+            if let Some(term_map) = self.go_term_to_protein_set.get_mut(taxon_id) {
+                for term_id in &terms_to_remove {
+                    term_map.remove(term_id);
+                }
+            }
+        }
+    }
+
+    // The study proteins of each taxon are always kept, so the study stays a subset of the background,
+    // and the rest of the `sample_size` proteins are drawn from the other background proteins.
+    pub fn subsample<R: Rng>(
+        &self,
+        taxon_ids: &FxHashSet<TaxonID>,
+        study_pop: &StudyPop,
+        sample_size: usize,
+        rng: &mut R
+    ) -> Self {
+        let mut subsampled = BackgroundPop::default();
+
+        let mut sorted_taxon_ids: Vec<TaxonID> = taxon_ids.iter().copied().collect();
+        sorted_taxon_ids.sort_unstable();
+
+        for taxon_id in sorted_taxon_ids {
+            let Some(taxon_protein_to_go) = self.protein_to_go.get(&taxon_id) else { continue };
+            let study_proteins = study_pop.taxon_map.get(&taxon_id);
+            let in_study = |protein: &CompactString| study_proteins
+                .is_some_and(|proteins| proteins.contains(protein));
+
+            let (study_background, mut other_proteins): (Vec<&CompactString>, Vec<&CompactString>) = taxon_protein_to_go
+                .keys()
+                .partition(|protein| in_study(protein));
+            other_proteins.sort_unstable();
+
+            let other_sample_size = sample_size.saturating_sub(study_background.len());
+            let mut sampled_proteins: FxHashSet<&CompactString> = study_background.into_iter().collect();
+            if other_sample_size >= other_proteins.len() {
+                sampled_proteins.extend(other_proteins);
+            } else {
+                sampled_proteins.extend(
+                    sample(rng, other_proteins.len(), other_sample_size)
+                        .into_iter()
+                        .map(|index| other_proteins[index])
+                );
+            }
+
+            let go_term_to_proteins: GOTermToProteinSet = self.go_term_to_protein_set
+                .get(&taxon_id)
+                .map(|term_map| {
+                    term_map
+                        .iter()
+                        .filter_map(|(&go_term, term_proteins)| {
+                            let kept: FxHashSet<Protein> = term_proteins
+                                .iter()
+                                .filter(|protein| sampled_proteins.contains(protein.as_ref()))
+                                .cloned()
+                                .collect();
+                            (!kept.is_empty()).then_some((go_term, kept))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            subsampled.go_term_count.insert(
+                taxon_id,
+                go_term_to_proteins.iter().map(|(&go_term, proteins)| (go_term, proteins.len())).collect()
+            );
+            subsampled.go_term_to_protein_set.insert(taxon_id, go_term_to_proteins);
+            subsampled.taxon_protein_count.insert(taxon_id, sampled_proteins.len());
+            subsampled.protein_to_go.insert(
+                taxon_id,
+                taxon_protein_to_go
+                    .iter()
+                    .filter(|(protein, _)| sampled_proteins.contains(protein))
+                    .map(|(protein, go_terms)| (protein.clone(), go_terms.clone()))
+                    .collect()
+            );
+        }
+
+        subsampled
+    }
+
     pub fn filter_by_study_population(&mut self, taxon_ids: &FxHashSet<TaxonID>, study_pop: &StudyPop) {
         for &taxon_id in taxon_ids {
             let study_terms = match study_pop.go_term_count.get(&taxon_id) {
@@ -239,7 +648,8 @@ impl BackgroundPop {
 
 fn process_single_taxon(
     taxon_background_path: &PathBuf,
-    categories: &Vec<EvidenceCategory>
+    categories: &Vec<EvidenceCategory>,
+    excluded_codes: &FxHashSet<String>
 ) -> Result<Option<(usize, ProteinToGO, GOTermCount, GOTermToProteinSet)>, BackgroundParserError> {
     
     if !taxon_background_path.is_file() {
@@ -263,8 +673,73 @@ fn process_single_taxon(
         }
     };
 
-    let reader = BufReader::with_capacity(128 * 1024, file);
+    parse_background_reader(
+        BufReader::with_capacity(128 * 1024, file),
+        taxon_background_path,
+        categories,
+        excluded_codes
+    )
+}
+
+// Parses only the first `sample_lines` lines of a background file, so a whole directory can be checked quickly.
+// Returns the taxon ID from the file name, the number of proteins read and the GO terms they are annotated to.
+pub fn check_background_file(
+    taxon_background_path: &PathBuf,
+    categories: &Vec<EvidenceCategory>,
+    sample_lines: usize
+) -> Result<(TaxonID, usize, FxHashSet<GOTermID>), BackgroundParserError> {
+    let taxon_id = taxon_background_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.strip_suffix("_background.txt"))
+        .and_then(|taxon_id| taxon_id.parse::<TaxonID>().ok())
+        .ok_or_else(|| BackgroundParserError::InvalidFileName {
+            file_path: taxon_background_path.to_path_buf(),
+        })?;
+
+    let io_error = |e: IoError| BackgroundParserError::FileProcessingIoError {
+        file_path: taxon_background_path.to_path_buf(),
+        kind: e.kind(),
+        message: e.to_string(),
+    };
+
+    let file = File::open(taxon_background_path).map_err(io_error)?;
+    let sample = BufReader::new(file)
+        .lines()
+        .take(sample_lines)
+        .collect::<IoResult<Vec<String>>>()
+        .map_err(io_error)?
+        .join("\n");
 
+    let parsed = parse_background_reader(
+        sample.as_bytes(),
+        taxon_background_path,
+        categories,
+        &FxHashSet::default()
+    )?;
+
+    Ok(match parsed {
+        Some((protein_count, _, _, go_term_to_protein_set)) => (
+            taxon_id,
+            protein_count,
+            go_term_to_protein_set.into_keys().collect()
+        ),
+        None => (taxon_id, 0, FxHashSet::default()),
+    })
+}
+
+pub fn is_tarball(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.ends_with(".tar.gz") || name.ends_with(".tgz"))
+}
+
+fn parse_background_reader<R: BufRead>(
+    reader: R,
+    taxon_background_path: &PathBuf,
+    categories: &Vec<EvidenceCategory>,
+    excluded_codes: &FxHashSet<String>
+) -> Result<Option<(usize, ProteinToGO, GOTermCount, GOTermToProteinSet)>, BackgroundParserError> {
     let mut protein_to_go_map: FxHashMap<CompactString, FxHashSet<GOTermID>> = FxHashMap::default();
     let mut go_term_counts: FxHashMap<GOTermID, usize> = FxHashMap::default();
     let mut go_term_to_protein_set: FxHashMap<GOTermID, FxHashSet<Protein>> = FxHashMap::default();
@@ -295,7 +770,7 @@ fn process_single_taxon(
         let code_str = CompactString::new(parts[2]);
         let category = map_code_to_category(&code_str, line_number, taxon_background_path)?; 
 
-        if categories.contains(&category) {
+        if categories.contains(&category) && !excluded_codes.contains(code_str.as_str()) {
             let protein_compact_str = CompactString::new(parts[0]);
             let protein_arc = Arc::new(protein_compact_str.clone());
             
@@ -331,3 +806,225 @@ fn process_single_taxon(
     }
 }
 
+fn process_uniprot_dat_file(
+    dat_file: &PathBuf,
+    taxon_ids: &FxHashSet<TaxonID>,
+    categories: &Vec<EvidenceCategory>,
+    excluded_codes: &FxHashSet<String>
+) -> Result<FxHashMap<TaxonID, (usize, ProteinToGO, GOTermCount, GOTermToProteinSet)>, BackgroundParserError> {
+
+    let file = File::open(dat_file).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            BackgroundParserError::FileNotFound {
+                file_path: dat_file.to_path_buf(),
+            }
+        } else {
+            BackgroundParserError::FileProcessingIoError {
+                file_path: dat_file.to_path_buf(),
+                kind: e.kind(),
+                message: e.to_string(),
+            }
+        }
+    })?;
+
+    let reader = BufReader::with_capacity(128 * 1024, file);
+
+    let mut taxon_maps: FxHashMap<TaxonID, (ProteinToGO, GOTermCount, GOTermToProteinSet)> = FxHashMap::default();
+
+    let mut accession: Option<CompactString> = None;
+    let mut entry_taxon_id: Option<TaxonID> = None;
+    let mut entry_go_terms: Vec<GOTermID> = Vec::new();
+
+    for (line_idx, line_result) in reader.lines().enumerate() {
+        let line_number = line_idx + 1;
+        let line = line_result.map_err(|e| BackgroundParserError::FileProcessingIoError {
+            file_path: dat_file.to_path_buf(),
+            kind: e.kind(),
+            message: e.to_string(),
+        })?;
+
+        if let Some(rest) = line.strip_prefix("AC   ") {
+            if accession.is_none() {
+                accession = rest
+                    .split(';')
+                    .map(str::trim)
+                    .find(|acc| !acc.is_empty())
+                    .map(CompactString::new);
+            }
+        } else if let Some(rest) = line.strip_prefix("OX   ") {
+            entry_taxon_id = rest
+                .trim()
+                .strip_prefix("NCBI_TaxID=")
+                .and_then(|ids| ids.split(|c: char| !c.is_ascii_digit()).next())
+                .and_then(|id| id.parse::<TaxonID>().ok());
+        } else if let Some(rest) = line.strip_prefix("DR   GO;") {
+            let fields: Vec<&str> = rest
+                .split(';')
+                .map(str::trim)
+                .filter(|field| !field.is_empty())
+                .collect();
+
+            let (Some(go_field), Some(evidence_field)) = (fields.first(), fields.last()) else { continue };
+            let code_str = CompactString::new(evidence_field.split(':').next().unwrap_or(""));
+            let category = map_code_to_category(&code_str, line_number, dat_file)?;
+
+            if categories.contains(&category) && !excluded_codes.contains(code_str.as_str()) {
+                if let Some(Ok(go_id)) = go_field.strip_prefix("GO:").map(|id| id.parse::<GOTermID>()) {
+                    entry_go_terms.push(go_id);
+                }
+            }
+        } else if line.starts_with("//") {
+            if let (Some(protein), Some(taxon_id)) = (accession.take(), entry_taxon_id.take()) {
+                if taxon_ids.contains(&taxon_id) && !entry_go_terms.is_empty() {
+                    let (protein_to_go_map, go_term_counts, go_term_to_protein_set) = taxon_maps
+                        .entry(taxon_id)
+                        .or_default();
+                    let protein_arc = Arc::new(protein.clone());
+
+                    for &go_id in &entry_go_terms {
+                        protein_to_go_map
+                            .entry(protein.clone())
+                            .or_insert_with(FxHashSet::default)
+                            .insert(go_id);
+
+                        let is_new_association_for_go_term = go_term_to_protein_set
+                            .entry(go_id)
+                            .or_insert_with(FxHashSet::default)
+                            .insert(Arc::clone(&protein_arc));
+
+                        if is_new_association_for_go_term {
+                            *go_term_counts.entry(go_id).or_insert(0) += 1;
+                        }
+                    }
+                }
+            }
+            accession = None;
+            entry_taxon_id = None;
+            entry_go_terms.clear();
+        }
+    }
+
+    Ok(taxon_maps
+        .into_iter()
+        .map(|(taxon_id, (protein_to_go_map, go_term_counts, go_term_to_protein_set))| {
+            (taxon_id, (protein_to_go_map.len(), protein_to_go_map, go_term_counts, go_term_to_protein_set))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{write::GzEncoder, Compression};
+
+    #[test]
+    fn from_tarball_reads_per_taxon_background_files() {
+        let tarball = std::env::temp_dir().join(format!("taxago_from_tarball_{}.tar.gz", std::process::id()));
+        let mut builder = tar::Builder::new(GzEncoder::new(File::create(&tarball).unwrap(), Compression::default()));
+        for (file_name, contents) in [
+            ("backgrounds/9606_background.txt", "P1\tGO:0008150\tIDA\nP1\tGO:0003674\tIEA\nP2\tGO:0008150\tIBA\n"),
+            ("backgrounds/10090_background.txt", "Q1\tGO:0005575\tIDA\n"),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, file_name, contents.as_bytes()).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let background_pop = BackgroundPop::read_background_pop(
+            &[9606].into_iter().collect(),
+            &tarball.to_string_lossy(),
+            None,
+            &map_input_to_category("all".to_string()).unwrap(),
+            &FxHashSet::default()
+        ).unwrap().unwrap();
+        std::fs::remove_file(&tarball).unwrap();
+
+        let protein_to_go = &background_pop.protein_to_go[&9606];
+        assert_eq!(protein_to_go.len(), 2);
+        assert_eq!(protein_to_go["P1"], [8150, 3674].into_iter().collect());
+        assert_eq!(protein_to_go["P2"], [8150].into_iter().collect());
+        assert_eq!(background_pop.taxon_protein_count[&9606], 2);
+        assert!(!background_pop.protein_to_go.contains_key(&10090));
+    }
+
+    #[test]
+    fn evidence_file_skips_comments_and_blank_lines() {
+        let evidence_file = std::env::temp_dir().join(format!("taxago_evidence_{}.txt", std::process::id()));
+        std::fs::write(&evidence_file, "experimental\n# ignore this\nphylogenetic\n").unwrap();
+
+        let categories = map_input_to_category(evidence_file.to_string_lossy().into_owned());
+        std::fs::remove_file(&evidence_file).unwrap();
+
+        assert_eq!(categories.unwrap(), vec![EvidenceCategory::Experimental, EvidenceCategory::Phylogenetic]);
+    }
+
+    #[test]
+    fn excluding_iea_drops_an_electronic_only_background() {
+        let background_dir = std::env::temp_dir().join(format!("taxago_evidence_exclude_{}", std::process::id()));
+        std::fs::create_dir_all(&background_dir).unwrap();
+        std::fs::write(background_dir.join("9606_background.txt"), "P1\tGO:0008150\tIEA\nP2\tGO:0003674\tIEA\n").unwrap();
+
+        let background_pop = BackgroundPop::read_background_pop(
+            &[9606].into_iter().collect(),
+            &background_dir.to_string_lossy(),
+            None,
+            &map_input_to_category("all".to_string()).unwrap(),
+            &map_input_to_excluded_codes("IEA").unwrap()
+        ).unwrap().unwrap();
+        std::fs::remove_dir_all(&background_dir).unwrap();
+
+        assert!(background_pop.protein_to_go.get(&9606).is_none_or(|protein_to_go| protein_to_go.is_empty()));
+        assert!(!background_pop.taxon_protein_count.contains_key(&9606));
+    }
+
+    #[test]
+    fn from_uniprot_dat_file_reads_go_cross_references() {
+        let dat_file = std::env::temp_dir().join(format!("taxago_uniprot_{}.dat", std::process::id()));
+        std::fs::write(&dat_file, "\
+            ID   P1_HUMAN                Reviewed;         393 AA.\n\
+            AC   P1; Q0SECONDARY;\n\
+            AC   Q1SECONDARY;\n\
+            OX   NCBI_TaxID=9606 {ECO:0000313|EMBL:ABC12345.1};\n\
+            DR   EMBL; AB000001; BAA00001.1; -; mRNA.\n\
+            DR   GO; GO:0008150; P:biological_process; IDA:UniProtKB.\n\
+            DR   GO; GO:0003674; F:molecular_function; IEA:InterPro.\n\
+            DR   InterPro; IPR000001; Kringle.\n\
+            //\n\
+            ID   P2_HUMAN                Reviewed;         120 AA.\n\
+            AC   P2;\n\
+            OX   NCBI_TaxID=9606;\n\
+            DR   GO; GO:0008150; P:biological_process; IBA:GO_Central.\n\
+            DR   GO; GO:0005575; C:cellular_component; IEA:UniProtKB-SubCell.\n\
+            //\n\
+            ID   P3_HUMAN                Reviewed;          88 AA.\n\
+            AC   P3;\n\
+            OX   NCBI_TaxID=9606;\n\
+            DR   EMBL; AB000003; BAA00003.1; -; mRNA.\n\
+            //\n\
+            ID   Q1_MOUSE                Reviewed;         210 AA.\n\
+            AC   Q1;\n\
+            OX   NCBI_TaxID=10090;\n\
+            DR   GO; GO:0005575; C:cellular_component; IDA:MGI.\n\
+            //\n").unwrap();
+
+        let background_pop = BackgroundPop::from_uniprot_dat_file(
+            &dat_file,
+            &[9606].into_iter().collect(),
+            &map_input_to_category("all".to_string()).unwrap(),
+            &map_input_to_excluded_codes("IEA").unwrap()
+        ).unwrap().unwrap();
+        std::fs::remove_file(&dat_file).unwrap();
+
+        // P3 has no GO cross-reference, so it is not part of the background.
+        let protein_to_go = &background_pop.protein_to_go[&9606];
+        assert_eq!(protein_to_go.len(), 2);
+        assert_eq!(protein_to_go["P1"], [8150].into_iter().collect());
+        assert_eq!(protein_to_go["P2"], [8150].into_iter().collect());
+        assert_eq!(background_pop.taxon_protein_count[&9606], 2);
+        assert_eq!(background_pop.go_term_count[&9606], [(8150, 2)].into_iter().collect());
+        assert!(!background_pop.protein_to_go.contains_key(&10090));
+    }
+}
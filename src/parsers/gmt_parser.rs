@@ -0,0 +1,74 @@
+use rustc_hash::FxHashSet;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use compact_str::CompactString;
+use thiserror::Error;
+
+#[derive(Debug, Clone)]
+pub struct GeneSet {
+    pub name: String,
+    pub description: String,
+    pub members: FxHashSet<CompactString>,
+}
+
+#[derive(Error, Debug)]
+pub enum GmtParserError {
+    #[error("GMT file not found at path: '{0}'.")]
+    FileNotFound(PathBuf),
+
+    #[error("GMT I/O Error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Line {line} of GMT file '{file_path}' has fewer than the 3 required columns (name, description, members).")]
+    MissingColumns {
+        line: usize,
+        file_path: PathBuf,
+    },
+}
+
+// Reads a GMT file: one tab-separated gene set per line, as name, description and its members.
+pub fn parse_gmt_file(gmt_file_path: &PathBuf) -> Result<Vec<GeneSet>, GmtParserError> {
+    if !gmt_file_path.exists() {
+        return Err(GmtParserError::FileNotFound(gmt_file_path.clone()));
+    }
+
+    let reader = BufReader::new(File::open(gmt_file_path)?);
+    let mut gene_sets = Vec::new();
+
+    for (line_index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let mut fields = line.split('\t');
+        let (Some(name), Some(description)) = (fields.next(), fields.next()) else {
+            return Err(GmtParserError::MissingColumns {
+                line: line_index + 1,
+                file_path: gmt_file_path.clone(),
+            });
+        };
+
+        let members: FxHashSet<CompactString> = fields
+            .map(str::trim)
+            .filter(|member| !member.is_empty())
+            .map(CompactString::new)
+            .collect();
+
+        if members.is_empty() {
+            return Err(GmtParserError::MissingColumns {
+                line: line_index + 1,
+                file_path: gmt_file_path.clone(),
+            });
+        }
+
+        gene_sets.push(GeneSet {
+            name: name.trim().to_string(),
+            description: description.trim().to_string(),
+            members,
+        });
+    }
+
+    Ok(gene_sets)
+}
@@ -1,3 +1,4 @@
 pub mod obo_parser;
 pub mod background_parser;
-pub mod study_parser;
\ No newline at end of file
+pub mod study_parser;
+pub mod gmt_parser;
\ No newline at end of file
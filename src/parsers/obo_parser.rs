@@ -1,13 +1,15 @@
 use std::collections::VecDeque;
 use rustc_hash::FxHashMap;
 use std::fs::File;
-use std::io::{BufReader, BufRead, Error};
-use std::mem;
+use std::io::{Error, ErrorKind};
 use std::path::PathBuf;
 use ucfirst::ucfirst;
 use daggy::{Dag, NodeIndex, Walker};
 use regex::Regex;
 use lazy_static::lazy_static;
+use memchr::memchr_iter;
+use memmap2::Mmap;
+use rayon::prelude::*;
 use strum_macros::EnumIter; 
 use thiserror::Error;
 
@@ -28,6 +30,43 @@ pub enum NameSpace {
     CellularComponent,
 }
 
+impl NameSpace {
+    // Namespace as written in OBO files, e.g. "biological_process".
+    pub fn obo_name(&self) -> &'static str {
+        match self {
+            NameSpace::BiologicalProcess => "biological_process",
+            NameSpace::MolecularFunction => "molecular_function",
+            NameSpace::CellularComponent => "cellular_component",
+        }
+    }
+
+    // Used in result columns, e.g. "Biological Process".
+    pub fn label(&self) -> &'static str {
+        match self {
+            NameSpace::BiologicalProcess => "Biological Process",
+            NameSpace::MolecularFunction => "Molecular Function",
+            NameSpace::CellularComponent => "Cellular Component",
+        }
+    }
+
+    // Used in output file and directory names, e.g. "Biological_Process".
+    pub fn file_label(&self) -> &'static str {
+        match self {
+            NameSpace::BiologicalProcess => "Biological_Process",
+            NameSpace::MolecularFunction => "Molecular_Function",
+            NameSpace::CellularComponent => "Cellular_Component",
+        }
+    }
+
+    pub fn abbreviation(&self) -> &'static str {
+        match self {
+            NameSpace::BiologicalProcess => "BP",
+            NameSpace::MolecularFunction => "MF",
+            NameSpace::CellularComponent => "CC",
+        }
+    }
+}
+
 #[derive(Default, Debug, Clone)]
 pub struct OboTerm {
     pub name: String,
@@ -35,6 +74,8 @@ pub struct OboTerm {
     pub definition: String,
     pub is_obsolete: bool,
     pub relationships: FxHashMap<u32, Relationship>,
+    pub disjoint_from: Vec<GOTermID>,
+    pub replaced_by: Vec<GOTermID>,
 }
 impl OboTerm {
     pub fn new() -> Self {
@@ -44,6 +85,8 @@ impl OboTerm {
             definition: String::with_capacity(350),
             is_obsolete: false,
             relationships: FxHashMap::default(),
+            disjoint_from: Vec::new(),
+            replaced_by: Vec::new(),
         }
     }
 }
@@ -70,6 +113,12 @@ pub enum OboParserError {
     InvalidFileExtension {
         filename: String, 
         extension_found: String 
+    },
+
+    #[error("{missing_count} relationships in the OBO file point to GO terms missing from the ontology, more than the allowed {max_missing}. The file may be truncated or malformed.")]
+    TooManyMissingReferences {
+        missing_count: usize,
+        max_missing: usize
     }
 }
 
@@ -121,6 +170,15 @@ pub fn parse_relationship(input: &str, regex: &Regex) -> Option<(u32, Relationsh
 }
 
 pub fn parse_obo_file(obo_file_path: &PathBuf) -> Result<OboMap, OboParserError> {
+    parse_obo_terms(obo_file_path, false)
+}
+
+// Obsolete terms are left out of the ontology graph, so they are only kept when looking terms up by ID.
+pub fn parse_obo_file_with_obsolete(obo_file_path: &PathBuf) -> Result<OboMap, OboParserError> {
+    parse_obo_terms(obo_file_path, true)
+}
+
+fn parse_obo_terms(obo_file_path: &PathBuf, include_obsolete: bool) -> Result<OboMap, OboParserError> {
 
     if !obo_file_path.exists() {
         return Err(OboParserError::FileNotFound { 
@@ -145,108 +203,159 @@ pub fn parse_obo_file(obo_file_path: &PathBuf) -> Result<OboMap, OboParserError>
         }
     }
 
+    let obo = File::open(obo_file_path)?;
+    // SAFETY: the file is only read, and is expected not to change while it is being parsed.
+    let mmap = unsafe { Mmap::map(&obo)? };
+    let contents = std::str::from_utf8(&mmap)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+
+    // Terms span several lines, so the file is first split at the `[Term]` headers and the
+    // term blocks are then parsed in parallel.
+    let mut line_start = 0;
+    let mut term_starts: Vec<usize> = Vec::new();
+    for line_end in memchr_iter(b'\n', contents.as_bytes()).chain(std::iter::once(contents.len())) {
+        if contents[line_start..line_end].trim_end_matches('\r') == "[Term]" {
+            term_starts.push(line_start);
+        }
+        line_start = line_end + 1;
+    }
+
+    let term_blocks: Vec<&str> = term_starts
+        .iter()
+        .enumerate()
+        .map(|(i, &start)| {
+            let end = term_starts.get(i + 1).copied().unwrap_or(contents.len());
+            &contents[start..end]
+        })
+        .collect();
+
     let mut obo_terms: FxHashMap<u32, OboTerm> = FxHashMap::with_capacity_and_hasher(
-        41_000, 
+        term_blocks.len(),
         rustc_hash::FxBuildHasher::default()
     );
+    obo_terms.par_extend(
+        term_blocks
+            .par_iter()
+            .filter_map(|block| parse_term_block(block))
+            .filter(|(_, term)| !term.is_obsolete || include_obsolete)
+    );
 
-    let obo = File::open(obo_file_path)?;
-    let reader = BufReader::with_capacity(3000 * 1024, obo);
+    Ok(obo_terms)
+}
 
-    let mut new_term = false;
+// Parses one `[Term]` stanza, up to the first blank line. Returns `None` if the term has no ID.
+fn parse_term_block(block: &str) -> Option<(u32, OboTerm)> {
     let mut current_term = OboTerm::new();
     let mut current_id: u32 = 0;
-    let mut obsolete_term: bool = false;
 
-    for line in reader.lines() {
-        let line = line?;
-        
-        if line == "[Term]" {
-            new_term = true;
-            current_term = OboTerm::new();
-            obsolete_term = false; 
-            current_id = 0; 
-
-        } else if new_term {
-            match line.trim() {
-                line if line.starts_with("id: ") => {
-                    let id: u32 = line.split("GO:")
-                        .nth(1)
-                        .expect("No GO: ID found")
-                        .parse()
-                        .expect("Invalid GO number");
-                    current_id = id;
-                },
-                line if line.starts_with("name: ") => {
-                    let name = ucfirst(line.split(": ")
-                        .nth(1)
-                        .expect("No name found"));
-                    current_term.name = name;
-                },
-                line if line.starts_with("namespace: ") => {
-                    let namespace = match line.split(": ")
-                        .nth(1)
-                        .expect("No namespace found") {
-                            "biological_process" => NameSpace::BiologicalProcess,
-                            "molecular_function" => NameSpace::MolecularFunction,
-                            "cellular_component" => NameSpace::CellularComponent,
-                            _ => panic!("Invalid namespace"),
-                    };
-                    current_term.namespace = namespace;
-                },
-                line if line.starts_with("def: ") => {
-                    let definition =  line.split("\"")
-                        .nth(1)
-                        .expect("No definition found");
-
-                    current_term.definition = definition.to_string();
-                },
-                line if line.starts_with("is_a: ") => {
-                    let parent_id: u32 = parse_is_a(&line)
-                        .expect("No parent ID found");
-                    current_term.relationships.insert(parent_id, Relationship::IsA);
-                },
-                line if line.starts_with("is_obsolete: ") => {
-                    let is_obsolete = match line.split(": ")
-                        .nth(1)
-                        .expect("Wrong is_obsolete format") {
-                            "true" => true,
-                            _ => false,
-                    };
-                    obsolete_term=is_obsolete;
-                    current_term.is_obsolete = is_obsolete;
-                },
-                line if line.starts_with("relationship: ") => {
-                    if let Some((id, relationship)) = parse_relationship(&line, &RELATIONSHIP_REGEX) {
+    for line in block.lines().skip(1) {
+        match line.trim() {
+            line if line.starts_with("id: ") => {
+                let id: u32 = line.split("GO:")
+                    .nth(1)
+                    .expect("No GO: ID found")
+                    .parse()
+                    .expect("Invalid GO number");
+                current_id = id;
+            },
+            line if line.starts_with("name: ") => {
+                let name = ucfirst(line.split(": ")
+                    .nth(1)
+                    .expect("No name found"));
+                current_term.name = name;
+            },
+            line if line.starts_with("namespace: ") => {
+                let namespace = match line.split(": ")
+                    .nth(1)
+                    .expect("No namespace found") {
+                        "biological_process" => NameSpace::BiologicalProcess,
+                        "molecular_function" => NameSpace::MolecularFunction,
+                        "cellular_component" => NameSpace::CellularComponent,
+                        _ => panic!("Invalid namespace"),
+                };
+                current_term.namespace = namespace;
+            },
+            line if line.starts_with("def: ") => {
+                let definition =  line.split("\"")
+                    .nth(1)
+                    .expect("No definition found");
+
+                current_term.definition = definition.to_string();
+            },
+            line if line.starts_with("is_a: ") => {
+                let parent_id: u32 = parse_is_a(line)
+                    .expect("No parent ID found");
+                current_term.relationships.insert(parent_id, Relationship::IsA);
+            },
+            line if line.starts_with("is_obsolete: ") => {
+                current_term.is_obsolete = line.split(": ")
+                    .nth(1)
+                    .expect("Wrong is_obsolete format") == "true";
+            },
+            line if line.starts_with("relationship: ") => {
+                if let Some((id, relationship)) = parse_relationship(line, &RELATIONSHIP_REGEX) {
+                    current_term.relationships.insert(id, relationship);
+                }
+            },
+            line if line.starts_with("intersection_of: ") => {
+                if !line.contains("intersection_of: GO:") {
+                    if let Some((id, relationship)) = parse_relationship(line, &INTERSECTION_REGEX) {
                         current_term.relationships.insert(id, relationship);
                     }
-                },
-                line if line.starts_with("intersection_of: ") => {
-                    if !line.contains("intersection_of: GO:") {
-                        if let Some((id, relationship)) = parse_relationship(&line, &INTERSECTION_REGEX) {
-                            current_term.relationships.insert(id, relationship);
-                        }
-                    } else {
-                        continue;
-                    }
-                    
                 }
-                line if line.is_empty() => {
-                    new_term = false;
-                    if current_id != 0 && obsolete_term == false {  
-                        obo_terms.insert(current_id, mem::take(&mut current_term));
-                    }
-                },
-                _ => (), 
             }
+            line if line.starts_with("disjoint_from: ") => {
+                if let Some(disjoint_id) = parse_is_a(line) {
+                    current_term.disjoint_from.push(disjoint_id);
+                }
+            },
+            line if line.starts_with("replaced_by: ") => {
+                if let Some(replacement_id) = parse_is_a(line) {
+                    current_term.replaced_by.push(replacement_id);
+                }
+            },
+            line if line.is_empty() => break,
+            _ => (),
         }
     }
-    
-    if new_term && current_id != 0 && !obsolete_term {
-        obo_terms.insert(current_id, mem::take(&mut current_term));
+
+    (current_id != 0).then_some((current_id, current_term))
+}
+
+// Relationship targets absent from the map (e.g. in a truncated file) are left out of the ontology graph.
+// Up to 10 of them are listed in a warning; more than `max_missing_references` is treated as an error.
+pub fn validate_references(obo_map: &OboMap, max_missing_references: usize) -> Result<(), OboParserError> {
+    let mut missing_references: Vec<(GOTermID, GOTermID)> = obo_map
+        .iter()
+        .flat_map(|(term_id, term)| {
+            term.relationships
+                .keys()
+                .filter(|target_id| !obo_map.contains_key(target_id))
+                .map(move |target_id| (*term_id, *target_id))
+        })
+        .collect();
+
+    if missing_references.is_empty() {
+        return Ok(());
     }
-    
-    Ok(obo_terms)
+    missing_references.sort_unstable();
+
+    eprintln!("Warning: {} relationships point to GO terms missing from the ontology:", missing_references.len());
+    for (term_id, target_id) in missing_references.iter().take(10) {
+        eprintln!("  GO:{:07} -> GO:{:07}", term_id, target_id);
+    }
+    if missing_references.len() > 10 {
+        eprintln!("  ... and {} more", missing_references.len() - 10);
+    }
+
+    if missing_references.len() > max_missing_references {
+        return Err(OboParserError::TooManyMissingReferences {
+            missing_count: missing_references.len(),
+            max_missing: max_missing_references
+        });
+    }
+
+    Ok(())
 }
 
 pub fn build_ontology_graph(obo_map: &OboMap) -> Result<(OntologyGraph, FxHashMap<u32, NodeIndex>), Error> {
@@ -263,7 +372,7 @@ pub fn build_ontology_graph(obo_map: &OboMap) -> Result<(OntologyGraph, FxHashMa
         let source_index = go_id_to_node_index[node_id];
         
         for (parent_id, relationship_type) in term.relationships.iter() {
-            let target_index = go_id_to_node_index[parent_id];
+            let Some(&target_index) = go_id_to_node_index.get(parent_id) else { continue };
             ontology_graph
                 .add_edge(target_index, source_index, relationship_type.clone())
                 .unwrap();
@@ -321,3 +430,108 @@ pub fn assign_levels_from_roots(
 
     (term_to_level, level_to_terms)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    const SYNTHETIC_OBO: &str = "format-version: 1.2
+
+[Term]
+id: GO:0000001
+name: parent process
+namespace: biological_process
+def: \"A synthetic parent term.\" [GOC:test]
+
+[Term]
+id: GO:0000002
+name: child process
+namespace: biological_process
+def: \"A synthetic child term.\" [GOC:test]
+intersection_of: GO:0000001 ! parent process
+intersection_of: part_of GO:0000003 ! synthetic component
+disjoint_from: GO:0000004 ! other process
+
+[Term]
+id: GO:0000003
+name: synthetic component
+namespace: cellular_component
+def: \"A synthetic component.\" [GOC:test]
+
+[Term]
+id: GO:0000004
+name: other process
+namespace: biological_process
+def: \"A synthetic unrelated term.\" [GOC:test]
+";
+
+    fn parse_synthetic_obo(test_name: &str) -> OboMap {
+        let obo_path = std::env::temp_dir().join(format!("taxago_{}_{}.obo", test_name, std::process::id()));
+        fs::write(&obo_path, SYNTHETIC_OBO).unwrap();
+        let obo_map = parse_obo_file(&obo_path);
+        fs::remove_file(&obo_path).unwrap();
+        obo_map.unwrap()
+    }
+
+    #[test]
+    fn intersection_of_adds_relationships() {
+        let obo_map = parse_synthetic_obo("intersection_of");
+        let child = &obo_map[&2];
+
+        assert!(matches!(child.relationships.get(&3), Some(Relationship::PartOf)));
+        // The genus of a logical definition is not an additional parent.
+        assert!(!child.relationships.contains_key(&1));
+
+        let (ontology_graph, go_id_to_node_index) = build_ontology_graph(&obo_map).unwrap();
+        assert!(ontology_graph.find_edge(go_id_to_node_index[&3], go_id_to_node_index[&2]).is_some());
+    }
+
+    #[test]
+    fn disjoint_from_is_stored_without_an_edge() {
+        let obo_map = parse_synthetic_obo("disjoint_from");
+
+        assert_eq!(obo_map[&2].disjoint_from, vec![4]);
+        assert!(!obo_map[&2].relationships.contains_key(&4));
+        assert!(obo_map[&4].disjoint_from.is_empty());
+    }
+
+    // Term 2 points to the missing terms 8 and 9, as in a truncated OBO file.
+    fn obo_map_with_missing_references() -> OboMap {
+        let mut obo_map = parse_synthetic_obo("missing_references");
+        let child = obo_map.get_mut(&2).unwrap();
+        child.relationships.insert(8, Relationship::IsA);
+        child.relationships.insert(9, Relationship::PartOf);
+        obo_map
+    }
+
+    #[test]
+    fn validate_references_accepts_a_complete_ontology() {
+        let obo_map = parse_synthetic_obo("complete_references");
+
+        assert!(validate_references(&obo_map, 0).is_ok());
+    }
+
+    #[test]
+    fn validate_references_allows_missing_targets_up_to_the_limit() {
+        let obo_map = obo_map_with_missing_references();
+
+        assert!(validate_references(&obo_map, 2).is_ok());
+        assert!(matches!(
+            validate_references(&obo_map, 1),
+            Err(OboParserError::TooManyMissingReferences { missing_count: 2, max_missing: 1 })
+        ));
+    }
+
+    #[test]
+    fn build_ontology_graph_skips_edges_to_missing_terms() {
+        let obo_map = obo_map_with_missing_references();
+
+        let (ontology_graph, go_id_to_node_index) = build_ontology_graph(&obo_map).unwrap();
+
+        assert_eq!(ontology_graph.node_count(), obo_map.len());
+        assert_eq!(ontology_graph.edge_count(), 1);
+        assert!(!go_id_to_node_index.contains_key(&8));
+        assert!(ontology_graph.find_edge(go_id_to_node_index[&3], go_id_to_node_index[&2]).is_some());
+    }
+}
@@ -1,6 +1,6 @@
 use rustc_hash::{FxHashMap, FxHashSet};
 use std::fs::{read_dir, File};
-use std::io::{BufRead, BufReader, ErrorKind};
+use std::io::{BufRead, BufReader, ErrorKind, Read};
 use std::path::PathBuf;
 use std::sync::Arc;
 use csv::ReaderBuilder;
@@ -14,7 +14,8 @@ pub struct StudyPop {
     pub taxon_map: FxHashMap<TaxonID, FxHashSet<Protein>>,
     pub taxon_protein_count: FxHashMap<TaxonID, usize>,
     pub go_term_count: FxHashMap<TaxonID, GOTermCount>,
-    pub go_term_to_protein_set: FxHashMap<TaxonID, FxHashMap<GOTermID, FxHashSet<Protein>>>
+    pub go_term_to_protein_set: FxHashMap<TaxonID, FxHashMap<GOTermID, FxHashSet<Protein>>>,
+    pub protein_scores: FxHashMap<TaxonID, FxHashMap<Protein, f64>>
 }
 
 #[derive(Error, Debug)]
@@ -22,7 +23,7 @@ pub enum StudyPopError {
     #[error("File not found: {0}")]
     FileNotFound(PathBuf),
 
-    #[error("Invalid file extension for file: {0}. Expected .csv, .fa, or .fasta.")]
+    #[error("Invalid file extension for file: {0}. Expected .csv, .tsv, .fa, or .fasta, or a file whose content is recognised as FASTA or CSV.")]
     InvalidFileExtension(PathBuf),
 
     #[error("FASTA file ({0}) must start with a '>' header line.")]
@@ -30,8 +31,125 @@ pub enum StudyPopError {
 
     #[error("FASTA file ({0}) contains multiple '>' taxon ID header lines. Only one is permitted.")]
     FastaMultipleHeaders(PathBuf),
+
+    #[error("Protein ID '{0}' contains whitespace and was split into separate protein IDs.")]
+    InvalidProteinId(String),
+
+    #[error("Study population list contains a directory: {0}. Comma-separated lists must only contain files.")]
+    DirectoryInFileList(PathBuf),
+
+    #[error("TSV file ({file_path}) is missing the required '{column}' column.")]
+    TsvMissingRequiredColumn {
+        column: String,
+        file_path: PathBuf,
+    },
+
+    #[error("InterPro TSV file ({file_path}) has no row with the '{column}' column. Run InterProScan with --goterms.")]
+    InterproTsvMissingColumn {
+        column: String,
+        file_path: PathBuf,
+    },
+
+    #[error("InterPro TSV file name ({0}) must start with the taxon ID of its proteins, e.g. 9606_interpro.tsv.")]
+    InterproTsvMissingTaxonId(PathBuf),
+
+    #[error("Ranked study file ({file_path}) is missing the required '{column}' column.")]
+    RankedMissingRequiredColumn {
+        column: String,
+        file_path: PathBuf,
+    },
+
+    #[error("Ranked study file ({file_path}) contains an invalid score for protein {protein}: '{score}'.")]
+    RankedInvalidScore {
+        protein: String,
+        score: String,
+        file_path: PathBuf,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StudyFormat {
+    Fasta,
+    Csv,
+    Tsv,
+}
+
+// Guesses the format from the first 1 KB: FASTA files start with a '>' header and CSV files have
+// comma-separated fields on their first line.
+pub fn detect_file_format(path: &PathBuf) -> Option<StudyFormat> {
+    let mut buffer = [0u8; 1024];
+    let bytes_read = File::open(path).ok()?.read(&mut buffer).ok()?;
+    let content = String::from_utf8_lossy(&buffer[..bytes_read]);
+    let content = content.trim_start();
+
+    if content.starts_with('>') {
+        return Some(StudyFormat::Fasta);
+    }
+    match content.lines().next() {
+        Some(first_line) if first_line.contains(',') => Some(StudyFormat::Csv),
+        _ => None,
+    }
+}
+
+// The extension decides the format; files with a missing or unrecognised extension fall back to content detection.
+fn study_file_format(path: &PathBuf) -> Option<StudyFormat> {
+    match path.extension().and_then(|s| s.to_str()) {
+        Some("csv") => Some(StudyFormat::Csv),
+        Some("tsv") => Some(StudyFormat::Tsv),
+        Some("fa") | Some("fasta") => Some(StudyFormat::Fasta),
+        _ => {
+            let detected_format = detect_file_format(path);
+            if let Some(format) = detected_format {
+                eprintln!("[INFO] Detected {:?} format from the content of study file: {}", format, path.display());
+            }
+            detected_format
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum CompatibilityError {
+    #[error("No taxon of the study population has a background population. Study taxon IDs without background: {}", join_taxon_ids(.missing_taxa))]
+    NoOverlap { missing_taxa: Vec<TaxonID> },
+
+    #[error("Some taxa of the study population have no background population. Study taxon IDs without background: {}", join_taxon_ids(.missing_taxa))]
+    MissingTaxa { missing_taxa: Vec<TaxonID> },
+}
+
+fn join_taxon_ids(taxon_ids: &[TaxonID]) -> String {
+    taxon_ids.iter().map(|taxon_id| taxon_id.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+// Every study taxon needs a background population; otherwise its enrichment results would silently be empty.
+pub fn validate_taxon_overlap(study: &StudyPop, background: &BackgroundPop) -> Result<(), CompatibilityError> {
+    let mut missing_taxa: Vec<TaxonID> = study.taxon_map
+        .keys()
+        .filter(|taxon_id| !background.taxon_protein_count.contains_key(taxon_id))
+        .copied()
+        .collect();
+
+    if missing_taxa.is_empty() {
+        return Ok(());
+    }
+    missing_taxa.sort_unstable();
+
+    if missing_taxa.len() == study.taxon_map.len() {
+        Err(CompatibilityError::NoOverlap { missing_taxa })
+    } else {
+        Err(CompatibilityError::MissingTaxa { missing_taxa })
+    }
 }
 
+const TSV_ENTRY_COLUMN: &str = "Entry";
+const TSV_ORGANISM_ID_COLUMN: &str = "Organism ID";
+// InterProScan TSV output has no header; GO annotations are the 14th column and only present with --goterms.
+const INTERPRO_MIN_COLUMNS: usize = 11;
+const INTERPRO_GO_COLUMN_INDEX: usize = 13;
+const INTERPRO_GO_COLUMN: &str = "GO annotations";
+const RANKED_TAXON_COLUMN: &str = "taxon_id";
+const RANKED_PROTEIN_COLUMN: &str = "protein_id";
+const RANKED_SCORE_COLUMN: &str = "score";
+
 type BoxedResult<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync + 'static>>;
 
 impl StudyPop {
@@ -80,6 +198,133 @@ impl StudyPop {
             return Ok(None);
         }
 
+        Ok(Some(Self::from_taxon_map(taxon_map, protein_to_go)))
+    }
+
+    pub fn from_tsv_file(
+        tsv_file: &PathBuf,
+        protein_to_go: &FxHashMap<TaxonID, ProteinToGO>,
+    ) -> BoxedResult<Option<Self>> {
+        let file = match File::open(tsv_file) {
+            Ok(f) => f,
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                return Err(Box::new(StudyPopError::FileNotFound(tsv_file.clone())));
+            }
+            Err(e) => return Err(Box::new(e)),
+        };
+        let mut tsv_reader = ReaderBuilder::new()
+            .delimiter(b'\t')
+            .trim(csv::Trim::All)
+            .flexible(true)
+            .has_headers(true)
+            .from_reader(file);
+
+        let (entry_index, organism_index) = find_tsv_columns(&mut tsv_reader, tsv_file)?;
+
+        let mut taxon_map: FxHashMap<TaxonID, FxHashSet<Protein>> = FxHashMap::default();
+
+        for result in tsv_reader.records() {
+            let record = result.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync + 'static>)?;
+
+            let (Some(protein_str), Some(organism_str)) = (record.get(entry_index), record.get(organism_index)) else {
+                continue;
+            };
+            if protein_str.is_empty() {
+                continue;
+            }
+            let Ok(taxon_id) = organism_str.parse::<TaxonID>() else {
+                continue;
+            };
+
+            taxon_map
+                .entry(taxon_id)
+                .or_insert_with(FxHashSet::default)
+                .insert(Arc::new(CompactString::new(protein_str)));
+        }
+
+        if taxon_map.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(Self::from_taxon_map(taxon_map, protein_to_go)))
+    }
+
+    // InterPro TSV files already carry GO annotations, so no background protein-to-GO mapping is needed.
+    // Proteins without any GO annotation still count towards the study population size.
+    pub fn from_interpro_tsv(
+        tsv_file: &PathBuf,
+        taxon_id: TaxonID,
+    ) -> BoxedResult<Option<Self>> {
+        let file = match File::open(tsv_file) {
+            Ok(f) => f,
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                return Err(Box::new(StudyPopError::FileNotFound(tsv_file.clone())));
+            }
+            Err(e) => return Err(Box::new(e)),
+        };
+        let mut tsv_reader = ReaderBuilder::new()
+            .delimiter(b'\t')
+            .trim(csv::Trim::All)
+            .flexible(true)
+            .has_headers(false)
+            .from_reader(file);
+
+        let mut proteins: FxHashSet<Protein> = FxHashSet::default();
+        let mut go_term_to_proteins: FxHashMap<GOTermID, FxHashSet<Protein>> = FxHashMap::default();
+        let mut has_go_column = false;
+
+        for result in tsv_reader.records() {
+            let record = result.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync + 'static>)?;
+
+            let Some(protein_str) = record.get(0).filter(|protein| !protein.is_empty()) else {
+                continue;
+            };
+            let protein = Arc::new(CompactString::new(protein_str));
+            proteins.insert(Arc::clone(&protein));
+
+            let Some(go_annotations) = record.get(INTERPRO_GO_COLUMN_INDEX) else {
+                continue;
+            };
+            has_go_column = true;
+
+            for go_term in go_annotations.split(['|', ';']).filter_map(parse_interpro_go_term) {
+                go_term_to_proteins
+                    .entry(go_term)
+                    .or_insert_with(FxHashSet::default)
+                    .insert(Arc::clone(&protein));
+            }
+        }
+
+        if proteins.is_empty() {
+            return Ok(None);
+        }
+        if !has_go_column {
+            return Err(Box::new(StudyPopError::InterproTsvMissingColumn {
+                column: INTERPRO_GO_COLUMN.to_string(),
+                file_path: tsv_file.clone(),
+            }));
+        }
+
+        let go_term_count: GOTermCount = go_term_to_proteins
+            .iter()
+            .map(|(&go_term, term_proteins)| (go_term, term_proteins.len()))
+            .collect();
+
+        let mut study_pop = StudyPop::default();
+        study_pop.taxon_protein_count.insert(taxon_id, proteins.len());
+        study_pop.taxon_map.insert(taxon_id, proteins);
+        if !go_term_count.is_empty() {
+            study_pop.go_term_count.insert(taxon_id, go_term_count);
+            study_pop.go_term_to_protein_set.insert(taxon_id, go_term_to_proteins);
+        }
+
+        Ok(Some(study_pop))
+    }
+
+    fn from_taxon_map(
+        taxon_map: FxHashMap<TaxonID, FxHashSet<Protein>>,
+        protein_to_go: &FxHashMap<TaxonID, ProteinToGO>,
+    ) -> Self {
         let mut taxon_protein_count = FxHashMap::with_capacity_and_hasher(
             taxon_map.len(),
             rustc_hash::FxBuildHasher::default()
@@ -111,28 +356,71 @@ impl StudyPop {
             }
         }
 
-        Ok(Some(Self {
+        Self {
             taxon_map,
             taxon_protein_count,
             go_term_count,
-            go_term_to_protein_set
-        }))
+            go_term_to_protein_set,
+            protein_scores: FxHashMap::default()
+        }
+    }
+
+    pub fn from_ranked_csv_file(
+        csv_file: &PathBuf,
+        protein_to_go: &FxHashMap<TaxonID, ProteinToGO>,
+    ) -> BoxedResult<Option<Self>> {
+        let protein_scores = read_ranked_csv(csv_file)?;
+
+        if protein_scores.is_empty() {
+            return Ok(None);
+        }
+
+        let taxon_map: FxHashMap<TaxonID, FxHashSet<Protein>> = protein_scores
+            .iter()
+            .map(|(&taxon_id, scores)| (taxon_id, scores.keys().cloned().collect()))
+            .collect();
+
+        let mut study_pop = Self::from_taxon_map(taxon_map, protein_to_go);
+        study_pop.protein_scores = protein_scores;
+
+        Ok(Some(study_pop))
     }
 
     pub fn read_study_pop(
         study_data_path: &PathBuf,
         protein_to_go: &FxHashMap<TaxonID, ProteinToGO>
     ) -> BoxedResult<Option<Self>> {
+        if let Some(study_files) = split_study_file_list(study_data_path)? {
+            let mut study_pop: Option<StudyPop> = None;
+
+            for study_file in &study_files {
+                if let Some(file_study_pop) = StudyPop::read_study_pop(study_file, protein_to_go)? {
+                    match study_pop.as_mut() {
+                        Some(existing) => existing.merge(file_study_pop),
+                        None => study_pop = Some(file_study_pop),
+                    }
+                }
+            }
+
+            return Ok(study_pop);
+        }
+
         if !study_data_path.exists() {
             return Err(Box::new(StudyPopError::FileNotFound(study_data_path.clone())));
         }
 
         if study_data_path.is_file() {
-            match study_data_path.extension().and_then(|s| s.to_str()) {
-                Some("csv") => {
+            match study_file_format(study_data_path) {
+                Some(StudyFormat::Csv) => {
                     return StudyPop::from_csv_file(study_data_path, protein_to_go);
                 }
-                Some("fa") | Some("fasta") => {
+                Some(StudyFormat::Tsv) if is_interpro_tsv(study_data_path) => {
+                    return StudyPop::from_interpro_tsv(study_data_path, interpro_taxon_id(study_data_path)?);
+                }
+                Some(StudyFormat::Tsv) => {
+                    return StudyPop::from_tsv_file(study_data_path, protein_to_go);
+                }
+                Some(StudyFormat::Fasta) => {
                     match parse_fasta_file(study_data_path, protein_to_go)? {
                         Some((taxon_id, protein_set, go_term_count_map, go_term_to_proteins)) => {
                             let mut taxon_map = FxHashMap::default();
@@ -150,12 +438,13 @@ impl StudyPop {
                                 taxon_protein_count,
                                 go_term_count,
                                 go_term_to_protein_set,
+                                protein_scores: FxHashMap::default(),
                             }));
                         }
                         None => return Ok(None), 
                     }
                 }
-                _ => return Err(Box::new(StudyPopError::InvalidFileExtension(study_data_path.clone()))),
+                None => return Err(Box::new(StudyPopError::InvalidFileExtension(study_data_path.clone()))),
             }
         } else if study_data_path.is_dir() {
             let results: Vec<_> = read_dir(study_data_path)
@@ -193,13 +482,55 @@ impl StudyPop {
                 taxon_map,
                 taxon_protein_count,
                 go_term_count,
-                go_term_to_protein_set
+                go_term_to_protein_set,
+                protein_scores: FxHashMap::default()
             }));
         } else {
              return Err(Box::new(std::io::Error::new(ErrorKind::Other, format!("Path is not a file or directory: {}", study_data_path.display()))));
         }
     }
 
+    pub fn filter_by_background_population(
+        &mut self,
+        taxon_ids: &FxHashSet<TaxonID>,
+        background_pop: &BackgroundPop
+    ) {
+        for taxon_id in taxon_ids {
+            let Some(background_terms) = background_pop.go_term_count.get(taxon_id) else { continue };
+
+            if let Some(count_map) = self.go_term_count.get_mut(taxon_id) {
+                count_map.retain(|term_id, _| background_terms.contains_key(term_id));
+            }
+
+            if let Some(term_map) = self.go_term_to_protein_set.get_mut(taxon_id) {
+                term_map.retain(|term_id, _| background_terms.contains_key(term_id));
+            }
+        }
+    }
+
+    fn merge(&mut self, other: StudyPop) {
+        for (taxon_id, proteins) in other.taxon_map {
+            let taxon_proteins = self.taxon_map.entry(taxon_id).or_default();
+            taxon_proteins.extend(proteins);
+            self.taxon_protein_count.insert(taxon_id, taxon_proteins.len());
+        }
+
+        for (taxon_id, go_term_proteins) in other.go_term_to_protein_set {
+            let taxon_go_term_proteins = self.go_term_to_protein_set.entry(taxon_id).or_default();
+            let taxon_go_term_count = self.go_term_count.entry(taxon_id).or_default();
+
+            for (go_term, proteins) in go_term_proteins {
+                let term_proteins = taxon_go_term_proteins.entry(go_term).or_default();
+                term_proteins.extend(proteins);
+                taxon_go_term_count.insert(go_term, term_proteins.len());
+            }
+        }
+
+        for (taxon_id, scores) in other.protein_scores {
+            self.protein_scores.entry(taxon_id).or_default().extend(scores);
+        }
+    }
+
     pub fn filter_by_threshold(
         &mut self,
         taxon_ids: &FxHashSet<TaxonID>,
@@ -301,6 +632,7 @@ pub fn parse_fasta_file(
     }
 
     let taxon_id = taxon_id_from_file.unwrap(); 
+    let mut proteins_read: usize = 0;
 
     while let Some(line_result) = lines_iter.next() {
         let line = line_result.map_err(Box::new)?;
@@ -314,7 +646,19 @@ pub fn parse_fasta_file(
             return Err(Box::new(StudyPopError::FastaMultipleHeaders(fasta_file_path.clone())));
         }
 
-        protein_set.insert(Arc::new(CompactString::new(trimmed_line)));
+        if trimmed_line.contains(char::is_whitespace) {
+            eprintln!("Warning: taxon {}: {}", taxon_id, StudyPopError::InvalidProteinId(trimmed_line.to_string()));
+        }
+
+        for protein_id in trimmed_line.split_whitespace() {
+            proteins_read += 1;
+            protein_set.insert(Arc::new(CompactString::new(protein_id)));
+        }
+    }
+
+    let duplicate_count = proteins_read - protein_set.len();
+    if duplicate_count > 0 {
+        eprintln!("Warning: taxon {}: {} duplicate protein entries were deduplicated from FASTA file", taxon_id, duplicate_count);
     }
 
     let mut final_go_term_count: GOTermCount = FxHashMap::default();
@@ -395,9 +739,47 @@ fn extract_taxon_id_from_fasta(
     Ok(taxon_id_to_return)
 }
 
+fn split_study_file_list(
+    study_data: &PathBuf,
+) -> BoxedResult<Option<Vec<PathBuf>>> {
+    let Some(study_data_str) = study_data.to_str() else { return Ok(None) };
+    if !study_data_str.contains(',') {
+        return Ok(None);
+    }
+
+    let study_files: Vec<PathBuf> = study_data_str
+        .split(',')
+        .map(str::trim)
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .collect();
+
+    for study_file in &study_files {
+        if !study_file.exists() {
+            return Err(Box::new(StudyPopError::FileNotFound(study_file.clone())));
+        }
+        if study_file.is_dir() {
+            return Err(Box::new(StudyPopError::DirectoryInFileList(study_file.clone())));
+        }
+        if study_file_format(study_file).is_none() {
+            return Err(Box::new(StudyPopError::InvalidFileExtension(study_file.clone())));
+        }
+    }
+
+    Ok(Some(study_files))
+}
+
 pub fn collect_taxon_ids(
     study_data: &PathBuf,
 ) -> BoxedResult<FxHashSet<TaxonID>> {
+    if let Some(study_files) = split_study_file_list(study_data)? {
+        let mut taxon_ids = FxHashSet::default();
+        for study_file in &study_files {
+            taxon_ids.extend(collect_taxon_ids(study_file)?);
+        }
+        return Ok(taxon_ids);
+    }
+
     if !study_data.exists() {
         return Err(Box::new(StudyPopError::FileNotFound(study_data.clone())));
     }
@@ -405,8 +787,8 @@ pub fn collect_taxon_ids(
     let mut taxon_ids = FxHashSet::default();
 
     if study_data.is_file() {
-        match study_data.extension().and_then(|s| s.to_str()) {
-            Some("csv") => {
+        match study_file_format(study_data) {
+            Some(StudyFormat::Csv) => {
                 let file = File::open(study_data).map_err(|e| {
                     if e.kind() == ErrorKind::NotFound { 
                         Box::new(StudyPopError::FileNotFound(study_data.clone()))
@@ -428,12 +810,37 @@ pub fn collect_taxon_ids(
                     .collect();
                 taxon_ids.extend(header_taxons);
             }
-            Some("fa") | Some("fasta") => {
+            Some(StudyFormat::Tsv) if is_interpro_tsv(study_data) => {
+                taxon_ids.insert(interpro_taxon_id(study_data)?);
+            }
+            Some(StudyFormat::Tsv) => {
+                let file = File::open(study_data).map_err(|e| {
+                    if e.kind() == ErrorKind::NotFound {
+                        Box::new(StudyPopError::FileNotFound(study_data.clone()))
+                    } else {
+                        Box::new(e) as Box<dyn std::error::Error + Send + Sync + 'static>
+                    }
+                })?;
+                let mut tsv_reader = ReaderBuilder::new()
+                    .delimiter(b'\t')
+                    .trim(csv::Trim::All)
+                    .flexible(true)
+                    .has_headers(true)
+                    .from_reader(file);
+                let (_, organism_index) = find_tsv_columns(&mut tsv_reader, study_data)?;
+                for result in tsv_reader.records() {
+                    let record = result.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync + 'static>)?;
+                    if let Some(Ok(taxon_id)) = record.get(organism_index).map(|id| id.parse::<TaxonID>()) {
+                        taxon_ids.insert(taxon_id);
+                    }
+                }
+            }
+            Some(StudyFormat::Fasta) => {
                 if let Some(id) = extract_taxon_id_from_fasta(study_data)? {
                     taxon_ids.insert(id);
                 }
             }
-            _ => return Err(Box::new(StudyPopError::InvalidFileExtension(study_data.clone()))),
+            None => return Err(Box::new(StudyPopError::InvalidFileExtension(study_data.clone()))),
         }
     } else if study_data.is_dir() {
         let entries: Vec<_> = read_dir(study_data)
@@ -460,6 +867,135 @@ pub fn collect_taxon_ids(
     Ok(taxon_ids)
 }
 
+pub fn collect_ranked_taxon_ids(
+    ranked_file: &PathBuf,
+) -> BoxedResult<FxHashSet<TaxonID>> {
+    Ok(read_ranked_csv(ranked_file)?.into_keys().collect())
+}
+
+fn read_ranked_csv(
+    csv_file: &PathBuf,
+) -> BoxedResult<FxHashMap<TaxonID, FxHashMap<Protein, f64>>> {
+    let file = match File::open(csv_file) {
+        Ok(f) => f,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            return Err(Box::new(StudyPopError::FileNotFound(csv_file.clone())));
+        }
+        Err(e) => return Err(Box::new(e)),
+    };
+    let mut csv_reader = ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .has_headers(true)
+        .from_reader(file);
+
+    let headers = csv_reader
+        .headers()
+        .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync + 'static>)?
+        .clone();
+
+    let find_column = |column: &str| {
+        headers
+            .iter()
+            .position(|header| header == column)
+            .ok_or_else(|| Box::new(StudyPopError::RankedMissingRequiredColumn {
+                column: column.to_string(),
+                file_path: csv_file.clone(),
+            }) as Box<dyn std::error::Error + Send + Sync + 'static>)
+    };
+
+    let taxon_index = find_column(RANKED_TAXON_COLUMN)?;
+    let protein_index = find_column(RANKED_PROTEIN_COLUMN)?;
+    let score_index = find_column(RANKED_SCORE_COLUMN)?;
+
+    let mut protein_scores: FxHashMap<TaxonID, FxHashMap<Protein, f64>> = FxHashMap::default();
+
+    for result in csv_reader.records() {
+        let record = result.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync + 'static>)?;
+
+        let (Some(taxon_str), Some(protein_str), Some(score_str)) = (
+            record.get(taxon_index),
+            record.get(protein_index),
+            record.get(score_index)
+        ) else { continue };
+
+        if protein_str.is_empty() {
+            continue;
+        }
+
+        let Ok(taxon_id) = taxon_str.parse::<TaxonID>() else { continue };
+
+        let score = match score_str.parse::<f64>() {
+            Ok(score) if score.is_finite() => score,
+            _ => return Err(Box::new(StudyPopError::RankedInvalidScore {
+                protein: protein_str.to_string(),
+                score: score_str.to_string(),
+                file_path: csv_file.clone(),
+            })),
+        };
+
+        protein_scores
+            .entry(taxon_id)
+            .or_default()
+            .insert(Arc::new(CompactString::new(protein_str)), score);
+    }
+
+    Ok(protein_scores)
+}
+
+// UniProt TSV exports start with a header row, while InterProScan output starts directly with a match
+// whose third column is the sequence length.
+fn is_interpro_tsv(tsv_file: &PathBuf) -> bool {
+    let Ok(file) = File::open(tsv_file) else { return false };
+    let mut first_line = String::new();
+    if BufReader::new(file).read_line(&mut first_line).is_err() {
+        return false;
+    }
+
+    let columns: Vec<&str> = first_line.trim_end().split('\t').collect();
+    columns.len() >= INTERPRO_MIN_COLUMNS
+        && !columns.contains(&TSV_ENTRY_COLUMN)
+        && columns[2].trim().parse::<usize>().is_ok()
+}
+
+// InterProScan output does not name the organism, so the taxon ID is taken from the leading digits of the file name.
+fn interpro_taxon_id(tsv_file: &PathBuf) -> BoxedResult<TaxonID> {
+    tsv_file
+        .file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| name.chars().take_while(char::is_ascii_digit).collect::<String>())
+        .and_then(|taxon_id| taxon_id.parse::<TaxonID>().ok())
+        .ok_or_else(|| Box::new(StudyPopError::InterproTsvMissingTaxonId(tsv_file.clone())) as Box<dyn std::error::Error + Send + Sync + 'static>)
+}
+
+// Annotations look like "GO:0005515" or, in newer InterProScan versions, "GO:0005515(InterPro)"; "-" means none.
+fn parse_interpro_go_term(annotation: &str) -> Option<GOTermID> {
+    let go_id = annotation.trim().strip_prefix("GO:")?;
+    let digits_end = go_id.find(|c: char| !c.is_ascii_digit()).unwrap_or(go_id.len());
+    go_id[..digits_end].parse::<GOTermID>().ok()
+}
+
+fn find_tsv_columns(
+    tsv_reader: &mut csv::Reader<File>,
+    tsv_file: &PathBuf,
+) -> BoxedResult<(usize, usize)> {
+    let headers = tsv_reader
+        .headers()
+        .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync + 'static>)?;
+
+    let find_column = |column: &str| {
+        headers
+            .iter()
+            .position(|header| header == column)
+            .ok_or_else(|| Box::new(StudyPopError::TsvMissingRequiredColumn {
+                column: column.to_string(),
+                file_path: tsv_file.clone(),
+            }) as Box<dyn std::error::Error + Send + Sync + 'static>)
+    };
+
+    Ok((find_column(TSV_ENTRY_COLUMN)?, find_column(TSV_ORGANISM_ID_COLUMN)?))
+}
+
 fn build_go_term_associations(
     proteins: &FxHashSet<Protein>,
     taxon_protein_to_go: &ProteinToGO,
@@ -485,4 +1021,101 @@ fn build_go_term_associations(
     }
 
     (go_term_count_map, go_term_to_protein_set_map)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_by_threshold_keeps_terms_with_exactly_threshold_proteins() {
+        let mut study_pop = StudyPop::default();
+        study_pop.go_term_count.insert(9606, [(1, 5), (2, 4)].into_iter().collect());
+        study_pop.go_term_to_protein_set.insert(9606, [(1, FxHashSet::default()), (2, FxHashSet::default())].into_iter().collect());
+
+        study_pop.filter_by_threshold(&[9606].into_iter().collect(), 5);
+
+        assert!(study_pop.go_term_count[&9606].contains_key(&1));
+        assert!(!study_pop.go_term_count[&9606].contains_key(&2));
+        assert!(study_pop.go_term_to_protein_set[&9606].contains_key(&1));
+        assert!(!study_pop.go_term_to_protein_set[&9606].contains_key(&2));
+    }
+
+    fn write_tsv(name: &str, contents: &str) -> PathBuf {
+        let tsv_file = std::env::temp_dir().join(format!("taxago_{}_{}.tsv", name, std::process::id()));
+        std::fs::write(&tsv_file, contents).unwrap();
+        tsv_file
+    }
+
+    fn background_protein_to_go(name: &str) -> FxHashMap<TaxonID, ProteinToGO> {
+        let background_dir = std::env::temp_dir().join(format!("taxago_{}_background_{}", name, std::process::id()));
+        std::fs::create_dir_all(&background_dir).unwrap();
+        std::fs::write(background_dir.join("9606_background.txt"), "P1\tGO:0008150\tIDA\nP1\tGO:0003674\tIEA\nP2\tGO:0008150\tIBA\n").unwrap();
+        std::fs::write(background_dir.join("10090_background.txt"), "Q1\tGO:0005575\tIDA\n").unwrap();
+
+        let background_pop = BackgroundPop::read_background_pop(
+            &[9606, 10090].into_iter().collect(),
+            &background_dir.to_string_lossy(),
+            None,
+            &map_input_to_category("all".to_string()).unwrap(),
+            &FxHashSet::default()
+        ).unwrap().unwrap();
+        std::fs::remove_dir_all(&background_dir).unwrap();
+
+        background_pop.protein_to_go
+    }
+
+    #[test]
+    fn from_tsv_file_maps_entries_to_background_go_terms() {
+        let tsv_file = write_tsv(
+            "uniprot_study",
+            "Entry\tEntry Name\tReviewed\tGene Names\tOrganism ID\n\
+             P1\tP1_HUMAN\treviewed\tGENE1 ALIAS1\t9606\n\
+             P2\tP2_HUMAN\tunreviewed\t\t9606\n\
+             P3\tP3_HUMAN\treviewed\tGENE3\t9606\n\
+             Q1\tQ1_MOUSE\treviewed\tGene1\t10090\n\
+             \tEMPTY_HUMAN\treviewed\tGENE4\t9606\n\
+             P4\tP4_HUMAN\treviewed\tGENE5\tunknown\n"
+        );
+
+        let study_pop = StudyPop::from_tsv_file(&tsv_file, &background_protein_to_go("uniprot_study")).unwrap().unwrap();
+        std::fs::remove_file(&tsv_file).unwrap();
+
+        let human_proteins: FxHashSet<&str> = study_pop.taxon_map[&9606].iter().map(|protein| protein.as_str()).collect();
+        assert_eq!(human_proteins, ["P1", "P2", "P3"].into_iter().collect());
+        assert_eq!(study_pop.taxon_protein_count[&9606], 3);
+        assert_eq!(study_pop.taxon_protein_count[&10090], 1);
+
+        assert_eq!(study_pop.go_term_count[&9606], [(8150, 2), (3674, 1)].into_iter().collect());
+        assert_eq!(study_pop.go_term_count[&10090], [(5575, 1)].into_iter().collect());
+        let biological_process_proteins: FxHashSet<&str> = study_pop.go_term_to_protein_set[&9606][&8150]
+            .iter()
+            .map(|protein| protein.as_str())
+            .collect();
+        assert_eq!(biological_process_proteins, ["P1", "P2"].into_iter().collect());
+    }
+
+    #[test]
+    fn from_tsv_file_only_needs_the_entry_and_organism_id_columns() {
+        let tsv_file = write_tsv("uniprot_required_columns", "Organism ID\tEntry\n9606\tP1\n10090\tQ1\n");
+
+        let study_pop = StudyPop::from_tsv_file(&tsv_file, &background_protein_to_go("uniprot_required_columns")).unwrap().unwrap();
+        std::fs::remove_file(&tsv_file).unwrap();
+
+        assert_eq!(study_pop.taxon_protein_count, [(9606, 1), (10090, 1)].into_iter().collect());
+        assert_eq!(study_pop.go_term_count[&9606], [(8150, 1), (3674, 1)].into_iter().collect());
+    }
+
+    #[test]
+    fn from_tsv_file_names_the_missing_column() {
+        let tsv_file = write_tsv("uniprot_missing_column", "Entry\tGene Names\nP1\tGENE1\n");
+
+        let error = StudyPop::from_tsv_file(&tsv_file, &FxHashMap::default()).unwrap_err();
+        std::fs::remove_file(&tsv_file).unwrap();
+
+        match error.downcast_ref::<StudyPopError>() {
+            Some(StudyPopError::TsvMissingRequiredColumn { column, .. }) => assert_eq!(column, "Organism ID"),
+            other => panic!("expected a missing column error, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use std::env::var;
+use std::path::PathBuf;
+use dirs::home_dir;
+
+// Path of a bundled asset (ontology, background population, lineage, VCV matrices) installed under
+// `$CARGO_HOME/taxago_assets`, falling back to `~/.cargo` when CARGO_HOME is not set.
+pub fn default_asset_path(filename: &str) -> String {
+    let cargo_home = var("CARGO_HOME")
+        .unwrap_or_else(|_| {
+            home_dir()
+                .expect("Could not determine home directory")
+                .join(".cargo")
+                .to_string_lossy()
+                .into_owned()
+        });
+    PathBuf::from(cargo_home)
+        .join("taxago_assets")
+        .join(filename)
+        .to_string_lossy()
+        .into_owned()
+}
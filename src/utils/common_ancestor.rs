@@ -1,6 +1,82 @@
 use crate::parsers::obo_parser::*;
+use crate::parsers::background_parser::GOTermID;
 use daggy::{NodeIndex, Walker};
 use rustc_hash::{FxHashMap, FxHashSet};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum AncestorAnalysisError {
+    #[error("GO:{0:07} not found in the ontology")]
+    TermNotFound(GOTermID),
+
+    #[error("No common ancestors found between the provided GO terms")]
+    NoCommonAncestors,
+}
+
+#[derive(Debug, Clone)]
+pub struct AncestorResult {
+    pub common_ancestors: Vec<GOTermID>,
+    pub most_specific_ancestors: Vec<GOTermID>,
+    pub mermaid_chart: String,
+}
+
+// Finds the common ancestors of `terms` on an already built ontology graph, so callers that keep the
+// ontology in memory can run the analysis without parsing the OBO file again.
+pub fn run_common_ancestor_analysis(
+    terms: &[GOTermID],
+    ontology: &OboMap,
+    ontology_graph: &OntologyGraph,
+    go_id_to_node_index: &FxHashMap<GOTermID, NodeIndex>,
+) -> Result<AncestorResult, AncestorAnalysisError> {
+    if let Some(&missing_term) = terms.iter().find(|go_id| !go_id_to_node_index.contains_key(go_id)) {
+        return Err(AncestorAnalysisError::TermNotFound(missing_term));
+    }
+
+    let node_index_to_go_id: FxHashMap<NodeIndex, GOTermID> = go_id_to_node_index
+        .iter()
+        .map(|(&go_id, &node_idx)| (node_idx, go_id))
+        .collect();
+
+    let ancestry_paths: Vec<AncestryPath> = terms
+        .iter()
+        .map(|go_id| collect_ancestry_path(ontology_graph, go_id_to_node_index[go_id]))
+        .collect();
+
+    let common_ancestors = find_common_ancestors(&ancestry_paths, &node_index_to_go_id);
+    if common_ancestors.is_empty() {
+        return Err(AncestorAnalysisError::NoCommonAncestors);
+    }
+
+    let common_nodes: FxHashSet<NodeIndex> = common_ancestors
+        .iter()
+        .map(|go_id| go_id_to_node_index[go_id])
+        .collect();
+    let most_specific_ancestors: Vec<GOTermID> = find_lca_set(&common_nodes, ontology_graph)
+        .into_iter()
+        .map(|node_idx| node_index_to_go_id[&node_idx])
+        .collect();
+
+    let first_common_ancestor = find_first_common_ancestor(
+        &ancestry_paths,
+        &node_index_to_go_id,
+        ontology_graph
+    );
+
+    let mermaid_chart = generate_mermaid_chart(
+        ontology_graph,
+        terms,
+        go_id_to_node_index,
+        &node_index_to_go_id,
+        ontology,
+        first_common_ancestor
+    );
+
+    Ok(AncestorResult {
+        common_ancestors,
+        most_specific_ancestors,
+        mermaid_chart,
+    })
+}
 
 pub fn collect_ancestry_path(
     graph: &OntologyGraph,
@@ -21,30 +97,53 @@ pub fn collect_ancestry_path(
     path
 }
 
-pub fn find_common_ancestors(
-    paths: &[AncestryPath],
-    node_index_to_go_id: &FxHashMap<NodeIndex, u32>
-) -> Vec<u32> {
-    if paths.is_empty() {
-        return Vec::new();
-    }
-
+fn intersect_ancestry_paths(paths: &[AncestryPath]) -> FxHashSet<NodeIndex> {
     let path_sets: Vec<FxHashSet<NodeIndex>> = paths
         .iter()
         .map(|path| path.iter().map(|(idx, _)| *idx).collect())
         .collect();
-    
-    let common_nodes = path_sets
+
+    path_sets
         .iter()
         .skip(1)
         .fold(path_sets[0].clone(), |acc, set| {
             acc.intersection(set).cloned().collect()
-        });
-    
-    common_nodes
+        })
+}
+
+pub fn find_common_ancestors(
+    paths: &[AncestryPath],
+    node_index_to_go_id: &FxHashMap<NodeIndex, u32>
+) -> Vec<u32> {
+    if paths.is_empty() {
+        return Vec::new();
+    }
+
+    let mut common_ancestors: Vec<u32> = intersect_ancestry_paths(paths)
         .into_iter()
         .map(|idx| node_index_to_go_id[&idx])
-        .collect()
+        .collect();
+    common_ancestors.sort_unstable();
+    common_ancestors
+}
+
+// Keeps only the most specific common ancestors. Every ancestor of a common ancestor is itself common,
+// so a node is redundant exactly when one of its direct children is also in the set.
+pub fn find_lca_set(
+    common_nodes: &FxHashSet<NodeIndex>,
+    graph: &OntologyGraph
+) -> Vec<NodeIndex> {
+    let mut lca_set: Vec<NodeIndex> = common_nodes
+        .iter()
+        .copied()
+        .filter(|&node_idx| {
+            !graph.children(node_idx)
+                .iter(graph)
+                .any(|(_, child_idx)| common_nodes.contains(&child_idx))
+        })
+        .collect();
+    lca_set.sort_unstable();
+    lca_set
 }
 
 pub fn find_first_common_ancestor(
@@ -56,29 +155,12 @@ pub fn find_first_common_ancestor(
         return None;
     }
 
-    let path_sets: Vec<FxHashSet<NodeIndex>> = paths
-        .iter()
-        .map(|path| path.iter().map(|(idx, _)| *idx).collect())
-        .collect();
-    
-    let common_nodes: FxHashSet<NodeIndex> = path_sets
-        .iter()
-        .skip(1)
-        .fold(path_sets[0].clone(), |acc, set| {
-            acc.intersection(set).cloned().collect()
-        });
-
-    let first_intersection = common_nodes.iter()
-        .find(|&&node_idx| {
-            let children: FxHashSet<NodeIndex> = graph.children(node_idx)
-                .iter(graph)
-                .map(|(_, child_idx)| child_idx)
-                .collect();
-            
-            children.intersection(&common_nodes).next().is_none()
-        });
+    let common_nodes = intersect_ancestry_paths(paths);
 
-    first_intersection.map(|&idx| node_index_to_go_id[&idx])
+    find_lca_set(&common_nodes, graph)
+        .into_iter()
+        .map(|idx| node_index_to_go_id[&idx])
+        .min()
 }
 
 pub fn topological_sort(
@@ -89,32 +171,38 @@ pub fn topological_sort(
 ) -> Vec<u32> {
     let mut sorted = Vec::new();
     let mut visited = FxHashSet::default();
-    
-    fn visit(
-        node_id: u32,
-        graph: &OntologyGraph,
-        visited: &mut FxHashSet<u32>,
-        sorted: &mut Vec<u32>,
-        go_id_to_node_index: &FxHashMap<u32, NodeIndex>,
-        node_index_to_go_id: &FxHashMap<NodeIndex, u32>,
-    ) {
-        if visited.insert(node_id) {
-            let node_idx = go_id_to_node_index[&node_id];
-            let mut parents = graph.parents(node_idx);
-            
-            while let Some((_, parent_idx)) = parents.walk_next(graph) {
-                let parent_id = node_index_to_go_id[&parent_idx];
-                visit(parent_id, graph, visited, sorted, go_id_to_node_index, node_index_to_go_id);
-            }
-            
-            sorted.push(node_id);
+
+    let parents_of = |node_id: u32| -> Vec<u32> {
+        let mut parent_ids = Vec::new();
+        let mut parents = graph.parents(go_id_to_node_index[&node_id]);
+        while let Some((_, parent_idx)) = parents.walk_next(graph) {
+            parent_ids.push(node_index_to_go_id[&parent_idx]);
         }
-    }
-    
+        parent_ids
+    };
+
+    // Depth-first post-order with an explicit stack, since GO chains can be deep enough to overflow
+    // the call stack. Each entry holds a node, its parents and the index of the next parent to visit.
+    let mut stack: Vec<(u32, Vec<u32>, usize)> = Vec::new();
+
     for &root_id in root_ids {
-        visit(root_id, graph, &mut visited, &mut sorted, go_id_to_node_index, node_index_to_go_id);
+        if visited.insert(root_id) {
+            stack.push((root_id, parents_of(root_id), 0));
+        }
+
+        while let Some((node_id, parent_ids, next_parent)) = stack.last_mut() {
+            if let Some(&parent_id) = parent_ids.get(*next_parent) {
+                *next_parent += 1;
+                if visited.insert(parent_id) {
+                    stack.push((parent_id, parents_of(parent_id), 0));
+                }
+            } else {
+                sorted.push(*node_id);
+                stack.pop();
+            }
+        }
     }
-    
+
     sorted
 }
 
@@ -325,4 +413,69 @@ pub fn generate_mermaid_chart(
     end\n
     Ontology~~~Legends");
     mermaid
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Builds an ontology from (child, parent) is_a edges.
+    fn ontology(edges: &[(GOTermID, GOTermID)]) -> OboMap {
+        let mut ontology: OboMap = FxHashMap::default();
+        for &(child, parent) in edges {
+            ontology.entry(parent).or_default();
+            ontology
+                .entry(child)
+                .or_default()
+                .relationships
+                .insert(parent, Relationship::IsA);
+        }
+        ontology
+    }
+
+    #[test]
+    fn common_ancestors_of_three_terms_from_the_same_lineage() {
+        // 10 <- 20 <- 30 <- 40, with 50 a child of 30 and 60 a child of 20.
+        let ontology = ontology(&[(20, 10), (30, 20), (40, 30), (50, 30), (60, 20)]);
+        let (ontology_graph, go_id_to_node_index) = build_ontology_graph(&ontology).unwrap();
+
+        let result = run_common_ancestor_analysis(&[40, 50, 60], &ontology, &ontology_graph, &go_id_to_node_index).unwrap();
+        assert_eq!(result.common_ancestors, vec![10, 20]);
+        assert_eq!(result.most_specific_ancestors, vec![20]);
+
+        let result = run_common_ancestor_analysis(&[40, 50, 30], &ontology, &ontology_graph, &go_id_to_node_index).unwrap();
+        assert_eq!(result.common_ancestors, vec![10, 20, 30]);
+        assert_eq!(result.most_specific_ancestors, vec![30]);
+    }
+
+    #[test]
+    fn unknown_terms_are_reported() {
+        let ontology = ontology(&[(20, 10)]);
+        let (ontology_graph, go_id_to_node_index) = build_ontology_graph(&ontology).unwrap();
+
+        let result = run_common_ancestor_analysis(&[20, 99], &ontology, &ontology_graph, &go_id_to_node_index);
+        assert!(matches!(result, Err(AncestorAnalysisError::TermNotFound(99))));
+    }
+
+    #[test]
+    fn deep_linear_chains_do_not_overflow_the_stack() {
+        const DEPTH: GOTermID = 1000;
+        let edges: Vec<(GOTermID, GOTermID)> = (2..=DEPTH).map(|go_id| (go_id, go_id - 1)).collect();
+        let ontology = ontology(&edges);
+        let (ontology_graph, go_id_to_node_index) = build_ontology_graph(&ontology).unwrap();
+        let node_index_to_go_id: FxHashMap<NodeIndex, u32> = go_id_to_node_index
+            .iter()
+            .map(|(&go_id, &node_idx)| (node_idx, go_id))
+            .collect();
+
+        let path = collect_ancestry_path(&ontology_graph, go_id_to_node_index[&DEPTH]);
+        assert_eq!(path.len(), DEPTH as usize);
+
+        let sorted = topological_sort(&ontology_graph, &[DEPTH], &go_id_to_node_index, &node_index_to_go_id);
+        assert_eq!(sorted, (1..=DEPTH).collect::<Vec<_>>());
+
+        let result = run_common_ancestor_analysis(&[DEPTH, DEPTH - 1], &ontology, &ontology_graph, &go_id_to_node_index).unwrap();
+        assert_eq!(result.common_ancestors.len(), (DEPTH - 1) as usize);
+        assert_eq!(result.most_specific_ancestors, vec![DEPTH - 1]);
+    }
+}
@@ -0,0 +1,172 @@
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use crate::parsers::{
+    background_parser::*,
+    obo_parser::*
+};
+use crate::analysis::results_summary::read_results_dir;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffStatus {
+    Gained,
+    Lost,
+    Increased,
+    Decreased,
+    Stable,
+}
+
+impl DiffStatus {
+    fn name(&self) -> &'static str {
+        match self {
+            DiffStatus::Gained => "gained",
+            DiffStatus::Lost => "lost",
+            DiffStatus::Increased => "increased",
+            DiffStatus::Decreased => "decreased",
+            DiffStatus::Stable => "stable",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TermDiff {
+    pub species: String,
+    pub go_term: GOTermID,
+    pub status: DiffStatus,
+    pub before: Option<(f64, f64)>,
+    pub after: Option<(f64, f64)>,
+}
+
+impl TermDiff {
+    // Significant in both runs, with log(Odds Ratios) of opposite sign.
+    pub fn changed_direction(&self) -> bool {
+        match (self.before, self.after) {
+            (Some((before_lor, _)), Some((after_lor, _))) => before_lor * after_lor < 0.0,
+            _ => false,
+        }
+    }
+}
+
+// Result files only list significant terms, so a term missing from one run was not significant in it.
+pub fn diff_results(
+    before: &FxHashMap<String, Vec<(GOTermID, f64, f64)>>,
+    after: &FxHashMap<String, Vec<(GOTermID, f64, f64)>>,
+    min_lor_change: f64,
+) -> Vec<TermDiff> {
+    let species: FxHashSet<&String> = before.keys().chain(after.keys()).collect();
+    let mut diffs = Vec::new();
+
+    for species_name in species {
+        let to_map = |results: Option<&Vec<(GOTermID, f64, f64)>>| -> FxHashMap<GOTermID, (f64, f64)> {
+            results
+                .map(|rows| rows.iter().map(|&(go_term, lor, p_value)| (go_term, (lor, p_value))).collect())
+                .unwrap_or_default()
+        };
+        let before_terms = to_map(before.get(species_name));
+        let after_terms = to_map(after.get(species_name));
+
+        let go_terms: FxHashSet<GOTermID> = before_terms.keys().chain(after_terms.keys()).copied().collect();
+
+        for go_term in go_terms {
+            let before_values = before_terms.get(&go_term).copied();
+            let after_values = after_terms.get(&go_term).copied();
+
+            let status = match (before_values, after_values) {
+                (None, Some(_)) => DiffStatus::Gained,
+                (Some(_), None) => DiffStatus::Lost,
+                (Some((before_lor, _)), Some((after_lor, _))) => {
+                    let change = after_lor - before_lor;
+                    if change > min_lor_change {
+                        DiffStatus::Increased
+                    } else if change < -min_lor_change {
+                        DiffStatus::Decreased
+                    } else {
+                        DiffStatus::Stable
+                    }
+                },
+                (None, None) => continue,
+            };
+
+            diffs.push(TermDiff {
+                species: species_name.clone(),
+                go_term,
+                status,
+                before: before_values,
+                after: after_values,
+            });
+        }
+    }
+
+    diffs.sort_by(|a, b| a.species.cmp(&b.species).then(a.go_term.cmp(&b.go_term)));
+    diffs
+}
+
+pub fn diff_result_dirs(
+    before_dir: &Path,
+    after_dir: &Path,
+    min_lor_change: f64,
+) -> Result<Vec<TermDiff>, Box<dyn Error>> {
+    let before = read_results_dir(&before_dir.join("single_taxon_results"))?;
+    let after = read_results_dir(&after_dir.join("single_taxon_results"))?;
+
+    Ok(diff_results(&before, &after, min_lor_change))
+}
+
+pub fn write_results_diff(
+    diffs: &[TermDiff],
+    ontology: &OboMap,
+    output_file: &PathBuf,
+) -> Result<(), Box<dyn Error>> {
+    println!("Writing results diff to: {}\n", output_file.display());
+
+    let file = File::create(output_file)?;
+    let mut writer = BufWriter::new(file);
+
+    writer.write_all(b"Species\tGO_ID\tName\tNamespace\tStatus\tBefore_LOR\tAfter_LOR\tBefore_p\tAfter_p\n")?;
+
+    let format_value = |value: Option<f64>, scientific: bool| match value {
+        Some(value) if scientific => format!("{:.5e}", value),
+        Some(value) => format!("{:.3}", value),
+        None => "NA".to_string(),
+    };
+
+    for diff in diffs {
+        let (name, namespace) = match ontology.get(&diff.go_term) {
+            Some(term) => (
+                term.name.as_str(),
+                term.namespace.label()
+            ),
+            None => ("", ""),
+        };
+
+        writeln!(
+            writer,
+            "{}\tGO:{:07}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            diff.species,
+            diff.go_term,
+            name,
+            namespace,
+            diff.status.name(),
+            format_value(diff.before.map(|(lor, _)| lor), false),
+            format_value(diff.after.map(|(lor, _)| lor), false),
+            format_value(diff.before.map(|(_, p_value)| p_value), true),
+            format_value(diff.after.map(|(_, p_value)| p_value), true)
+        )?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn print_diff_summary(diffs: &[TermDiff]) {
+    let count = |status: DiffStatus| diffs.iter().filter(|diff| diff.status == status).count();
+
+    println!("Gained significance: {}", count(DiffStatus::Gained));
+    println!("Lost significance: {}", count(DiffStatus::Lost));
+    println!("Increased log(Odds Ratio): {}", count(DiffStatus::Increased));
+    println!("Decreased log(Odds Ratio): {}", count(DiffStatus::Decreased));
+    println!("Stable: {}", count(DiffStatus::Stable));
+    println!("Changed direction: {}\n", diffs.iter().filter(|diff| diff.changed_direction()).count());
+}
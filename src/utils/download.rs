@@ -1,9 +1,20 @@
 use serde::{Serialize, Deserialize};
 use std::fs;
 use std::path::Path;
-use anyhow::{Result, Context};
+use anyhow::Result;
 use csv::WriterBuilder;
 use std::io::Write;
+use crate::analysis::results_summary::{read_result_rows, read_results_header, results_file_taxon};
+use rust_xlsxwriter::{
+    Workbook, Format, Color,
+    ConditionalFormat3ColorScale, ConditionalFormatType
+};
+
+const XLSX_NAMESPACES: [&str; 3] = [
+    "Biological Process",
+    "Molecular Function",
+    "Cellular Component"
+];
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GOResult {
@@ -33,6 +44,7 @@ impl DownloadManager {
             "csv" => format!("{}.csv", original_filename),
             "tsv" => format!("{}.tsv", original_filename),
             "json" => format!("{}.json", original_filename),
+            "xlsx" => format!("{}.xlsx", original_filename),
             _ => format!("{}.{}", original_filename, format),
         };
         
@@ -82,31 +94,73 @@ impl DownloadManager {
                 let content = serde_json::to_vec_pretty(&results)?;
                 Ok((output_filename, content))
             }
+            "xlsx" => {
+                let content = self.build_xlsx_workbook(&original_header, &results)?;
+                Ok((output_filename, content))
+            }
             _ => Err(anyhow::anyhow!("Unsupported format"))?
         }
     }
 
+    fn build_xlsx_workbook(&self, header: &str, results: &[GOResult]) -> Result<Vec<u8>> {
+        let mut workbook = Workbook::new();
+        let header_format = Format::new().set_bold();
+        let header_fields: Vec<&str> = header.split('\t').collect();
+
+        for namespace in XLSX_NAMESPACES {
+            let worksheet = workbook.add_worksheet();
+            worksheet.set_name(namespace)?;
+
+            for (col, field) in header_fields.iter().enumerate() {
+                worksheet.write_string_with_format(0, col as u16, *field, &header_format)?;
+            }
+            worksheet.set_freeze_panes(1, 0)?;
+            worksheet.set_column_width(0, 14)?;
+            worksheet.set_column_width(1, 60)?;
+            worksheet.set_column_width(2, 20)?;
+            worksheet.set_column_width(3, 16)?;
+            worksheet.set_column_width(4, 24)?;
+
+            let mut row: u32 = 0;
+            for result in results.iter().filter(|r| r.namespace == namespace) {
+                row += 1;
+                worksheet.write_string(row, 0, &result.go_term)?;
+                worksheet.write_string(row, 1, &result.name)?;
+                worksheet.write_string(row, 2, &result.namespace)?;
+                worksheet.write_number(row, 3, result.odds_ratio)?;
+                worksheet.write_number(row, 4, result.statistical_significance)?;
+            }
+
+            if row > 0 {
+                let color_scale = ConditionalFormat3ColorScale::new()
+                    .set_minimum_color(Color::RGB(0xF8696B))
+                    .set_midpoint(ConditionalFormatType::Number, 0)
+                    .set_midpoint_color(Color::RGB(0xFFFFFF))
+                    .set_maximum_color(Color::RGB(0x63BE7B));
+                worksheet.add_conditional_format(1, 3, row, 3, &color_scale)?;
+            }
+        }
+
+        let content = workbook.save_to_buffer()?;
+        Ok(content)
+    }
+
     fn get_original_file_info(&self) -> Result<(String, String)> {
         let results_dir = Path::new(&self.results_path).parent().unwrap_or(Path::new(""));
         
         let mut original_filename = String::from("results");
         let mut original_header;
         
-        let content = fs::read_to_string(&self.results_path)
-            .context("Failed to read results file")?;
-        
-        let lines: Vec<&str> = content.lines().collect();
-        if !lines.is_empty() {
-            original_header = lines[0].to_string();
-        } else {
+        let header = read_results_header(Path::new(&self.results_path))
+            .map_err(|e| anyhow::anyhow!("Failed to read results file: {}", e))?;
+        if header.iter().all(|column| column.is_empty()) {
             return Err(anyhow::anyhow!("Results file is empty"));
         }
+        original_header = header.join("\t");
         
         let extract_taxonomy_name = |filename: &str| -> String {
-            if let Some(suffix_pos) = filename.find("_GOEA_results.txt") {
-                filename[0..suffix_pos].to_string()
-            } else if let Some(name_without_ext) = filename.strip_suffix("_GOEA_results.txt") {
-                name_without_ext.to_string()
+            if let Some(taxon_name) = results_file_taxon(filename) {
+                taxon_name.to_string()
             } else if let Some(name_without_ext) = filename.strip_suffix(".txt") {
                 name_without_ext.to_string()
             } else {
@@ -181,26 +235,19 @@ impl DownloadManager {
     }
 
     fn read_results(&self) -> Result<Vec<GOResult>> {
-        let content = fs::read_to_string(&self.results_path)
-            .context("Failed to read results file")?;
+        let rows = read_result_rows(Path::new(&self.results_path))
+            .map_err(|e| anyhow::anyhow!("Failed to read results file: {}", e))?;
 
-        let mut results = Vec::new();
-        let lines: Vec<&str> = content.lines().collect();
-        
-        if lines.is_empty() {
-            return Err(anyhow::anyhow!("Results file is empty"));
-        }    
-            
-        for line in lines.iter().skip(1) { 
-            let fields: Vec<&str> = line.split('\t').collect();
-            let odds_ratio: f64 = fields[3].parse().unwrap_or(0.0);
-                results.push(GOResult {
-                    go_term: fields[0].to_string(),
-                    name: fields[1].to_string(),
-                    namespace: fields[2].to_string(),
-                    odds_ratio: (odds_ratio * 1000.0).round() / 1000.0,
-                    statistical_significance: fields[4].parse().unwrap_or(0.0)});
-        }
+        let mut results: Vec<GOResult> = rows
+            .into_iter()
+            .map(|row| GOResult {
+                go_term: format!("GO:{:07}", row.go_term),
+                name: row.name,
+                namespace: row.namespace,
+                odds_ratio: (row.log_odds_ratio * 1000.0).round() / 1000.0,
+                statistical_significance: row.p_value,
+            })
+            .collect();
 
         results.sort_by(|a, b| {
             let odds_ratio_cmp = b.odds_ratio.partial_cmp(&a.odds_ratio).unwrap_or(std::cmp::Ordering::Equal);
@@ -214,3 +261,33 @@ impl DownloadManager {
         Ok(results)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_results_are_downloaded_as_tsv() {
+        let results_dir = std::env::temp_dir().join(format!("taxago_download_csv_{}", std::process::id()));
+        fs::create_dir_all(&results_dir).unwrap();
+        let results_file = results_dir.join("Homo_sapiens_GOEA_results.csv");
+        fs::write(
+            &results_file,
+            "GO Term ID,Name,Namespace,log(Odds Ratio),Statistical significance\n\
+             GO:0000002,\"transport, active\",Biological Process,0.5,1.00000e-2\n\
+             GO:0000001,binding,Molecular Function,1.2345,3.00000e-4\n",
+        ).unwrap();
+
+        let manager = DownloadManager::new(results_file.to_string_lossy().into_owned());
+        let (filename, content) = manager.generate_file_content("tsv").unwrap();
+        fs::remove_dir_all(&results_dir).unwrap();
+
+        assert_eq!(filename, "results.tsv");
+        assert_eq!(
+            String::from_utf8(content).unwrap(),
+            "GO Term ID\tName\tNamespace\tlog(Odds Ratio)\tStatistical significance\n\
+             GO:0000001\tbinding\tMolecular Function\t1.235\t0.0003\n\
+             GO:0000002\ttransport, active\tBiological Process\t0.500\t0.01\n"
+        );
+    }
+}
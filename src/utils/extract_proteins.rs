@@ -0,0 +1,52 @@
+use rustc_hash::FxHashSet;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::error::Error;
+use std::path::PathBuf;
+use crate::parsers::{
+    background_parser::*,
+    obo_parser::*
+};
+
+// Looks up the background proteins annotated to each GO term, the inverse of a protein's GO term lookup.
+// Rows are sorted by GO term, taxon and protein.
+pub fn extract_proteins(
+    go_terms: &FxHashSet<GOTermID>,
+    background_population: &BackgroundPop,
+) -> Vec<(GOTermID, TaxonID, Protein)> {
+    let mut rows: Vec<(GOTermID, TaxonID, Protein)> = background_population.go_term_to_protein_set
+        .iter()
+        .flat_map(|(&taxon_id, term_proteins)| {
+            go_terms
+                .iter()
+                .filter_map(move |go_term| term_proteins.get(go_term).map(|proteins| (*go_term, proteins)))
+                .flat_map(move |(go_term, proteins)| {
+                    proteins.iter().map(move |protein| (go_term, taxon_id, protein.clone()))
+                })
+        })
+        .collect();
+
+    rows.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+    rows
+}
+
+pub fn write_extracted_proteins(
+    rows: &[(GOTermID, TaxonID, Protein)],
+    ontology: &OboMap,
+    output_file: &PathBuf,
+) -> Result<(), Box<dyn Error>> {
+    println!("Writing {} protein annotations to: {}\n", rows.len(), output_file.display());
+
+    let file = File::create(output_file)?;
+    let mut writer = BufWriter::new(file);
+
+    writer.write_all(b"GO_ID\tGO_Name\tTaxon_ID\tProtein_Accession\n")?;
+
+    for (go_term, taxon_id, protein) in rows {
+        let name = ontology.get(go_term).map_or("", |term| term.name.as_str());
+        writeln!(writer, "GO:{:07}\t{}\t{}\t{}", go_term, name, taxon_id, protein)?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
@@ -1,3 +1,7 @@
 pub mod semantic_similarity;
 pub mod common_ancestor;
 pub mod download;
+pub mod diff_results;
+pub mod extract_proteins;
+pub mod progress;
+pub mod assets;
@@ -0,0 +1,24 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static PROGRESS_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+// Sends progress messages to stderr, so stdout only carries machine-readable output (e.g. --jsonl-stream).
+pub fn set_progress_to_stderr(to_stderr: bool) {
+    PROGRESS_TO_STDERR.store(to_stderr, Ordering::Relaxed);
+}
+
+pub fn progress_to_stderr() -> bool {
+    PROGRESS_TO_STDERR.load(Ordering::Relaxed)
+}
+
+// `println!` for progress messages, printed to stderr instead after `set_progress_to_stderr(true)`.
+#[macro_export]
+macro_rules! progress {
+    ($($arg:tt)*) => {
+        if $crate::utils::progress::progress_to_stderr() {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
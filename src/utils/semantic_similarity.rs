@@ -3,17 +3,25 @@ use std::fs::{self,File};
 use std::path::Path;
 use std::io::{BufRead, BufReader, Write};
 use daggy::NodeIndex;
+use petgraph::algo::toposort;
 use crate::parsers::{
     background_parser::*,
     obo_parser::*,
 };
 use crate::utils::common_ancestor::*;
+use crate::analysis::results_summary::{read_single_taxon_results, results_file_taxon};
 use std::collections::VecDeque;
 use daggy::Walker;
 use clap::ValueEnum;
 use std::fmt;
+use plotly::{
+    Plot, HeatMap, Layout, Scatter,
+    common::{Title, ColorScale, ColorScaleElement, HoverInfo, Line, Mode},
+    layout::{Axis, Margin},
+};
 
 pub type InformationContent = f64;
+pub type SValueCache = FxHashMap<GOTermID, FxHashMap<GOTermID, f64>>;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ValueEnum)] 
 pub enum Method {
@@ -34,6 +42,38 @@ impl fmt::Display for Method {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ValueEnum)]
+pub enum ZeroIcBehavior {
+    ReturnZero,
+    ReturnOne,
+    #[value(name = "return-nan")]
+    ReturnNaN,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct LinSimilarity {
+    pub zero_ic_behavior: ZeroIcBehavior,
+}
+
+impl LinSimilarity {
+    pub fn new(zero_ic_behavior: ZeroIcBehavior) -> Self {
+        Self { zero_ic_behavior }
+    }
+
+    // Both terms having IC = 0 only happens for root-level terms, where the ratio is undefined.
+    pub fn similarity(&self, ic_term1: f64, ic_term2: f64, ic_mica: f64) -> f64 {
+        let denom = ic_term1 + ic_term2;
+        if denom == 0.0 {
+            return match self.zero_ic_behavior {
+                ZeroIcBehavior::ReturnZero => 0.0,
+                ZeroIcBehavior::ReturnOne => 1.0,
+                ZeroIcBehavior::ReturnNaN => f64::NAN,
+            };
+        }
+        (2.0 * ic_mica) / denom
+    }
+}
+
 #[derive(Debug)]
 pub struct TermPair {
     pub term1: u32,
@@ -63,6 +103,7 @@ impl TermPair {
         ic_term2: f64,
         mica: (u32, f64),
         method: Method,
+        zero_ic_behavior: ZeroIcBehavior,
     ) -> Self {
         let mut pair = Self {
             term1,
@@ -75,8 +116,7 @@ impl TermPair {
         pair.similarity = match method { 
             Method::Resnik => pair.mica.1,
             Method::Lin => {
-                let denom = pair.ic_term1 + pair.ic_term2;
-                if denom == 0.0 { 0.0 } else { (2.0 * pair.mica.1) / denom }
+                LinSimilarity::new(zero_ic_behavior).similarity(pair.ic_term1, pair.ic_term2, pair.mica.1)
             }
             Method::JiangConrath => {
                 let distance = (pair.ic_term1 + pair.ic_term2 - 2.0 * pair.mica.1).max(0.0);
@@ -84,7 +124,8 @@ impl TermPair {
             },
             Method::Wang => {0.0}
         };
-        if !pair.similarity.is_finite() || pair.similarity < 0.0 {
+        let undefined_lin = method == Method::Lin && pair.similarity.is_nan();
+        if !undefined_lin && (!pair.similarity.is_finite() || pair.similarity < 0.0) {
             pair.similarity = 0.0;
         }
         pair
@@ -154,8 +195,48 @@ pub fn read_go_terms_from_file(file_path: &str) -> Result<FxHashSet<u32>, String
         .map_err(|e| format!("{} in file: {}", e, file_path))
 }
 
-pub fn process_go_terms_input(input: &str) -> Result<FxHashSet<u32>, String> {
+const RESULTS_HEADER_PREFIX: &str = "GO Term ID\t";
+
+pub fn read_go_terms_from_results(file_path: &str, max_terms: usize) -> Result<FxHashSet<u32>, String> {
+    let mut ranked_terms: Vec<(u32, f64)> = read_single_taxon_results(Path::new(file_path))
+        .map_err(|e| format!("Error reading results file {}: {}", file_path, e))?
+        .into_iter()
+        .map(|(go_id, _, significance)| (go_id, significance))
+        .collect();
+
+    if ranked_terms.is_empty() {
+        return Err(format!("No valid GO terms found in results file: {}", file_path));
+    }
+
+    ranked_terms.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+    if max_terms > 0 && ranked_terms.len() > max_terms {
+        println!("Keeping the {} most significant of {} GO terms\n", max_terms, ranked_terms.len());
+        ranked_terms.truncate(max_terms);
+    }
+
+    Ok(ranked_terms.into_iter().map(|(go_id, _)| go_id).collect())
+}
+
+fn is_results_file(file_path: &str) -> bool {
+    let named_as_results = Path::new(file_path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(results_file_taxon)
+        .is_some();
+
+    named_as_results || File::open(file_path)
+        .ok()
+        .and_then(|file| BufReader::new(file).lines().next())
+        .and_then(|line| line.ok())
+        .is_some_and(|header| header.starts_with(RESULTS_HEADER_PREFIX))
+}
+
+pub fn process_go_terms_input(input: &str, max_terms: usize) -> Result<FxHashSet<u32>, String> {
     if !input.contains(',') && Path::new(input).exists() {
+        if is_results_file(input) {
+            println!("Input appears to be a TaxaGO results file. Reading GO terms from: {}\n", input);
+            return read_go_terms_from_results(input, max_terms);
+        }
         println!("Input appears to be a file path. Reading GO terms from file: {}\n", input);
         read_go_terms_from_file(input)
     } else {
@@ -245,47 +326,57 @@ pub fn find_mica_for_pair(
     }
 }
 
+// Ontology lookups and scoring options shared by the term pairs of every taxon.
+pub struct TermPairConfig<'a> {
+    pub ontology_graph: &'a OntologyGraph,
+    pub go_id_to_node_index: &'a FxHashMap<u32, NodeIndex>,
+    pub node_index_to_go_id: &'a FxHashMap<NodeIndex, u32>,
+    pub global_rev_topo_order: &'a [GOTermID],
+    pub method: Method,
+    pub zero_ic_behavior: ZeroIcBehavior,
+}
+
 pub fn generate_term_pairs(
     go_terms: &FxHashSet<u32>, 
     taxon_id: TaxonID,
     ic_results: &FxHashMap<TaxonID, FxHashMap<u32, f64>>,
-    ontology_graph: &OntologyGraph,
-    go_id_to_node_index: &FxHashMap<u32, NodeIndex>,
-    node_index_to_go_id: &FxHashMap<NodeIndex, u32>,
-    global_rev_topo_order: &[GOTermID], 
-    method: Method,
+    config: &TermPairConfig,
 ) -> Vec<TermPair> {
+    let TermPairConfig {
+        ontology_graph,
+        go_id_to_node_index,
+        node_index_to_go_id,
+        global_rev_topo_order,
+        method,
+        zero_ic_behavior,
+    } = *config;
     let terms_vec: Vec<u32> = go_terms.iter().cloned().collect();
     let mut pairs = Vec::new();
 
     match method {
         Method::Wang => {
             println!("Calculating Wang's similarity for term pairs...");
+            let s_value_cache = precompute_s_values(
+                go_terms,
+                ontology_graph,
+                go_id_to_node_index,
+                node_index_to_go_id,
+                global_rev_topo_order,
+            );
+
             for i in 0..terms_vec.len() {
                 for j in i..terms_vec.len() {
                     let term1 = terms_vec[i];
                     let term2 = terms_vec[j];
 
-                    match wang_similarity(
-                        term1,
-                        term2,
-                        ontology_graph,
-                        go_id_to_node_index,
-                        node_index_to_go_id,
-                        global_rev_topo_order,
-                    ) {
-                        Ok(mut sim_score) => {
-                            if !sim_score.is_finite() || sim_score < 0.0 { sim_score = 0.0; }
-                            pairs.push(TermPair::new_for_wang(term1, term2, sim_score));
+                    let sim_score = match (s_value_cache.get(&term1), s_value_cache.get(&term2)) {
+                        (Some(s_values_term1), Some(s_values_term2)) => {
+                            wang_similarity_cached(term1, term2, s_values_term1, s_values_term2)
                         }
-                        Err(e) => {
-                            eprintln!(
-                                "Error calculating Wang's similarity for GO:{:07} and GO:{:07}: {}",
-                                term1, term2, e
-                            );
-                            pairs.push(TermPair::new_for_wang(term1, term2, 0.0));
-                        }
-                    }
+                        _ if term1 == term2 => 1.0,
+                        _ => 0.0,
+                    };
+                    pairs.push(TermPair::new_for_wang(term1, term2, sim_score));
                 }
             }
         }
@@ -328,7 +419,7 @@ pub fn generate_term_pairs(
                     };
 
                     let pair = TermPair::new_for_ic(
-                        term1, term2, ic_term1, ic_term2, mica, method,
+                        term1, term2, ic_term1, ic_term2, mica, method, zero_ic_behavior,
                     );
                     pairs.push(pair);
                 }
@@ -454,16 +545,56 @@ pub fn wang_similarity(
         global_rev_topo_order,
     )?;
 
-    let sv_term1 = calculate_semantic_value(&s_values_term1);
-    let sv_term2 = calculate_semantic_value(&s_values_term2);
+    Ok(wang_similarity_cached(term1_id, term2_id, &s_values_term1, &s_values_term2))
+}
+
+pub fn precompute_s_values(
+    terms: &FxHashSet<u32>,
+    ontology_graph: &OntologyGraph,
+    go_id_to_node_index: &FxHashMap<GOTermID, NodeIndex>,
+    node_index_to_go_id: &FxHashMap<NodeIndex, GOTermID>,
+    global_rev_topo_order: &[GOTermID],
+) -> SValueCache {
+    terms
+        .iter()
+        .filter_map(|&term_id| {
+            match calculate_s_values(
+                term_id,
+                ontology_graph,
+                go_id_to_node_index,
+                node_index_to_go_id,
+                global_rev_topo_order,
+            ) {
+                Ok(s_values) => Some((term_id, s_values)),
+                Err(e) => {
+                    eprintln!("Error calculating S-values for GO:{:07}: {}", term_id, e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+pub fn wang_similarity_cached(
+    term1_id: GOTermID,
+    term2_id: GOTermID,
+    s_values_term1: &FxHashMap<GOTermID, f64>,
+    s_values_term2: &FxHashMap<GOTermID, f64>,
+) -> f64 {
+    if term1_id == term2_id {
+        return 1.0;
+    }
+
+    let sv_term1 = calculate_semantic_value(s_values_term1);
+    let sv_term2 = calculate_semantic_value(s_values_term2);
 
     if sv_term1 == 0.0 || sv_term2 == 0.0 {
-        return Ok(0.0);
+        return 0.0;
     }
 
     let mut sum_common_s_values = 0.0;
 
-    for (ancestor_t_id, s_value_for_t_in_term1) in &s_values_term1 {
+    for (ancestor_t_id, s_value_for_t_in_term1) in s_values_term1 {
         if let Some(s_value_for_t_in_term2) = s_values_term2.get(ancestor_t_id) {
             sum_common_s_values += s_value_for_t_in_term1 + s_value_for_t_in_term2;
         }
@@ -471,13 +602,65 @@ pub fn wang_similarity(
 
     let denominator = sv_term1 + sv_term2;
     if denominator == 0.0 {
-        return Ok(0.0);
+        return 0.0;
     }
 
-    let mut similarity = sum_common_s_values / denominator;
-    if !similarity.is_finite() || similarity < 0.0 { similarity = 0.0; }
+    let similarity = sum_common_s_values / denominator;
+    if !similarity.is_finite() || similarity < 0.0 { 0.0 } else { similarity }
+}
+
+// Computes the pairwise similarity of `go_terms` for each taxon on an already built ontology graph.
+// IC-based methods use the background GO term counts of each taxon; the ancestors of the terms are
+// included so their most informative common ancestors have an IC value.
+pub fn run_semantic_similarity(
+    go_terms: &FxHashSet<GOTermID>,
+    taxon_ids: &FxHashSet<TaxonID>,
+    go_term_count: &FxHashMap<TaxonID, FxHashMap<GOTermID, usize>>,
+    ontology_graph: &OntologyGraph,
+    go_id_to_node_index: &FxHashMap<GOTermID, NodeIndex>,
+    method: Method,
+    zero_ic_behavior: ZeroIcBehavior,
+) -> Result<FxHashMap<TaxonID, Vec<TermPair>>, String> {
+    let node_index_to_go_id: FxHashMap<NodeIndex, GOTermID> = go_id_to_node_index
+        .iter()
+        .map(|(&go_id, &node_idx)| (node_idx, go_id))
+        .collect();
 
-    Ok(similarity)
+    let topo_result = toposort(ontology_graph, None)
+        .map_err(|cycle| format!("Ontology graph contains a cycle at node {:?}", cycle.node_id()))?;
+    let mut propagation_order: Vec<GOTermID> = topo_result
+        .iter()
+        .filter_map(|node_idx| node_index_to_go_id.get(node_idx).copied())
+        .collect();
+    propagation_order.reverse();
+
+    let mut expanded_terms = go_terms.clone();
+    for term in go_terms {
+        if let Some(&node_idx) = go_id_to_node_index.get(term) {
+            for (ancestor_idx, _) in collect_ancestry_path(ontology_graph, node_idx) {
+                expanded_terms.insert(node_index_to_go_id[&ancestor_idx]);
+            }
+        }
+    }
+
+    let ic_results = calculate_information_content(go_term_count, &expanded_terms, go_id_to_node_index);
+
+    let config = TermPairConfig {
+        ontology_graph,
+        go_id_to_node_index,
+        node_index_to_go_id: &node_index_to_go_id,
+        global_rev_topo_order: &propagation_order,
+        method,
+        zero_ic_behavior,
+    };
+
+    Ok(taxon_ids
+        .iter()
+        .map(|&taxon_id| {
+            let term_pairs = generate_term_pairs(go_terms, taxon_id, &ic_results, &config);
+            (taxon_id, term_pairs)
+        })
+        .collect())
 }
 
 pub fn write_similarity_to_tsv(
@@ -555,3 +738,295 @@ pub fn write_similarity_to_tsv(
     println!("Successfully wrote similarity matrix to {}", filename);
     Ok(())
 }
+
+fn similarity_matrix(
+    term_pairs: &[TermPair],
+    sorted_go_ids: &[GOTermID],
+) -> Vec<Vec<f64>> {
+    let mut similarity_map: FxHashMap<(GOTermID, GOTermID), f64> = FxHashMap::default();
+    for pair in term_pairs {
+        similarity_map.insert((pair.term1, pair.term2), pair.similarity);
+        if pair.term1 != pair.term2 {
+            similarity_map.insert((pair.term2, pair.term1), pair.similarity);
+        }
+    }
+
+    sorted_go_ids
+        .iter()
+        .map(|row_go_id| {
+            sorted_go_ids
+                .iter()
+                .map(|col_go_id| {
+                    let default = if row_go_id == col_go_id { 1.0 } else { 0.0 };
+                    similarity_map.get(&(*row_go_id, *col_go_id)).copied().unwrap_or(default)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// One merge of the average-linkage clustering. Clusters are numbered as in SciPy: terms by their index below n,
+// and the cluster formed by merge k as n + k.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkageMerge {
+    pub left: usize,
+    pub right: usize,
+    pub distance: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Linkage {
+    pub order: Vec<usize>,
+    pub merges: Vec<LinkageMerge>,
+}
+
+pub fn average_linkage(
+    matrix: &[Vec<f64>]
+) -> Linkage {
+    let n = matrix.len();
+    if n < 2 {
+        return Linkage { order: (0..n).collect(), merges: Vec::new() };
+    }
+
+    let max_similarity = matrix
+        .iter()
+        .flatten()
+        .cloned()
+        .fold(0.0_f64, f64::max);
+    let scale = if max_similarity > 0.0 { max_similarity } else { 1.0 };
+
+    let mut clusters: Vec<Vec<usize>> = (0..n).map(|i| vec![i]).collect();
+    let mut cluster_ids: Vec<usize> = (0..n).collect();
+    let mut merges: Vec<LinkageMerge> = Vec::with_capacity(n - 1);
+
+    while clusters.len() > 1 {
+        let mut best = (0, 1, f64::INFINITY);
+
+        for i in 0..clusters.len() {
+            for j in (i + 1)..clusters.len() {
+                // Undefined (NaN) Lin similarities of `--zero-ic return-nan` are left out of the average.
+                let (total, defined_pairs) = clusters[i]
+                    .iter()
+                    .flat_map(|&a| clusters[j].iter().map(move |&b| matrix[a][b]))
+                    .filter(|similarity| !similarity.is_nan())
+                    .fold((0.0, 0_usize), |(total, count), similarity| (total + 1.0 - similarity / scale, count + 1));
+                let distance = if defined_pairs > 0 { total / defined_pairs as f64 } else { 1.0 };
+
+                if distance < best.2 {
+                    best = (i, j, distance);
+                }
+            }
+        }
+
+        let (i, j, distance) = best;
+        let merged = clusters.remove(j);
+        clusters[i].extend(merged);
+
+        merges.push(LinkageMerge { left: cluster_ids[i], right: cluster_ids.remove(j), distance });
+        cluster_ids[i] = n + merges.len() - 1;
+    }
+
+    Linkage { order: clusters.remove(0), merges }
+}
+
+// One U-shaped line (x, y) per merge, with the terms at their position in the heatmap and each merge at its distance.
+fn dendrogram_lines(linkage: &Linkage) -> Vec<(Vec<f64>, Vec<f64>)> {
+    let n = linkage.order.len();
+    let mut positions: Vec<(f64, f64)> = vec![(0.0, 0.0); n + linkage.merges.len()];
+    for (position, &term_idx) in linkage.order.iter().enumerate() {
+        positions[term_idx] = (position as f64, 0.0);
+    }
+
+    linkage.merges
+        .iter()
+        .enumerate()
+        .map(|(k, merge)| {
+            let (left_x, left_height) = positions[merge.left];
+            let (right_x, right_height) = positions[merge.right];
+            positions[n + k] = ((left_x + right_x) / 2.0, merge.distance);
+
+            (
+                vec![left_x, left_x, right_x, right_x],
+                vec![left_height, merge.distance, merge.distance, right_height]
+            )
+        })
+        .collect()
+}
+
+pub fn write_similarity_heatmap_html(
+    term_pairs: &[TermPair],
+    go_terms: &FxHashSet<GOTermID>,
+    taxon_id: TaxonID,
+    method: Method,
+    output_dir: &str,
+) -> Result<String, String> {
+    let output_path = Path::new(output_dir);
+    fs::create_dir_all(output_path)
+        .map_err(|e| format!("Failed to create output directory {}: {}", output_dir, e))?;
+
+    let method_filename_part = format!("{}", method)
+        .to_lowercase()
+        .replace(|c: char| !c.is_alphanumeric() && c != '_', "_")
+        .replace('-', "_");
+
+    let filename = format!(
+        "{}/similarity_{}_taxon_{}_heatmap.html",
+        output_dir, method_filename_part, taxon_id
+    );
+
+    let mut sorted_go_ids: Vec<GOTermID> = go_terms.iter().cloned().collect();
+    sorted_go_ids.sort_unstable();
+
+    let matrix = similarity_matrix(term_pairs, &sorted_go_ids);
+    let linkage = average_linkage(&matrix);
+    let order = &linkage.order;
+
+    let labels: Vec<String> = order
+        .iter()
+        .map(|&i| format!("GO:{:07}", sorted_go_ids[i]))
+        .collect();
+
+    let ordered_matrix: Vec<Vec<f64>> = order
+        .iter()
+        .map(|&i| order.iter().map(|&j| matrix[i][j]).collect())
+        .collect();
+
+    let max_similarity = ordered_matrix
+        .iter()
+        .flatten()
+        .cloned()
+        .fold(1.0_f64, f64::max);
+
+    let trace = HeatMap::new(labels.clone(), labels, ordered_matrix)
+        .color_scale(ColorScale::Vector(vec![
+            ColorScaleElement(0.0, "rgb(255,255,255)".to_string()),
+            ColorScaleElement(1.0, "rgb(8,48,107)".to_string()),
+        ]))
+        .zmin(0.0)
+        .zmax(max_similarity)
+        .hover_template("%{y} - %{x}<br>Similarity: %{z:.4f}<extra></extra>");
+
+    // The dendrogram sits above the heatmap on its own axes, whose x range lines up with the heatmap columns.
+    let layout = Layout::new()
+        .title(Title::with_text(format!("{} semantic similarity for taxon {}", method, taxon_id)))
+        .x_axis(Axis::new().tick_angle(-45.0).auto_margin(true))
+        .y_axis(Axis::new().auto_margin(true).domain(&[0.0, 0.8]))
+        .x_axis2(Axis::new().anchor("y2").range(vec![-0.5, order.len() as f64 - 0.5]).visible(false))
+        .y_axis2(Axis::new().anchor("x2").domain(&[0.82, 1.0]).visible(false))
+        .margin(Margin::new().left(120).bottom(120))
+        .width(900)
+        .height(1000);
+
+    let mut plot = Plot::new();
+    plot.add_trace(trace);
+    for (x, y) in dendrogram_lines(&linkage) {
+        plot.add_trace(
+            Scatter::new(x, y)
+                .mode(Mode::Lines)
+                .line(Line::new().color("rgb(68,68,68)").width(1.0))
+                .x_axis("x2")
+                .y_axis("y2")
+                .hover_info(HoverInfo::Skip)
+                .show_legend(false)
+        );
+    }
+    plot.set_layout(layout);
+    plot.write_html(&filename);
+
+    println!("Successfully wrote similarity heatmap to {}", filename);
+    Ok(filename)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn average_linkage_order_skips_undefined_similarities() {
+        // GO terms 0 and 1 are merged first. Term 2 is similar to term 0 and undefined for term 1,
+        // so it joins their cluster before the unrelated term 3 does.
+        let nan = f64::NAN;
+        let matrix = vec![
+            vec![1.0, 0.95, 0.9, 0.1],
+            vec![0.95, 1.0, nan, 0.1],
+            vec![0.9, nan, 1.0, 0.05],
+            vec![0.1, 0.1, 0.05, 1.0],
+        ];
+
+        assert_eq!(average_linkage(&matrix).order, vec![0, 1, 2, 3]);
+    }
+
+    // Terms 0 and 2 and terms 1 and 3 are similar pairs with little similarity between the pairs.
+    fn two_pairs_matrix() -> Vec<Vec<f64>> {
+        vec![
+            vec![1.0, 0.1, 0.9, 0.1],
+            vec![0.1, 1.0, 0.1, 0.8],
+            vec![0.9, 0.1, 1.0, 0.1],
+            vec![0.1, 0.8, 0.1, 1.0],
+        ]
+    }
+
+    #[test]
+    fn average_linkage_merges_the_closest_clusters_first() {
+        let linkage = average_linkage(&two_pairs_matrix());
+
+        assert_eq!(linkage.order, vec![0, 2, 1, 3]);
+
+        let merged: Vec<(usize, usize)> = linkage.merges.iter().map(|merge| (merge.left, merge.right)).collect();
+        assert_eq!(merged, vec![(0, 2), (1, 3), (4, 5)]);
+
+        let distances: Vec<f64> = linkage.merges.iter().map(|merge| merge.distance).collect();
+        for (distance, expected) in distances.iter().zip([0.1, 0.2, 0.9]) {
+            assert!((distance - expected).abs() < 1e-12, "distances {:?}", distances);
+        }
+    }
+
+    #[test]
+    fn dendrogram_joins_each_merge_above_its_clusters() {
+        let lines = dendrogram_lines(&average_linkage(&two_pairs_matrix()));
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].0, vec![0.0, 0.0, 1.0, 1.0]);
+        assert_eq!(lines[1].0, vec![2.0, 2.0, 3.0, 3.0]);
+        // The last merge joins the middle of the two pairs at the height of their own merges.
+        let (top_x, top_y) = &lines[2];
+        assert_eq!(top_x, &vec![0.5, 0.5, 2.5, 2.5]);
+        assert!((top_y[0] - 0.1).abs() < 1e-12 && (top_y[3] - 0.2).abs() < 1e-12);
+        assert!((top_y[1] - 0.9).abs() < 1e-12);
+    }
+
+    #[test]
+    fn lin_similarity_for_every_zero_ic_behavior() {
+        let cases = [
+            (ZeroIcBehavior::ReturnZero, 0.0),
+            (ZeroIcBehavior::ReturnOne, 1.0),
+            (ZeroIcBehavior::ReturnNaN, f64::NAN),
+        ];
+
+        for (zero_ic_behavior, expected) in cases {
+            let lin = LinSimilarity::new(zero_ic_behavior);
+
+            let undefined = lin.similarity(0.0, 0.0, 0.0);
+            if expected.is_nan() {
+                assert!(undefined.is_nan(), "{:?} gave {}", zero_ic_behavior, undefined);
+            } else {
+                assert_eq!(undefined, expected, "{:?}", zero_ic_behavior);
+            }
+
+            // Terms with a non-zero Information Content are unaffected by the behavior.
+            assert!((lin.similarity(2.0, 4.0, 1.5) - 0.5).abs() < 1e-12, "{:?}", zero_ic_behavior);
+        }
+    }
+
+    #[test]
+    fn term_pairs_keep_nan_only_for_lin() {
+        for zero_ic_behavior in [ZeroIcBehavior::ReturnZero, ZeroIcBehavior::ReturnOne, ZeroIcBehavior::ReturnNaN] {
+            let lin = TermPair::new_for_ic(1, 2, 0.0, 0.0, (3, 0.0), Method::Lin, zero_ic_behavior);
+            let expected = LinSimilarity::new(zero_ic_behavior).similarity(0.0, 0.0, 0.0);
+            assert!(lin.similarity == expected || (lin.similarity.is_nan() && expected.is_nan()));
+
+            let resnik = TermPair::new_for_ic(1, 2, 0.0, 0.0, (3, 0.0), Method::Resnik, zero_ic_behavior);
+            assert_eq!(resnik.similarity, 0.0);
+        }
+    }
+}